@@ -0,0 +1,29 @@
+defmodule Cusip do
+  @moduledoc """
+  Support for creating and validating CUSIPs, backed by the `cusip` Rust crate.
+
+  Functions that can reject their input return `{:ok, value}` or `{:error, message}`.
+  """
+
+  @doc "Parses a CUSIP, requiring the exact canonical form."
+  defdelegate parse(value), to: Cusip.Native
+
+  @doc "Parses a CUSIP, allowing surrounding whitespace and lowercase letters."
+  defdelegate parse_loose(value), to: Cusip.Native
+
+  @doc "Tests whether or not the string is a valid CUSIP."
+  defdelegate validate(value), to: Cusip.Native
+
+  @doc "Computes the Check Digit for an 8-character Payload."
+  defdelegate compute_check_digit(payload), to: Cusip.Native
+end
+
+defmodule Cusip.Native do
+  @moduledoc false
+  use Rustler, otp_app: :cusip, crate: "cusip_nif", path: "."
+
+  def parse(_value), do: :erlang.nif_error(:nif_not_loaded)
+  def parse_loose(_value), do: :erlang.nif_error(:nif_not_loaded)
+  def validate(_value), do: :erlang.nif_error(:nif_not_loaded)
+  def compute_check_digit(_payload), do: :erlang.nif_error(:nif_not_loaded)
+end
@@ -0,0 +1,22 @@
+defmodule Cusip.MixProject do
+  use Mix.Project
+
+  def project do
+    [
+      app: :cusip,
+      version: "0.3.0",
+      elixir: "~> 1.14",
+      description: "Support for creating and validating CUSIPs, backed by the cusip Rust crate",
+      package: [licenses: ["MIT", "Apache-2.0"]],
+      deps: deps()
+    ]
+  end
+
+  def application do
+    []
+  end
+
+  defp deps do
+    [{:rustler, "~> 0.37", runtime: false}]
+  end
+end
@@ -0,0 +1,44 @@
+//! # cusip-elixir
+//!
+//! Erlang and Elixir bindings for the `cusip` crate, built as a NIF with
+//! [Rustler](https://github.com/rusterlium/rustler). This lets BEAM services use the same parsing,
+//! validation and _Check Digit_ computation as Rust code, rather than calling out to another
+//! process.
+//!
+//! The NIFs are loaded into the `Cusip.Native` module, which the `Cusip` module in `lib/` wraps.
+//! Build and test with `mix test` from this directory. Functions that can reject their input
+//! return `{:ok, value}` or `{:error, message}`, where the message is the `Display` form of the
+//! underlying `CUSIPError`.
+
+/// Parses a CUSIP, requiring the exact canonical form, and returns it.
+#[rustler::nif]
+fn parse(value: &str) -> Result<String, String> {
+    cusip::CUSIP::parse(value)
+        .map(|cusip| cusip.to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Parses a CUSIP, allowing leading or trailing whitespace and lowercase letters, and returns its
+/// canonical form.
+#[rustler::nif]
+fn parse_loose(value: &str) -> Result<String, String> {
+    cusip::CUSIP::parse_loose(value)
+        .map(|cusip| cusip.to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Tests whether or not the string is a valid CUSIP.
+#[rustler::nif]
+fn validate(value: &str) -> bool {
+    cusip::validate(value)
+}
+
+/// Computes the _Check Digit_ for an 8-character _Payload_.
+#[rustler::nif]
+fn compute_check_digit(payload: &str) -> Result<String, String> {
+    cusip::build_from_payload(payload)
+        .map(|cusip| cusip.check_digit().to_string())
+        .map_err(|err| err.to_string())
+}
+
+rustler::init!("Elixir.Cusip.Native");
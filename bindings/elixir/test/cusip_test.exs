@@ -0,0 +1,23 @@
+defmodule CusipTest do
+  use ExUnit.Case
+
+  test "parse" do
+    assert Cusip.parse("S08000AA9") == {:ok, "S08000AA9"}
+    assert {:error, message} = Cusip.parse("09739d100")
+    assert message =~ "Issuer Number"
+  end
+
+  test "parse_loose" do
+    assert Cusip.parse_loose(" 09739d100 ") == {:ok, "09739D100"}
+  end
+
+  test "validate" do
+    assert Cusip.validate("037833100")
+    refute Cusip.validate("037833101")
+  end
+
+  test "compute_check_digit" do
+    assert Cusip.compute_check_digit("03783310") == {:ok, "0"}
+    assert {:error, _} = Cusip.compute_check_digit("0378331")
+  end
+end
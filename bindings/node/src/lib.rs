@@ -0,0 +1,96 @@
+//! # cusip-node
+//!
+//! Node.js bindings for the `cusip` crate, built with [napi-rs](https://napi.rs/). This lets
+//! JavaScript and TypeScript services use the same parsing, validation and _Check Digit_
+//! computation as Rust code, rather than reimplementing them.
+//!
+//! Build the native module with `npm run build` from this directory. Functions throw an `Error`
+//! whose message is the `Display` form of the underlying `CUSIPError` when the input is rejected.
+
+use napi::{Error, Result};
+use napi_derive::napi;
+
+/// The parts of a validated CUSIP, as returned by `parse()` and `parseLoose()`.
+#[napi(object)]
+pub struct Cusip {
+    /// The full 9-character CUSIP.
+    pub cusip: String,
+    /// The 6-character _Issuer Number_.
+    pub issuer_num: String,
+    /// The 2-character _Issue Number_.
+    pub issue_num: String,
+    /// The _Check Digit_.
+    pub check_digit: String,
+    /// Whether the CUSIP is a CINS identifier.
+    pub is_cins: bool,
+    /// The CINS _Country Code_, if the CUSIP is a CINS identifier.
+    pub cins_country_code: Option<String>,
+    /// Whether the CUSIP is reserved for private use.
+    pub is_private_use: bool,
+}
+
+impl From<cusip::CUSIP> for Cusip {
+    fn from(cusip: cusip::CUSIP) -> Self {
+        Cusip {
+            cusip: cusip.to_string(),
+            issuer_num: cusip.issuer_num().to_string(),
+            issue_num: cusip.issue_num().to_string(),
+            check_digit: cusip.check_digit().to_string(),
+            is_cins: cusip.is_cins(),
+            cins_country_code: cusip.as_cins().map(|cins| cins.country_code().to_string()),
+            is_private_use: cusip.is_private_use(),
+        }
+    }
+}
+
+fn to_napi_error(err: cusip::CUSIPError) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+/// Parse a string to a valid CUSIP, requiring the exact canonical form.
+#[napi]
+pub fn parse(value: String) -> Result<Cusip> {
+    cusip::CUSIP::parse(&value)
+        .map(Cusip::from)
+        .map_err(to_napi_error)
+}
+
+/// Parse a string to a valid CUSIP, allowing leading or trailing whitespace and lowercase
+/// letters.
+#[napi]
+pub fn parse_loose(value: String) -> Result<Cusip> {
+    cusip::CUSIP::parse_loose(&value)
+        .map(Cusip::from)
+        .map_err(to_napi_error)
+}
+
+/// Test whether or not the string is a valid CUSIP.
+#[napi]
+pub fn validate(value: String) -> bool {
+    cusip::validate(&value)
+}
+
+/// Build a CUSIP string from an 8-character _Payload_, computing the _Check Digit_.
+#[napi]
+pub fn build_from_payload(payload: String) -> Result<String> {
+    cusip::build_from_payload(&payload)
+        .map(|cusip| cusip.to_string())
+        .map_err(to_napi_error)
+}
+
+/// Build a CUSIP string from an _Issuer Number_ and an _Issue Number_, computing the _Check
+/// Digit_.
+#[napi]
+pub fn build_from_parts(issuer_num: String, issue_num: String) -> Result<String> {
+    cusip::build_from_parts(&issuer_num, &issue_num)
+        .map(|cusip| cusip.to_string())
+        .map_err(to_napi_error)
+}
+
+/// Compute the _Check Digit_ for an 8-character _Payload_.
+#[napi]
+pub fn compute_check_digit(payload: String) -> Result<String> {
+    cusip::build_from_payload(&payload)
+        .map(|cusip| cusip.check_digit().to_string())
+        .map_err(to_napi_error)
+}
@@ -0,0 +1,20 @@
+const assert = require("assert");
+const cusip = require("./index.js");
+
+assert.strictEqual(cusip.validate("037833100"), true);
+assert.strictEqual(cusip.validate("037833101"), false);
+
+const parsed = cusip.parse("S08000AA9");
+assert.strictEqual(parsed.issuerNum, "S08000");
+assert.strictEqual(parsed.issueNum, "AA");
+assert.strictEqual(parsed.isCins, true);
+assert.strictEqual(parsed.cinsCountryCode, "S");
+
+assert.strictEqual(cusip.parseLoose(" 09739d100 ").cusip, "09739D100");
+assert.throws(() => cusip.parse("09739d100"), /Issuer Number/);
+
+assert.strictEqual(cusip.buildFromPayload("03783310"), "037833100");
+assert.strictEqual(cusip.buildFromParts("037833", "10"), "037833100");
+assert.strictEqual(cusip.computeCheckDigit("03783310"), "0");
+
+console.log("ok");
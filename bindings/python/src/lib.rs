@@ -0,0 +1,135 @@
+//! # cusip-python
+//!
+//! Python bindings for the `cusip` crate, built with [PyO3](https://pyo3.rs/). This lets Python
+//! code use the same parsing, validation and _Check Digit_ computation as Rust code, rather than a
+//! separate pure-Python implementation.
+//!
+//! Build and install the `cusip` Python module into the active environment with
+//! `maturin develop` from this directory. Rejected input raises `ValueError` with the `Display`
+//! form of the underlying `CUSIPError` as its message.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn to_py_err(err: cusip::CUSIPError) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// A CUSIP in confirmed valid format.
+#[pyclass(name = "Cusip", module = "cusip", frozen, eq, ord, hash, str)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Cusip(cusip::CUSIP);
+
+#[pymethods]
+impl Cusip {
+    /// Parse a string to a valid CUSIP, requiring the exact canonical form.
+    #[new]
+    fn new(value: &str) -> PyResult<Self> {
+        Self::parse(value)
+    }
+
+    /// Parse a string to a valid CUSIP, requiring the exact canonical form.
+    #[staticmethod]
+    fn parse(value: &str) -> PyResult<Self> {
+        cusip::CUSIP::parse(value).map(Cusip).map_err(to_py_err)
+    }
+
+    /// Parse a string to a valid CUSIP, allowing leading or trailing whitespace and lowercase
+    /// letters.
+    #[staticmethod]
+    fn parse_loose(value: &str) -> PyResult<Self> {
+        cusip::CUSIP::parse_loose(value)
+            .map(Cusip)
+            .map_err(to_py_err)
+    }
+
+    /// Build a CUSIP from an 8-character Payload, computing the Check Digit.
+    #[staticmethod]
+    fn build_from_payload(payload: &str) -> PyResult<Self> {
+        cusip::build_from_payload(payload)
+            .map(Cusip)
+            .map_err(to_py_err)
+    }
+
+    /// Build a CUSIP from an Issuer Number and an Issue Number, computing the Check Digit.
+    #[staticmethod]
+    fn build_from_parts(issuer_num: &str, issue_num: &str) -> PyResult<Self> {
+        cusip::build_from_parts(issuer_num, issue_num)
+            .map(Cusip)
+            .map_err(to_py_err)
+    }
+
+    /// The 6-character Issuer Number.
+    #[getter]
+    fn issuer_num(&self) -> String {
+        self.0.issuer_num().to_string()
+    }
+
+    /// The 2-character Issue Number.
+    #[getter]
+    fn issue_num(&self) -> String {
+        self.0.issue_num().to_string()
+    }
+
+    /// The Payload (everything except the Check Digit).
+    #[getter]
+    fn payload(&self) -> String {
+        self.0.payload().to_string()
+    }
+
+    /// The Check Digit.
+    #[getter]
+    fn check_digit(&self) -> char {
+        self.0.check_digit()
+    }
+
+    /// Whether this CUSIP is a CINS identifier.
+    #[getter]
+    fn is_cins(&self) -> bool {
+        self.0.is_cins()
+    }
+
+    /// The CINS Country Code, or None if this CUSIP is not a CINS identifier.
+    #[getter]
+    fn cins_country_code(&self) -> Option<char> {
+        self.0.as_cins().map(|cins| cins.country_code())
+    }
+
+    /// Whether this CUSIP is reserved for private use.
+    #[getter]
+    fn is_private_use(&self) -> bool {
+        self.0.is_private_use()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Cusip('{}')", self.0)
+    }
+}
+
+impl std::fmt::Display for Cusip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Test whether or not the string is a valid CUSIP.
+#[pyfunction]
+fn validate(value: &str) -> bool {
+    cusip::validate(value)
+}
+
+/// Compute the Check Digit for an 8-character Payload.
+#[pyfunction]
+fn compute_check_digit(payload: &str) -> PyResult<char> {
+    cusip::build_from_payload(payload)
+        .map(|cusip| cusip.check_digit())
+        .map_err(to_py_err)
+}
+
+#[pymodule(name = "cusip")]
+fn cusip_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Cusip>()?;
+    m.add_function(wrap_pyfunction!(validate, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_check_digit, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+import pytest
+
+import cusip
+
+
+def test_parse():
+    c = cusip.Cusip.parse("S08000AA9")
+    assert str(c) == "S08000AA9"
+    assert repr(c) == "Cusip('S08000AA9')"
+    assert c.issuer_num == "S08000"
+    assert c.issue_num == "AA"
+    assert c.check_digit == "9"
+    assert c.is_cins
+    assert c.cins_country_code == "S"
+
+
+def test_parse_loose():
+    assert cusip.Cusip.parse_loose(" 09739d100 ") == cusip.Cusip("09739D100")
+
+
+def test_rejects_invalid():
+    with pytest.raises(ValueError, match="incorrect Check Digit"):
+        cusip.Cusip("037833101")
+
+
+def test_validate():
+    assert cusip.validate("037833100")
+    assert not cusip.validate("037833101")
+
+
+def test_build():
+    assert str(cusip.Cusip.build_from_payload("03783310")) == "037833100"
+    assert str(cusip.Cusip.build_from_parts("037833", "10")) == "037833100"
+    assert cusip.compute_check_digit("03783310") == "0"
+
+
+def test_hash_and_order():
+    a = cusip.Cusip("037833100")
+    b = cusip.Cusip("09739D100")
+    assert a < b
+    assert len({a, cusip.Cusip("037833100")}) == 1
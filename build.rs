@@ -0,0 +1,14 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        // Compile with protox rather than protoc so the build doesn't depend on an installed tool
+        println!("cargo:rerun-if-changed=proto/cusip.proto");
+        let fds = protox::compile(["cusip.proto"], ["proto"]).expect("compiling proto/cusip.proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_fds(fds)
+            .expect("generating gRPC service code");
+    }
+}
@@ -0,0 +1,6 @@
+use cusip::vectors::{render_json, test_vectors};
+
+fn main() {
+    // Writes the conformance test vectors as JSON, for checking other implementations
+    print!("{}", render_json(&test_vectors()));
+}
@@ -0,0 +1,301 @@
+#![warn(missing_docs)]
+//! # cusip::analytics
+//!
+//! Distribution statistics over sets of CUSIPs, for data-quality checks.
+//!
+//! Across any sizable set of genuine CUSIPs, the _Check Digit_ values are close to uniformly
+//! distributed. Fabricated identifiers, or identifiers whose last character was truncated and
+//! regenerated by some other process, tend to skew that distribution. `DigitDistribution` counts
+//! the _Check Digits_ (and the leading characters, for context) and applies Pearson's chi-squared
+//! test against the uniform distribution to flag sets that are implausibly skewed.
+//!
+//! `CusipCounter` tallies occurrences of each CUSIP and each _Issuer Number_ across a stream,
+//! answering top-N queries for concentration reports and for diagnosing feeds heavy with
+//! duplicates.
+//!
+//! ```
+//! use cusip::analytics::DigitDistribution;
+//! use cusip::build_from_parts;
+//!
+//! let mut dist = DigitDistribution::new();
+//! for n in 0..1000 {
+//!     dist.add(&build_from_parts(&format!("{n:06}"), "10").unwrap());
+//! }
+//! assert_eq!(dist.total(), 1000);
+//! assert!(!dist.is_implausible());
+//! ```
+//!
+//! ```
+//! use cusip::analytics::CusipCounter;
+//! use cusip::payload::IssuerNum;
+//! use cusip::CUSIP;
+//!
+//! let feed: Vec<CUSIP> = ["037833100", "037833100", "594918104", "037833209"]
+//!     .iter()
+//!     .map(|s| CUSIP::parse(s).unwrap())
+//!     .collect();
+//! let counter: CusipCounter = feed.iter().collect();
+//!
+//! assert_eq!(counter.top_n(1), [(CUSIP::parse("037833100").unwrap(), 2)]);
+//! assert_eq!(
+//!     counter.top_issuers(1),
+//!     [(IssuerNum::parse("037833").unwrap(), 3)]
+//! );
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::payload::IssuerNum;
+use crate::CUSIP;
+
+/// The critical value of the chi-squared distribution with 9 degrees of freedom at a
+/// significance level of 0.001.
+const CHI_SQUARED_CRITICAL_9DF_P001: f64 = 27.877;
+
+/// The smallest number of CUSIPs for which the chi-squared test is meaningful (an expected count
+/// of at least 5 per _Check Digit_).
+pub const MIN_SAMPLE_SIZE: u64 = 50;
+
+/// Counts of _Check Digits_ and leading characters over a set of CUSIPs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DigitDistribution {
+    check_digits: [u64; 10],
+    leading: BTreeMap<char, u64>,
+    total: u64,
+}
+
+impl DigitDistribution {
+    /// Constructs an empty distribution.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the CUSIP.
+    pub fn add(&mut self, cusip: &CUSIP) {
+        let check_digit = cusip.check_digit();
+        self.check_digits[(check_digit as u8 - b'0') as usize] += 1;
+        let lead = cusip.issuer_num().as_bytes()[0] as char;
+        *self.leading.entry(lead).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Returns the number of CUSIPs counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of CUSIPs counted with each _Check Digit_, indexed by digit.
+    pub fn check_digit_counts(&self) -> &[u64; 10] {
+        &self.check_digits
+    }
+
+    /// Returns the number of CUSIPs counted with each leading character.
+    pub fn leading_char_counts(&self) -> &BTreeMap<char, u64> {
+        &self.leading
+    }
+
+    /// Returns Pearson's chi-squared statistic comparing the _Check Digit_ counts with a uniform
+    /// distribution, or `None` if no CUSIPs have been counted.
+    pub fn chi_squared(&self) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        let expected = self.total as f64 / 10.0;
+        Some(
+            self.check_digits
+                .iter()
+                .map(|&observed| {
+                    let diff = observed as f64 - expected;
+                    diff * diff / expected
+                })
+                .sum(),
+        )
+    }
+
+    /// Returns true if the _Check Digit_ distribution is too skewed to plausibly come from genuine
+    /// CUSIPs (the chi-squared test rejects uniformity at a significance level of 0.001). Always
+    /// returns false for fewer than `MIN_SAMPLE_SIZE` CUSIPs, where the test is not meaningful.
+    pub fn is_implausible(&self) -> bool {
+        self.total >= MIN_SAMPLE_SIZE
+            && self
+                .chi_squared()
+                .is_some_and(|chi2| chi2 > CHI_SQUARED_CRITICAL_9DF_P001)
+    }
+}
+
+impl<'a> Extend<&'a CUSIP> for DigitDistribution {
+    fn extend<I: IntoIterator<Item = &'a CUSIP>>(&mut self, iter: I) {
+        for cusip in iter {
+            self.add(cusip);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a CUSIP> for DigitDistribution {
+    fn from_iter<I: IntoIterator<Item = &'a CUSIP>>(iter: I) -> Self {
+        let mut dist = Self::new();
+        dist.extend(iter);
+        dist
+    }
+}
+
+/// Occurrence counts per CUSIP and per _Issuer Number_.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CusipCounter {
+    cusips: HashMap<CUSIP, u64>,
+    issuers: HashMap<IssuerNum, u64>,
+    total: u64,
+}
+
+impl CusipCounter {
+    /// Constructs an empty counter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts an occurrence of the CUSIP.
+    pub fn add(&mut self, cusip: &CUSIP) {
+        *self.cusips.entry(*cusip).or_insert(0) += 1;
+        *self.issuers.entry(cusip.issuer()).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Returns the number of occurrences counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of distinct CUSIPs counted.
+    pub fn distinct(&self) -> usize {
+        self.cusips.len()
+    }
+
+    /// Returns the number of distinct _Issuer Numbers_ counted.
+    pub fn distinct_issuers(&self) -> usize {
+        self.issuers.len()
+    }
+
+    /// Returns the number of occurrences of the CUSIP.
+    pub fn count(&self, cusip: &CUSIP) -> u64 {
+        self.cusips.get(cusip).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of occurrences of CUSIPs with the _Issuer Number_.
+    pub fn issuer_count(&self, issuer_num: &IssuerNum) -> u64 {
+        self.issuers.get(issuer_num).copied().unwrap_or(0)
+    }
+
+    /// Returns the `n` most frequent CUSIPs with their counts, most frequent first. Ties are
+    /// ordered by CUSIP.
+    pub fn top_n(&self, n: usize) -> Vec<(CUSIP, u64)> {
+        top(self.cusips.iter().map(|(c, n)| (*c, *n)), n)
+    }
+
+    /// Returns the `n` most frequent _Issuer Numbers_ with their counts, most frequent first. Ties
+    /// are ordered by _Issuer Number_.
+    pub fn top_issuers(&self, n: usize) -> Vec<(IssuerNum, u64)> {
+        top(self.issuers.iter().map(|(i, n)| (*i, *n)), n)
+    }
+
+    /// Returns the CUSIPs that occurred more than once with their counts, most frequent first.
+    pub fn duplicates(&self) -> Vec<(CUSIP, u64)> {
+        let mut dups: Vec<_> = self.top_n(self.cusips.len());
+        dups.retain(|(_, n)| *n > 1);
+        dups
+    }
+}
+
+fn top<K: Ord>(counts: impl Iterator<Item = (K, u64)>, n: usize) -> Vec<(K, u64)> {
+    let mut counts: Vec<_> = counts.collect();
+    counts.sort_unstable_by(|(ka, na), (kb, nb)| (Reverse(na), ka).cmp(&(Reverse(nb), kb)));
+    counts.truncate(n);
+    counts
+}
+
+impl<'a> Extend<&'a CUSIP> for CusipCounter {
+    fn extend<I: IntoIterator<Item = &'a CUSIP>>(&mut self, iter: I) {
+        for cusip in iter {
+            self.add(cusip);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a CUSIP> for CusipCounter {
+    fn from_iter<I: IntoIterator<Item = &'a CUSIP>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_from_parts;
+
+    #[test]
+    fn counts() {
+        let cusips: Vec<CUSIP> = ["037833100", "S08000AA9", "09739D100"]
+            .iter()
+            .map(|s| CUSIP::parse(s).unwrap())
+            .collect();
+        let dist: DigitDistribution = cusips.iter().collect();
+        assert_eq!(dist.check_digit_counts(), &[2, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(dist.leading_char_counts()[&'0'], 2);
+        assert_eq!(dist.leading_char_counts()[&'S'], 1);
+        assert!(!dist.is_implausible(), "too few to judge");
+    }
+
+    #[test]
+    fn flags_skew() {
+        // Only keep the CUSIPs whose check digit is even, as a biased generator might
+        let cusips: Vec<CUSIP> = (0..2000)
+            .map(|n| build_from_parts(&format!("{n:06}"), "10").unwrap())
+            .filter(|c| matches!(c.check_digit(), '0' | '2' | '4' | '6' | '8'))
+            .collect();
+        let dist: DigitDistribution = cusips.iter().collect();
+        assert!(dist.total() >= MIN_SAMPLE_SIZE);
+        assert!(dist.is_implausible());
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(DigitDistribution::new().chi_squared(), None);
+    }
+
+    #[test]
+    fn counter() {
+        let cusips: Vec<CUSIP> = [
+            "037833100",
+            "594918104",
+            "037833100",
+            "594918104",
+            "037833209",
+            "S08000AA9",
+        ]
+        .iter()
+        .map(|s| CUSIP::parse(s).unwrap())
+        .collect();
+        let counter: CusipCounter = cusips.iter().collect();
+
+        assert_eq!(counter.total(), 6);
+        assert_eq!(counter.distinct(), 4);
+        assert_eq!(counter.distinct_issuers(), 3);
+        assert_eq!(counter.count(&cusips[0]), 2);
+        let issuer = |s| IssuerNum::parse(s).unwrap();
+        assert_eq!(counter.issuer_count(&issuer("037833")), 3);
+        assert_eq!(counter.issuer_count(&issuer("999999")), 0);
+
+        assert_eq!(counter.top_n(2), [(cusips[0], 2), (cusips[1], 2)]);
+        assert_eq!(counter.duplicates().len(), 2);
+        assert_eq!(
+            counter.top_issuers(10),
+            [
+                (issuer("037833"), 3),
+                (issuer("594918"), 2),
+                (issuer("S08000"), 1)
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+#![warn(missing_docs)]
+//! # cusip::array_set
+//!
+//! A fixed-capacity set of CUSIPs that never allocates, for `no_std` and embedded targets.
+//!
+//! `CusipArraySet<N>` stores up to `N` CUSIPs inline, in sorted order, so membership tests are a
+//! binary search and iteration is in ascending order. Inserting into a full set fails rather than
+//! growing.
+//!
+//! ```
+//! use cusip::array_set::CusipArraySet;
+//! use cusip::CUSIP;
+//!
+//! let apple = CUSIP::parse("037833100").unwrap();
+//! let microsoft = CUSIP::parse("594918104").unwrap();
+//!
+//! let mut set = CusipArraySet::<1>::new();
+//! assert_eq!(set.insert(apple), Ok(true));
+//! assert_eq!(set.insert(apple), Ok(false));
+//! assert!(set.insert(microsoft).is_err());
+//!
+//! assert!(set.contains(&apple));
+//! assert!(!set.contains(&microsoft));
+//! ```
+
+use core::fmt;
+
+use crate::CUSIP;
+
+/// Fills the unused slots of a `CusipArraySet`. It is never visible through the public API.
+const FILLER: CUSIP = CUSIP(*b"000000000");
+
+/// A set of at most `N` CUSIPs, stored inline in sorted order.
+#[derive(Clone, Copy)]
+pub struct CusipArraySet<const N: usize> {
+    items: [CUSIP; N],
+    len: usize,
+}
+
+/// The error returned when inserting into a full `CusipArraySet`. It gives back the CUSIP that
+/// could not be inserted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError(pub CUSIP);
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no room to insert {} into a full set", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+impl<const N: usize> CusipArraySet<N> {
+    /// Constructs an empty set.
+    pub const fn new() -> Self {
+        CusipArraySet {
+            items: [FILLER; N],
+            len: 0,
+        }
+    }
+
+    /// Returns the maximum number of CUSIPs the set can hold.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of CUSIPs in the set.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the set holds no CUSIPs.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the set holds as many CUSIPs as it can.
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the CUSIPs in the set, in ascending order.
+    pub fn as_slice(&self) -> &[CUSIP] {
+        &self.items[..self.len]
+    }
+
+    /// Returns an iterator over the CUSIPs in the set, in ascending order.
+    pub fn iter(&self) -> core::slice::Iter<'_, CUSIP> {
+        self.as_slice().iter()
+    }
+
+    /// Returns true if the set contains the CUSIP.
+    pub fn contains(&self, cusip: &CUSIP) -> bool {
+        self.as_slice().binary_search(cusip).is_ok()
+    }
+
+    /// Adds the CUSIP to the set. Returns `Ok(true)` if it was added, `Ok(false)` if it was
+    /// already present, or an error if it was not present and the set is full.
+    pub fn insert(&mut self, cusip: CUSIP) -> Result<bool, CapacityError> {
+        match self.as_slice().binary_search(&cusip) {
+            Ok(_) => Ok(false),
+            Err(_) if self.is_full() => Err(CapacityError(cusip)),
+            Err(index) => {
+                self.items.copy_within(index..self.len, index + 1);
+                self.items[index] = cusip;
+                self.len += 1;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Removes the CUSIP from the set, returning true if it was present.
+    pub fn remove(&mut self, cusip: &CUSIP) -> bool {
+        match self.as_slice().binary_search(cusip) {
+            Ok(index) => {
+                self.items.copy_within(index + 1..self.len, index);
+                self.len -= 1;
+                self.items[self.len] = FILLER;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Removes all the CUSIPs from the set.
+    pub fn clear(&mut self) {
+        self.items = [FILLER; N];
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for CusipArraySet<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for CusipArraySet<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize, const M: usize> PartialEq<CusipArraySet<M>> for CusipArraySet<N> {
+    fn eq(&self, other: &CusipArraySet<M>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<const N: usize> Eq for CusipArraySet<N> {}
+
+impl<'a, const N: usize> IntoIterator for &'a CusipArraySet<N> {
+    type Item = &'a CUSIP;
+    type IntoIter = core::slice::Iter<'a, CUSIP>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cusip(s: &str) -> CUSIP {
+        CUSIP::parse(s).unwrap()
+    }
+
+    #[test]
+    fn sorted_insert_and_remove() {
+        let mut set = CusipArraySet::<4>::new();
+        for s in ["S08000AA9", "037833100", "594918104"] {
+            assert_eq!(set.insert(cusip(s)), Ok(true));
+        }
+        assert_eq!(
+            set.as_slice(),
+            [cusip("037833100"), cusip("594918104"), cusip("S08000AA9")]
+        );
+
+        assert!(set.remove(&cusip("594918104")));
+        assert!(!set.remove(&cusip("594918104")));
+        assert_eq!(set.as_slice(), [cusip("037833100"), cusip("S08000AA9")]);
+    }
+
+    #[test]
+    fn full_set_rejects_new_members() {
+        let mut set = CusipArraySet::<2>::new();
+        set.insert(cusip("037833100")).unwrap();
+        set.insert(cusip("594918104")).unwrap();
+        assert!(set.is_full());
+        assert_eq!(set.insert(cusip("037833100")), Ok(false));
+        assert_eq!(
+            set.insert(cusip("S08000AA9")),
+            Err(CapacityError(cusip("S08000AA9")))
+        );
+
+        set.clear();
+        assert!(set.is_empty());
+        assert!(!set.contains(&cusip("037833100")));
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut set = CusipArraySet::<0>::default();
+        assert!(set.insert(cusip("037833100")).is_err());
+        assert_eq!(set, CusipArraySet::<3>::new());
+    }
+}
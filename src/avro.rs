@@ -0,0 +1,146 @@
+#![warn(missing_docs)]
+//! # cusip::avro
+//!
+//! Support for carrying CUSIPs in [Apache Avro](https://avro.apache.org/) records (requires the
+//! `avro` feature).
+//!
+//! A CUSIP is encoded as an Avro `string` holding the 9-character identifier, annotated with the
+//! logical type `cusip`. Readers that do not know the logical type simply see a `string`, so
+//! existing schemas that already carry CUSIPs as plain strings remain compatible.
+//!
+//! ```
+//! use apache_avro::types::Value;
+//! use cusip::CUSIP;
+//!
+//! let cusip = CUSIP::parse("037833100").unwrap();
+//! let value = Value::from(cusip);
+//! assert_eq!(value, Value::String("037833100".to_string()));
+//!
+//! let round_trip = CUSIP::try_from(value).unwrap();
+//! assert_eq!(round_trip, cusip);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use apache_avro::types::Value;
+use apache_avro::Schema;
+
+use crate::{CUSIPError, CUSIP};
+
+/// The canonical Avro schema fragment for a CUSIP field. Embed this as the `type` of a record
+/// field (or as a branch of a union for optional fields).
+pub const SCHEMA_JSON: &str = r#"{"type": "string", "logicalType": "cusip"}"#;
+
+/// Returns the parsed form of `SCHEMA_JSON`.
+pub fn schema() -> Schema {
+    Schema::parse_str(SCHEMA_JSON).expect("SCHEMA_JSON should always be a valid Avro schema")
+}
+
+/// All the ways converting an Avro `Value` to a `CUSIP` could fail.
+#[non_exhaustive]
+#[derive(Clone, PartialEq, Debug)]
+pub enum FromValueError {
+    /// The value was not an Avro `string` (or a union branch holding one).
+    UnexpectedType,
+    /// The value was a `string`, but not a valid CUSIP.
+    InvalidCUSIP(CUSIPError),
+}
+
+impl fmt::Display for FromValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromValueError::UnexpectedType => write!(f, "expected an Avro string value"),
+            FromValueError::InvalidCUSIP(err) => write!(f, "invalid CUSIP: {err}"),
+        }
+    }
+}
+
+impl Error for FromValueError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FromValueError::UnexpectedType => None,
+            FromValueError::InvalidCUSIP(err) => Some(err),
+        }
+    }
+}
+
+impl From<CUSIP> for Value {
+    fn from(cusip: CUSIP) -> Self {
+        Value::String(cusip.to_string())
+    }
+}
+
+impl From<&CUSIP> for Value {
+    fn from(cusip: &CUSIP) -> Self {
+        Value::String(cusip.to_string())
+    }
+}
+
+impl TryFrom<&Value> for CUSIP {
+    type Error = FromValueError;
+
+    /// Converts an Avro `string` to a `CUSIP`, using the strict `CUSIP::parse`. A non-null branch
+    /// of a union is unwrapped first.
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => CUSIP::parse(s).map_err(FromValueError::InvalidCUSIP),
+            Value::Union(_, inner) => CUSIP::try_from(inner.as_ref()),
+            _ => Err(FromValueError::UnexpectedType),
+        }
+    }
+}
+
+impl TryFrom<Value> for CUSIP {
+    type Error = FromValueError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        CUSIP::try_from(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_is_a_string() {
+        assert_eq!(schema(), Schema::String);
+    }
+
+    #[test]
+    fn round_trip_through_value() {
+        let cusip = CUSIP::parse("S08000AA9").unwrap();
+        let value: Value = (&cusip).into();
+        assert_eq!(CUSIP::try_from(&value), Ok(cusip));
+    }
+
+    #[test]
+    fn unwraps_union() {
+        let value = Value::Union(1, Box::new(Value::String("09739D100".to_string())));
+        assert_eq!(
+            CUSIP::try_from(value),
+            Ok(CUSIP::parse("09739D100").unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_type() {
+        assert_eq!(
+            CUSIP::try_from(Value::Int(1)),
+            Err(FromValueError::UnexpectedType)
+        );
+        assert_eq!(
+            CUSIP::try_from(Value::Union(0, Box::new(Value::Null))),
+            Err(FromValueError::UnexpectedType)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_cusip() {
+        match CUSIP::try_from(Value::String("09739D101".to_string())) {
+            Err(FromValueError::InvalidCUSIP(CUSIPError::IncorrectCheckDigit { .. })) => {}
+            other => panic!("Expected IncorrectCheckDigit, but got: {other:?}"),
+        }
+    }
+}
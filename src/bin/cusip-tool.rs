@@ -1,5 +1,182 @@
-//! This simple tool reads potential CUSIPs from stdin, one per line, and parses them. It will panic
-//! if any fail to parse. This can be used as a simple bulk test of a file of purported CUSIPs to
+//! This tool works with CUSIPs from the command line. It is built with the `cli` feature:
+//!
+//! ```sh
+//! cargo install cusip --features cli
+//! ```
+//!
+//! It has these subcommands (run `cusip-tool help <subcommand>` for the options of each):
+//!
+//! * `validate` (the default): Check values read from standard input
+//! * `fix`: Like `validate`, but fix incorrect _Check Digits_ (see below). `cusip-tool --fix`, as
+//!   earlier versions took it, still runs `fix`
+//! * `build`: Build a CUSIP from a _Payload_, or from an _Issuer Number_ and an _Issue Number_, or
+//!   build CUSIPs in bulk from _Payloads_ read from standard input
+//! * `explain`: Describe a CUSIP in detail, or explain why it is not valid
+//! * `gen`: Generate random valid CUSIPs
+//! * `convert`: Convert CUSIPs to ISINs with `--to-isin`, CUSIP-based ISINs to CUSIPs with
+//!   `--from-isin`, or CUSIPs to their _Payloads_ (without the _Check Digit_) with `--to-payload`
+//! * `check-isins`: Check ISINs, and the CUSIPs embedded in CUSIP-based ISINs
+//! * `normalize`: Write the values read in canonical form, uppercase and without surrounding
+//!   whitespace
+//! * `filter`: Pass through the values read from standard input that match all the given
+//!   predicates
+//! * `grep`: Pass through the values read from standard input that match a wildcard pattern
+//! * `annotate`: Pass a CSV or TSV file through, adding columns describing the CUSIP in each row
+//! * `join`: Write the rows of a metadata file whose CUSIPs match the values read
+//! * `scan`: Find valid CUSIPs in free text, such as text extracted from PDFs
+//! * `sort`: Write the distinct valid values read from standard input in canonical form and in
+//!   ascending order
+//! * `split`: Divide the values read between shard files, always putting the same CUSIP in the
+//!   same shard
+//! * `merge`: Merge files of CUSIPs in ascending order into one, without duplicates
+//! * `stats`: Summarize the values read from standard input: how many are valid, the kinds of
+//!   errors, and how the valid ones break down by format, country and private use, reporting
+//!   invalid values
+//! * `top-issuers`: List the _Issuer Numbers_ occurring most often in the values read, for
+//!   concentration checks
+//! * `serve`: Answer validation requests over HTTP (see below)
+//! * `completions`: Write a completion script for a shell (`bash`, `elvish`, `fish`, `powershell`
+//!   or `zsh`), for example:
+//!
+//!   ```sh
+//!   cusip-tool completions bash > ~/.local/share/bash-completion/completions/cusip-tool
+//!   cusip-tool completions zsh > "${fpath[1]}/_cusip-tool"
+//!   cusip-tool completions fish > ~/.config/fish/completions/cusip-tool.fish
+//!   ```
+//!
+//! Apart from `build` and `gen`, the subcommands read values one per line from the files named
+//! after their options, in order, or from standard input if no files are named (`-` names standard
+//! input too), so the tool fits into Makefiles and batch scripts. When reading files, failures are
+//! reported with the file name as well as the line number, and given more than one file,
+//! `validate`, `fix` and `stats` summarize each file before the total:
+//!
+//! ```text
+//! $ cusip-tool validate monday.txt tuesday.txt
+//! monday.txt: Read 1200 values; 1200 were valid CUSIPs and 0 were not.
+//! tuesday.txt: Line 17: "09739d100": Issuer Number "09739d" is not six uppercase ASCII alphanumeric characters
+//! tuesday.txt: Read 1180 values; 1179 were valid CUSIPs and 1 were not.
+//! Read 2380 values; 2379 were valid CUSIPs and 1 were not.
+//! 1 values failed.
+//! ```
+//!
+//! The subcommands that read values take `--jobs N` (or `-j N`) to parse and
+//! check them on `N` threads, which helps with files of many millions of lines. The input is read
+//! in chunks, and the output and reports are the same, in the same order, whatever the number of
+//! jobs.
+//!
+//! To try a pipeline on part of a large file first, `--limit N` processes only the first `N` lines
+//! of each file, after skipping the first `M` lines with `--skip M`. Failures are still reported
+//! with their line numbers in the whole file:
+//!
+//! ```sh
+//! cusip-tool validate --skip 1000000 --limit 1000 universe.txt
+//! ```
+//!
+//! Lines end with a newline, and a carriage return before it is dropped, so files with Windows line
+//! endings read the same. `--record-delimiter` ends each line with another byte instead: `nul`,
+//! for pipelines of tools like `find -print0`, `xargs -0` and `psql -0`, `cr` for old Mac files,
+//! `tab`, or any single ASCII character. The output still has one line per value:
+//!
+//! ```sh
+//! psql -A -t -0 -c 'select cusip from securities' | cusip-tool validate --record-delimiter nul
+//! ```
+//!
+//! ## Fixed-width records
+//!
+//! To read the values straight out of fixed-width records, such as mainframe extracts, with one
+//! record per line, give `--fixed-width --offset N`: each value is then the field of 9 bytes (or
+//! `--length` bytes) starting at byte `N` (counting from 0) of each line. With `--ebcdic`, the
+//! field is decoded from EBCDIC (code page 037), so no `cut` or `iconv` step is needed first:
+//!
+//! ```sh
+//! cusip-tool validate --fixed-width --offset 24 --ebcdic --errors-to rejected.txt HOLDINGS.DAT
+//! ```
+//!
+//! A line too short for the field is reported with what there is of it. Add `--loose` to accept
+//! fields padded with spaces.
+//!
+//! ## Parquet
+//!
+//! Built with the `arrow` feature as well, `validate` reads the values from a column of Parquet
+//! files with `--format parquet --column NAME`, so identifier columns in a data lake can be
+//! checked in place:
+//!
+//! ```sh
+//! cargo install cusip --features cli,arrow
+//! cusip-tool validate --format parquet --column cusip positions/*.parquet
+//! ```
+//!
+//! Failures are reported with row numbers in place of line numbers. Values that are not strings
+//! are cast to strings, and nulls are read as empty values, so they are reported as invalid.
+//!
+//! ## Excel workbooks
+//!
+//! Built with the `xlsx` feature, `validate` reads the values straight from workbooks (`.xlsx`,
+//! `.xls` or `.ods`) with `--format xlsx --column NAME`, where `NAME` is in the header row of the
+//! first sheet, or of the sheet named by `--sheet`. This saves exporting to CSV first, the step
+//! where identifiers tend to get mangled:
+//!
+//! ```sh
+//! cargo install cusip --features cli,xlsx
+//! cusip-tool validate --format xlsx --sheet Positions --column CUSIP --loose positions.xlsx
+//! ```
+//!
+//! Failures are reported with the number of the row after the header row in place of line
+//! numbers. Cells holding numbers are read as displayed, so a CUSIP that a spreadsheet turned into
+//! a number is reported as invalid (it has lost its leading zeros) rather than silently repaired.
+//!
+//! ## Reporting
+//!
+//! Failures and summaries go to standard error. Two global options control how much is reported:
+//!
+//! * `-q`: Leave out the failures, reporting only the summaries; `-qq` leaves out the summaries as
+//!   well, so only the exit status tells the outcome
+//! * `-v`: Also note each file as it is read, and each value `fix` fixes
+//!
+//! With `--errors-to FILE`, the failures are written to the file instead, whatever the verbosity.
+//! `validate` then also writes the valid values to standard output, in canonical form, so one run
+//! splits a file into good values and quarantined ones with their reasons:
+//!
+//! ```sh
+//! cusip-tool validate --loose --errors-to rejected.txt < feed.txt > accepted.txt
+//! ```
+//!
+//! With `--quarantine FILE`, each rejected row is also written to the file as it was read, with
+//! the reason added as a last column, so the file can be handed on for remediation and fed back
+//! in once fixed. The rows are the lines read (the field, with `--fixed-width`), or the whole CSV
+//! rows for `build --column`, in which case the header row comes first. Like `--errors-to`, it
+//! makes `validate` write the valid values to standard output:
+//!
+//! ```sh
+//! cusip-tool validate --quarantine quarantine.csv < feed.txt > accepted.txt
+//! ```
+//!
+//! By default, the tool reads all its input and exits with status 1 if any value failed. For data
+//! gates that tolerate some bad values, `--max-errors N` makes it succeed if at most `N` values
+//! failed, and stop with status 1 as soon as more do. `--fail-fast` stops at the first failure:
+//!
+//! ```sh
+//! cusip-tool validate --max-errors 10 vendor-feed.txt && load-feed vendor-feed.txt
+//! ```
+//!
+//! These four options apply to the subcommands that report invalid values; the others (such as
+//! `gen`, `scan` and `serve`) reject them.
+//!
+//! ## Follow mode
+//!
+//! With `--follow`, `validate` keeps reading its one file as it grows, like `tail -f`, reporting
+//! each failure as soon as its line is complete, to monitor a live feed drop or log. It reads the
+//! file from the start, starts again if the file is truncated, and runs until interrupted:
+//!
+//! ```sh
+//! cusip-tool validate --follow --errors-to rejected.txt /var/feeds/cusips.txt
+//! ```
+//!
+//! ## Validate mode
+//!
+//! The `validate` subcommand reads potential CUSIPs from stdin, one per line, and parses them. Each
+//! value that fails to parse is reported to standard error with its line number and the reason, and
+//! processing continues with the next line. This can be used as a simple bulk test of a file of purported CUSIPs to
 //! ensure there are no malformed entries present. If you have a known-good file of valid CUSIPs, it
 //! can be used to validate this crate considers them valid.
 //!
@@ -33,13 +210,37 @@
 //!   > cusips-us.txt.gz
 //! ```
 //!
+//! The `sort` subcommand can replace the `sort | uniq` steps, validating as it goes (invalid values
+//! are reported and left out). It sorts in fixed-size chunks, spilling to temporary files, so it
+//! handles files larger than memory:
+//!
+//! ```sh
+//! grep ',US' ISIN_LEI_20210209.csv \
+//!   | sed -e 's/^.*,US//' \
+//!   | sed -e 's/.$//' \
+//!   | cusip-tool sort | gzip -9 \
+//!   > cusips-us.txt.gz
+//! ```
+//!
 //! This file was still about 4.2MB for the version tested.
 //!
+//! The `sed` steps strip the country code and the ISIN _Check Digit_ without checking either.
+//! `convert --from-isin` does the same transformation, but checks the ISIN _Check Digit_ and the
+//! embedded CUSIP, and reports the ISINs that are not CUSIP-based:
+//!
+//! ```sh
+//! grep ',US' ISIN_LEI_20210209.csv \
+//!   | cut -d , -f 2 \
+//!   | cusip-tool convert --from-isin \
+//!   | cusip-tool sort | gzip -9 \
+//!   > cusips-us.txt.gz
+//! ```
+//!
 //! Having produced the file, it is now possible to run it through this tool. From the source
 //! directory of this crate, you can run:
 //!
 //! ```sh
-//! gzcat cusips-us.txt.gz | cargo run cusip-tool
+//! gzcat cusips-us.txt.gz | cargo run --features cli --bin cusip-tool
 //! ```
 //!
 //! And, output will be something like this:
@@ -48,92 +249,3100 @@
 //! Read 1591249 values; 1591249 were valid CUSIPs and 0 were not.
 //! ```
 //!
-//! If no bad values were found, the tool will exit with zero status, else non-zero.
+//! Any bad values are reported before that summary, like this:
+//!
+//! ```text
+//! Line 17: "09739d100": Issuer Number "09739d" is not six uppercase ASCII alphanumeric characters
+//! ```
+//!
+//! If no bad values were found, the tool will exit with zero status. Otherwise it also reports the
+//! number of failures and exits with status 1. If an input cannot be opened or read, it exits
+//! with status 2.
+//!
+//! To check every ISIN in the GLEIF file, whatever its country, use `check-isins`. It verifies the
+//! ISIN _Check Digit_ of each and, for the CUSIP-based ones, the embedded CUSIP and its _Check
+//! Digit_ too, reporting each inconsistency:
+//!
+//! ```sh
+//! cut -d , -f 2 ISIN_LEI_20210209.csv | tail -n +2 | cusip-tool check-isins
+//! ```
+//!
+//! An ISIN is CUSIP-based as for `cusip::isin::ISIN::cusip()`: ISINs with country code `US` or `CA`
+//! must embed a valid CUSIP, and other ISINs count as CUSIP-based only if they embed a valid CINS
+//! identifier. With `-v`, the ISINs that are not CUSIP-based are noted. `convert --from-isin`
+//! follows the same rule.
+//!
+//! By default values must be in exactly the canonical form. With `--loose`, the subcommands that
+//! read values also accept values with surrounding whitespace or lowercase letters, as
+//! `CUSIP::parse_loose()` does, which suits raw vendor files:
+//!
+//! ```sh
+//! cusip-tool validate --loose < vendor-file.txt
+//! ```
+//!
+//! With `--output jsonl`, `validate` also writes a JSON object to standard output for every value,
+//! valid or not, one per line, ready for `jq` or loading into a warehouse. Each has the same keys
+//! (see `cusip::report::render_json_line()`):
+//!
+//! ```text
+//! {"input":"037833101","valid":false,"normalized":null,"issuer_num":null,"issue_num":null,"check_digit":null,"kind":"IncorrectCheckDigit","field":"check_digit","message":"incorrect Check Digit '1' when expecting '0'"}
+//! ```
+//!
+//! In this mode failures are not also reported as text on standard error, but they are still
+//! written to any `--errors-to` or `--quarantine` file and count towards `--max-errors`, and the
+//! summary and exit status are the same.
+//!
+//! With `--output csv`, `validate` writes a CSV file of the parsed fields of every valid value to
+//! standard output, for quick analysis in a spreadsheet, and reports failures as usual:
+//!
+//! ```text
+//! cusip,issuer_num,issue_num,check_digit,is_cins,cins_country_code,country,has_private_issuer,is_private_issue,is_private_use
+//! 037833100,037833,10,0,false,,North America,false,false,false
+//! S08000AA9,S08000,AA,9,true,S,South Africa,false,false,false
+//! ```
+//!
+//! ## Build mode
+//!
+//! Given no arguments, `build` reads 8-character _Payloads_ from standard input, one per line, and
+//! writes the CUSIPs with their computed _Check Digits_ to standard output, for generating feeds.
+//! With `--column`, it reads CSV with a header row and takes the _Payloads_ from the named column:
+//!
+//! ```sh
+//! cusip-tool build --column payload < securities.csv > cusips.txt
+//! ```
+//!
+//! With `--issuer-column` and `--issue-column` instead, it builds each CUSIP from the _Issuer
+//! Number_ and _Issue Number_ in the named columns, for minting identifiers in bulk:
+//!
+//! ```sh
+//! cusip-tool build --issuer-column issuer --issue-column issue < new-issues.csv > cusips.txt
+//! ```
+//!
+//! Bad _Payloads_ (or rows with bad parts) are reported and counted as in `validate`.
+//!
+//! ## Annotate mode
+//!
+//! The `annotate` subcommand reads delimited text with a header row, and writes every row
+//! unchanged but for extra columns describing the value in the column named by `--column`:
+//!
+//! * `cusip_valid`: `true` or `false`
+//! * `cusip_normalized`: The CUSIP in canonical form, if valid
+//! * `cusip_format`: `domestic` or `cins`, if valid
+//! * `cusip_country`: The country, as in `--output csv` above, if valid
+//! * `cusip_private_use`: `true` or `false`, if valid
+//! * `cusip_error`: Why the value is not valid, if it is not
+//!
+//! The delimiter is a comma, or another character given with `--delimiter` (`tab` for TSV):
+//!
+//! ```sh
+//! cusip-tool annotate --column id --delimiter tab --loose < positions.tsv > positions-checked.tsv
+//! ```
+//!
+//! Invalid values are annotated, not reported, so they do not make the tool fail. Given more than
+//! one file, the output has the header row of the first, and the others must have the same header.
+//!
+//! When vendors move their identifiers between columns without notice, `--detect-column` picks the
+//! column instead: it samples the first 1000 rows of each file, takes the column where the most
+//! values are valid CUSIPs (as with `--loose`), and reports its choice on standard error before
+//! going on:
+//!
+//! ```sh
+//! $ cusip-tool annotate --detect-column < positions.csv > positions-checked.csv
+//! standard input: Using column 3 ("Security ID"), valid in 99.8% of 1000 sampled rows
+//! ```
+//!
+//! If no sampled value in any column is a valid CUSIP, the tool exits with status 1.
+//!
+//! ## Join mode
+//!
+//! The `join` subcommand looks up each value read in a delimited metadata file with a header row,
+//! writing the header and then the rows whose CUSIP (in the column named by `--column`) matches,
+//! in the order the values were read. Both sides are normalized as with `--loose`, so `037833100`
+//! matches ` 037833100 ` in the metadata, unlike with `join(1)`:
+//!
+//! ```sh
+//! cusip-tool join --metadata securities.csv --column cusip holdings.txt > holdings.csv
+//! ```
+//!
+//! Values with no matching row are reported and counted as failures, like invalid values. Metadata
+//! rows without a valid CUSIP are skipped, with a count at the end (and each noted, with `-v`).
+//!
+//! ## Scan mode
+//!
+//! The `scan` subcommand finds the valid CUSIPs in arbitrary text (see `cusip::scan` for how), and
+//! writes each with its line number and (one-based, byte) column, like `grep -n`, with the file name
+//! first when reading files:
+//!
+//! ```text
+//! $ cusip-tool scan prospectus.txt
+//! prospectus.txt:12:31:037833100
+//! prospectus.txt:40:1:G1151C101
+//! ```
+//!
+//! Finding no CUSIPs is not a failure.
+//!
+//! ## Normalize mode
+//!
+//! The `normalize` subcommand writes each value read in canonical form, trimmed and uppercase, so
+//! files can be canonicalized before they are diffed or loaded. Invalid values are reported and
+//! counted as failures, but passed through as they are, so the output has a line for each line
+//! read, unless `--drop-invalid` leaves them out:
+//!
+//! ```sh
+//! cusip-tool normalize --drop-invalid < vendor.txt > vendor-canonical.txt
+//! ```
+//!
+//! ## Filter mode
+//!
+//! The `filter` subcommand writes each valid value read from standard input that matches every
+//! predicate given, in canonical form, for slicing universes in shell pipelines:
+//!
+//! * `--cins-only`: Only CINS identifiers
+//! * `--country G`: Only CINS identifiers with this _Country Code_
+//! * `--private-use`: Only CUSIPs with a private-use _Issuer Number_ or _Issue Number_
+//! * `--issuer-prefix 037833`: Only CUSIPs whose _Issuer Number_ starts with this
+//! * `--issue-kind equity`: Only CUSIPs with an all-digit _Issue Number_ (the convention for
+//!   equities), or with `fixed-income`, only those whose _Issue Number_ contains letters
+//!
+//! For example, this writes the UK fixed-income CINS identifiers in a file:
+//!
+//! ```sh
+//! cusip-tool filter --country G --issue-kind fixed-income < universe.txt
+//! ```
+//!
+//! Invalid values are reported and counted as in `validate`.
+//!
+//! ## Explain mode
+//!
+//! The `explain` subcommand describes each CUSIP given on the command line in full, for
+//! investigating a rejected value:
+//!
+//! ```text
+//! $ cusip-tool explain G1151C101
+//! "G1151C101" is a valid CUSIP
+//!   Issuer Number      G1151C
+//!   Issue Number       10 (all digits: the convention for equities)
+//!   Check Digit        1 (correct)
+//!   Format             CINS
+//!   Country Code       G (United Kingdom)
+//!   Region             Europe
+//!   Private Issuer     no
+//!   Private Issue      no
+//! ```
+//!
+//! If only the _Check Digit_ is wrong, the breakdown shows the _Check Digit_ expected. Otherwise
+//! it gives the reason the value is not valid. Invalid values count as failures.
+//!
+//! ## Grep mode
+//!
+//! The `grep` subcommand is like `filter`, but selects values with a wildcard pattern (see
+//! `cusip::pattern` for the syntax): `?` matches any character, `#` any digit, and a final `*`
+//! whatever remains. Unlike plain `grep`, it only passes through valid values, in canonical form,
+//! and reports the invalid ones:
+//!
+//! ```sh
+//! cusip-tool grep '037833???' universe.txt
+//! cusip-tool grep --invert-match '??????##?' universe.txt # Issue Numbers with letters
+//! ```
+//!
+//! ## Split mode
+//!
+//! The `split` subcommand divides the valid values read between `--shards N` files, in canonical
+//! form, so a huge file can be fanned out to parallel jobs. The shard of each CUSIP is given by
+//! `CUSIP::partition_key()`, so the same CUSIP always lands in the same shard, whatever file it is
+//! in. The files are named by `--prefix` (`shard-` by default), the shard number and `.txt`:
+//!
+//! ```text
+//! $ cusip-tool split --shards 16 --prefix work/universe- universe.txt
+//! Wrote 1591249 values to 16 shards.
+//! $ ls work
+//! universe-00.txt  universe-01.txt  ...  universe-15.txt
+//! ```
+//!
+//! Invalid values are reported and counted as in `validate`.
+//!
+//! ## Merge mode
+//!
+//! The `merge` subcommand merges files that are each in ascending order, such as the output of
+//! `sort`, into one in ascending order without duplicates. It streams, holding only the next value
+//! of each file, so it can consolidate extracts far larger than memory:
+//!
+//! ```sh
+//! cusip-tool merge americas.txt europe.txt asia.txt > universe.txt
+//! ```
+//!
+//! Invalid values, and values that are out of order in their file, are reported, counted as
+//! failures, and left out.
+//!
+//! ## Top issuers
+//!
+//! The `top-issuers` subcommand counts the valid values read by _Issuer Number_, and writes the
+//! 10 (or `-n N`) most frequent with their counts and shares of the valid values, for
+//! concentration checks on position files. With `--names FILE`, it names the _Issuers_ from a CSV
+//! file with `issuer_num` and `name` columns (see `cusip::resolver`):
+//!
+//! ```text
+//! $ cusip-tool top-issuers -n 3 --names issuers.csv positions.txt
+//! Issuer  Count   Share  Name
+//! 037833    412  20.60%  Apple Inc.
+//! 594918    388  19.40%  Microsoft Corp.
+//! 02079K    120   6.00%  Alphabet Inc.
+//! Read 2000 valid values from 85 Issuers.
+//! ```
+//!
+//! Invalid values are reported and counted as in `validate`.
+//!
+//! ## Serve mode
+//!
+//! The `serve` subcommand answers validation requests over HTTP, so other teams can check values
+//! against this implementation without installing anything. It listens on `127.0.0.1:8080` unless
+//! given `--bind ADDRESS:PORT`, and takes `--loose` like the other subcommands. There are two
+//! endpoints, both answering with the JSON objects of `validate --output jsonl`:
+//!
+//! * `GET /validate/{value}`: One object for one value (percent-encoded if need be)
+//! * `POST /validate`: An array of objects, one for each string in a JSON array in the body
+//!
+//! ```text
+//! $ cusip-tool serve --bind 0.0.0.0:8080 &
+//! $ curl http://localhost:8080/validate/037833100
+//! {"input":"037833100","valid":true,"normalized":"037833100",...}
+//! $ curl -d '["037833100", "09739d100"]' http://localhost:8080/validate
+//! [{"input":"037833100","valid":true,...},{"input":"09739d100","valid":false,...}]
+//! ```
+//!
+//! An invalid value is not an HTTP error: the response says why it is not valid. The server runs
+//! until interrupted, with `-v` noting each request.
 //!
 //! ## Fix mode
 //!
-//! If you run with argument `--fix`, then any input CUSIPs that are only wrong due to incorrect
+//! If you run the `fix` subcommand instead, then any input CUSIPs that are only wrong due to incorrect
 //! _Check Digit_ will be fixed. In this mode, every good and every fixable input CUSIP is printed
-//! to standard output.
+//! to standard output, and only the values that could not be fixed count as failures.
 
-use cusip::CUSIP;
-use std::env;
+#[cfg(feature = "arrow")]
+use arrow::{array::AsArray, compute::cast, datatypes::DataType};
+#[cfg(feature = "xlsx")]
+use calamine::{open_workbook_auto, Reader};
+use clap::{ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use cusip::analytics::CusipCounter;
+use cusip::country::{CinsCountryCode, Domicile, Region};
+use cusip::detect::detect_cusip_column;
+use cusip::isin::{self, FromIsinError};
+use cusip::pattern::CusipPattern;
+use cusip::report::{render_json_line, ValidationReport};
+use cusip::resolver::{InMemoryIssuerResolver, IssuerResolver, LoadError};
+use cusip::scan::find_cusips;
+use cusip::sort::{merge_sorted, ExternalSorter, DEFAULT_CHUNK_SIZE};
+use cusip::{CUSIPError, CUSIP};
+#[cfg(feature = "arrow")]
+use parquet::arrow::{
+    arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder},
+    ProjectionMask,
+};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use std::str::from_utf8_unchecked;
+use std::io::SeekFrom;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::str::{from_utf8, from_utf8_unchecked};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[path = "../csv.rs"]
+mod csv;
 
 #[doc(hidden)]
-fn main() {
-    let mut fix: bool = false;
+#[derive(Parser)]
+#[command(
+    name = "cusip-tool",
+    version,
+    about = "Validate, build, fix and convert CUSIPs"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The same as the `fix` subcommand, as earlier versions took it
+    #[arg(long, hide = true)]
+    fix: bool,
+    #[command(flatten)]
+    reporting: Reporting,
+}
+
+#[doc(hidden)]
+#[derive(Args)]
+struct Reporting {
+    /// Report less: -q leaves out the failures, and -qq the summaries as well
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+    quiet: u8,
+    /// Report more: note each file as it is read, and each value fixed
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Write the failures to this file instead of standard error
+    #[arg(long, value_name = "FILE", global = true)]
+    errors_to: Option<PathBuf>,
+    /// Also write each rejected row to this file as it was read, with the reason in a column added
+    /// at the end
+    #[arg(long, value_name = "FILE", global = true)]
+    quarantine: Option<PathBuf>,
+    /// Succeed if at most this many values fail, and stop as soon as more do
+    #[arg(long, value_name = "N", global = true)]
+    max_errors: Option<u64>,
+    /// Stop at the first value that fails, like --max-errors 0
+    #[arg(long, global = true, conflicts_with = "max_errors")]
+    fail_fast: bool,
+}
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 2 && args[1] == "--fix" {
-        fix = true;
-    } else if args.len() != 1 {
-        eprintln!("usage: cusip-tool [--fix]");
-        std::process::exit(1);
+impl Reporting {
+    /// Returns the first option given about failures, for rejecting it where no values are
+    /// reported as failures.
+    fn failure_option(&self) -> Option<&'static str> {
+        [
+            (self.errors_to.is_some(), "--errors-to"),
+            (self.quarantine.is_some(), "--quarantine"),
+            (self.max_errors.is_some(), "--max-errors"),
+            (self.fail_fast, "--fail-fast"),
+        ]
+        .into_iter()
+        .find_map(|(given, option)| given.then_some(option))
     }
+}
 
-    let mut good = 0u64;
-    let mut bad = 0u64;
-    let mut fixed = 0u64;
+/// Where and how much to report, from the `Reporting` options.
+struct Reporter {
+    /// Negative when quiet, positive when verbose
+    level: i16,
+    /// The file given by `--errors-to`
+    errors: Option<Mutex<File>>,
+    /// The file given by `--quarantine`
+    quarantine: Option<Mutex<File>>,
+    /// How many failures are tolerated, from `--max-errors` or `--fail-fast`
+    max_errors: Option<u64>,
+    /// How many failures have been reported so far
+    failures: AtomicU64,
+}
 
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let line = line.unwrap();
-        match CUSIP::parse(&line) {
-            Ok(cusip) => {
-                good += 1;
-                if fix {
-                    println!("{cusip}");
-                }
-            }
-            Err(cusip::CUSIPError::IncorrectCheckDigit {
-                was: _,
-                expected: _,
-            }) => {
-                bad += 1;
-                if fix {
-                    let payload = &line.as_bytes()[0..8]; // We know it was the right length
-                    let payload = unsafe { from_utf8_unchecked(payload) }; // We know it is ASCII
+/// Creates a file for the reporter to write to, if one was given. Exits with status 2 if it
+/// cannot be created.
+fn create_report_file(path: Option<&PathBuf>) -> Option<Mutex<File>> {
+    path.map(|path| match File::create(path) {
+        Ok(file) => Mutex::new(file),
+        Err(err) => {
+            eprintln!("Error creating {}: {err}", path.display());
+            exit(2);
+        }
+    })
+}
 
-                    // We know the Check Digit was the only problem, so we can safely unwrap()
-                    let cusip = cusip::build_from_payload(payload).unwrap();
-                    println!("{cusip}");
-                    fixed += 1;
-                }
-            }
-            Err(err) => {
-                eprintln!("Input: {line}; Error: {err}");
-                bad += 1;
-            }
+/// Writes a line to one of the reporter's files. Exits with status 2 if it cannot be written.
+fn write_report_line(file: &Mutex<File>, what: &str, line: fmt::Arguments<'_>) {
+    let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Err(err) = writeln!(file, "{line}") {
+        eprintln!("Error writing the {what} file: {err}");
+        exit(2);
+    }
+}
+
+/// Standard output, locked and buffered. Exits with status 2 if it cannot be written (as when the
+/// reader of a pipe has gone), instead of panicking as `println!` does.
+struct Out {
+    out: io::BufWriter<io::StdoutLock<'static>>,
+    /// Whether to flush after every line, for output that should appear as the input arrives
+    eager: bool,
+}
+
+impl Out {
+    fn new() -> Self {
+        Out {
+            out: io::BufWriter::new(io::stdout().lock()),
+            eager: false,
         }
     }
 
-    if fix {
-        eprintln!(
-            "Read {} values; {} were valid CUSIPs and {} were not. Fixed {}; Omitted {}.",
-            good + bad,
-            good,
-            bad,
-            fixed,
-            bad - fixed
-        );
+    /// Returns standard output flushed after every line.
+    fn eager() -> Self {
+        Out {
+            eager: true,
+            ..Self::new()
+        }
+    }
+
+    /// Writes the line, with a newline after it.
+    fn line(&mut self, line: impl fmt::Display) {
+        let mut written = writeln!(self.out, "{line}");
+        if self.eager {
+            written = written.and_then(|()| self.out.flush());
+        }
+        if let Err(err) = written {
+            eprintln!("Error writing standard output: {err}");
+            exit(2);
+        }
+    }
+
+    /// Writes anything still buffered. This must be done before exiting, as `exit()` does not.
+    fn flush(&mut self) {
+        if let Err(err) = self.out.flush() {
+            eprintln!("Error writing standard output: {err}");
+            exit(2);
+        }
+    }
+}
 
-        if bad > fixed {
-            std::process::exit(1);
+static REPORTER: OnceLock<Reporter> = OnceLock::new();
+
+impl Reporter {
+    /// Sets up the reporter from the options. Exits with status 2 if the errors file or the
+    /// quarantine file cannot be created.
+    fn init(options: &Reporting) {
+        let errors = create_report_file(options.errors_to.as_ref());
+        let quarantine = create_report_file(options.quarantine.as_ref());
+        let level = i16::from(options.verbose) - i16::from(options.quiet);
+        let max_errors = if options.fail_fast {
+            Some(0)
         } else {
-            std::process::exit(0);
+            options.max_errors
+        };
+        let _ = REPORTER.set(Reporter {
+            level,
+            errors,
+            quarantine,
+            max_errors,
+            failures: AtomicU64::new(0),
+        });
+    }
+
+    fn get() -> &'static Reporter {
+        REPORTER.get_or_init(|| Reporter {
+            level: 0,
+            errors: None,
+            quarantine: None,
+            max_errors: None,
+            failures: AtomicU64::new(0),
+        })
+    }
+
+    /// Returns true if failures are written to an errors file or a quarantine file.
+    fn has_failures_file(&self) -> bool {
+        self.errors.is_some() || self.quarantine.is_some()
+    }
+
+    /// Writes the header row of delimited input to the quarantine file, if there is one, with the
+    /// name of the reason column added.
+    fn quarantine_header(&self, header: &str) {
+        if let Some(file) = &self.quarantine {
+            write_report_line(file, "quarantine", format_args!("{header},reason"));
         }
-    } else {
-        eprintln!(
-            "Read {} values; {} were valid CUSIPs and {} were not.",
-            good + bad,
-            good,
-            bad
-        );
+    }
+
+    /// Reports a value that failed, and why.
+    fn failure(&self, at: At<'_>, value: &str, reason: impl fmt::Display) {
+        self.failure_in_row(at, value, value, reason);
+    }
+
+    /// Reports a value that failed, and why, quarantining the whole row of delimited input that
+    /// it came from.
+    fn failure_in_row(&self, at: At<'_>, value: &str, row: &str, reason: impl fmt::Display) {
+        self.report_failure(at, value, row, reason, true);
+    }
+
+    /// Reports a value that failed, and why, except as text on standard error, for output that
+    /// already describes each failure.
+    fn failure_in_output(&self, at: At<'_>, value: &str, reason: impl fmt::Display) {
+        self.report_failure(at, value, value, reason, false);
+    }
+
+    fn report_failure(
+        &self,
+        at: At<'_>,
+        value: &str,
+        row: &str,
+        reason: impl fmt::Display,
+        as_text: bool,
+    ) {
+        if let Some(file) = &self.quarantine {
+            let reason = reason.to_string();
+            let reason = quote_field(&reason, ',');
+            write_report_line(file, "quarantine", format_args!("{row},{reason}"));
+        }
+        match &self.errors {
+            Some(file) => {
+                write_report_line(file, "errors", format_args!("{at}: {value:?}: {reason}"))
+            }
+            None if as_text && self.level >= 0 => eprintln!("{at}: {value:?}: {reason}"),
+            None => {}
+        }
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(max) = self.max_errors.filter(|&max| failures > max) {
+            self.summary(format_args!(
+                "Stopped at {at}: more than {max} values failed."
+            ));
+            exit(1);
+        }
+    }
+
+    /// Reports a summary of what was read.
+    fn summary(&self, summary: impl fmt::Display) {
+        if self.level >= -1 {
+            eprintln!("{summary}");
+        }
+    }
+
+    /// Reports progress, if verbose.
+    fn note(&self, note: impl fmt::Display) {
+        if self.level > 0 {
+            eprintln!("{note}");
+        }
+    }
+}
 
-        let result = (bad == 0) as i32;
-        std::process::exit(result);
+#[doc(hidden)]
+#[derive(Subcommand)]
+enum Command {
+    /// Validate values read from files or standard input, one per line (the default)
+    Validate {
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+        /// How to report the outcome for each value
+        #[arg(long, value_enum, default_value_t = Output::Text)]
+        output: Output,
+        /// Keep reading the file as it grows, like `tail -f`, until interrupted
+        #[arg(long, requires = "files", conflicts_with = "jobs")]
+        follow: bool,
+        /// The format of the files: text with a value on each line, Parquet (which needs the
+        /// `arrow` feature), or Excel workbooks (which need the `xlsx` feature)
+        #[arg(long, value_enum, default_value_t = Format::Lines, conflicts_with = "follow")]
+        format: Format,
+        /// The column holding the values, in Parquet files, or named in the header row of
+        /// workbooks
+        #[arg(long, required_if_eq_any([("format", "parquet"), ("format", "xlsx")]))]
+        column: Option<String>,
+        /// The sheet to read, in workbooks (defaults to the first)
+        #[arg(long)]
+        sheet: Option<String>,
+    },
+    /// Validate values read from files or standard input, fixing incorrect Check Digits, and print
+    /// the valid and fixed CUSIPs
+    Fix {
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Build a CUSIP from an 8-character Payload, or from an Issuer Number and an Issue Number.
+    /// With no arguments, build a CUSIP from each Payload read from standard input
+    Build {
+        /// The Payload, or the Issuer Number if ISSUE_NUM is given
+        #[arg(value_name = "PAYLOAD_OR_ISSUER_NUM")]
+        first: Option<String>,
+        /// The Issue Number
+        #[arg(requires = "first")]
+        issue_num: Option<String>,
+        /// Read standard input as CSV with a header row, taking Payloads from this column
+        #[arg(long, conflicts_with = "first")]
+        column: Option<String>,
+        /// Read standard input as CSV with a header row, taking Issuer Numbers from this column
+        #[arg(long, requires = "issue_column", conflicts_with_all = ["first", "column"])]
+        issuer_column: Option<String>,
+        /// Read standard input as CSV with a header row, taking Issue Numbers from this column
+        #[arg(long, requires = "issuer_column", conflicts_with_all = ["first", "column"])]
+        issue_column: Option<String>,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Describe CUSIPs in detail, or explain why they are not valid
+    Explain {
+        /// The values to explain
+        #[arg(required = true)]
+        values: Vec<String>,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Generate random valid CUSIPs
+    Gen {
+        /// How many CUSIPs to generate
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+        /// Seed for the random number generator, for repeatable output
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Use this Issuer Number instead of random ones
+        #[arg(long)]
+        issuer: Option<String>,
+    },
+    /// Convert values read from files or standard input, one per line, between CUSIPs and
+    /// CUSIP-based ISINs, or from CUSIPs to Payloads
+    Convert {
+        /// Convert CUSIPs to ISINs with this country code
+        #[arg(
+            long,
+            value_name = "COUNTRY",
+            required_unless_present_any = ["from_isin", "to_payload"],
+            conflicts_with_all = ["from_isin", "to_payload"]
+        )]
+        to_isin: Option<String>,
+        /// Convert CUSIP-based ISINs to CUSIPs
+        #[arg(long)]
+        from_isin: bool,
+        /// Convert CUSIPs to their 8-character Payloads, leaving out the Check Digit
+        #[arg(long, conflicts_with = "from_isin")]
+        to_payload: bool,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write each value read from files or standard input, one per line, in canonical form
+    /// (trimmed and uppercase), passing invalid values through as they are and reporting them
+    Normalize {
+        /// Leave the invalid values out instead of passing them through
+        #[arg(long)]
+        drop_invalid: bool,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Check ISINs read from files or standard input, one per line, reporting invalid ones and
+    /// CUSIP-based ones whose embedded CUSIP is invalid
+    CheckIsins {
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the valid values read from files or standard input, one per line, that match all the
+    /// predicates, reporting invalid values
+    Filter {
+        #[command(flatten)]
+        predicates: Predicates,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the valid values read from files or standard input, one per line, that match a
+    /// wildcard pattern, reporting invalid values
+    Grep {
+        /// The pattern, with `?` for any character, `#` for any digit and a final `*` for the rest
+        pattern: CusipPattern,
+        /// Write the values that do not match instead
+        #[arg(long)]
+        invert_match: bool,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write delimited text read from files or standard input, adding columns that describe the
+    /// value in a column of each row
+    Annotate {
+        /// The name of the column holding the values, in the header row
+        #[arg(long, required_unless_present = "detect_column")]
+        column: Option<String>,
+        /// Pick the column where the most sampled values are valid CUSIPs, instead of naming it
+        #[arg(long, conflicts_with = "column")]
+        detect_column: bool,
+        /// The delimiter between fields: a single character, or `tab`
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: char,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the rows of a delimited metadata file whose CUSIP matches a value read from files or
+    /// standard input, reporting values with no match
+    Join {
+        /// The metadata file, with a header row
+        #[arg(long, value_name = "FILE")]
+        metadata: PathBuf,
+        /// The name of the column holding the CUSIPs, in the header row of the metadata
+        #[arg(long)]
+        column: String,
+        /// The delimiter between fields of the metadata: a single character, or `tab`
+        #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+        delimiter: char,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Find the valid CUSIPs in text read from files or standard input, and write each with where
+    /// it was found
+    Scan {
+        #[command(flatten)]
+        files: Files,
+    },
+    /// Write the distinct CUSIPs read from files or standard input, one per line, in canonical form
+    /// and in ascending order, reporting invalid values
+    Sort {
+        /// How many CUSIPs to sort in memory before spilling to a temporary file
+        #[arg(long, default_value_t = DEFAULT_CHUNK_SIZE)]
+        chunk_size: usize,
+        /// Where to put the temporary files (defaults to the system temporary directory)
+        #[arg(long)]
+        temp_dir: Option<PathBuf>,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the valid values read from files or standard input to shard files, in canonical form,
+    /// choosing the shard of each by `CUSIP::partition_key()`, reporting invalid values
+    Split {
+        /// How many shard files to write
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        shards: u32,
+        /// The start of the path of each shard file, which is followed by the shard number and
+        /// `.txt`
+        #[arg(long, default_value = "shard-")]
+        prefix: String,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Merge files of CUSIPs, each in ascending order, writing the distinct CUSIPs in canonical
+    /// form and in ascending order, reporting invalid values and values out of order
+    Merge {
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Summarize values read from files or standard input, one per line: validity, kinds of errors,
+    /// and the valid values by format, country and private use, reporting invalid values
+    Stats {
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the Issuer Numbers occurring most often in values read from files or standard input,
+    /// with their counts and shares of the valid values, reporting invalid values
+    TopIssuers {
+        /// How many Issuer Numbers to write
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+        /// Name the Issuers from this CSV file, with `issuer_num` and `name` columns
+        #[arg(long, value_name = "FILE")]
+        names: Option<PathBuf>,
+        #[command(flatten)]
+        files: Files,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Answer validation requests over HTTP: GET /validate/{value}, or POST /validate with a
+    /// JSON array of values
+    Serve {
+        /// The address and port to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+        /// Accept values with surrounding whitespace or lowercase letters, instead of requiring the
+        /// exact canonical form
+        #[arg(long)]
+        loose: bool,
+    },
+    /// Write a completion script for a shell to standard output
+    Completions {
+        /// The shell to complete in
+        shell: Shell,
+    },
+}
+
+impl Command {
+    /// Returns true if the subcommand reports invalid values through the `Reporter`, so that the
+    /// options about failures apply to it.
+    fn reports_failures(&self) -> bool {
+        !matches!(
+            self,
+            Command::Build { first: Some(_), .. }
+                | Command::Explain { .. }
+                | Command::Gen { .. }
+                | Command::Scan { .. }
+                | Command::Serve { .. }
+                | Command::Completions { .. }
+        )
+    }
+}
+
+#[doc(hidden)]
+#[derive(Args)]
+struct InputArgs {
+    /// Accept values with surrounding whitespace or lowercase letters, instead of requiring the
+    /// exact canonical form
+    #[arg(long)]
+    loose: bool,
+    /// Process the values on this many threads, reading the input in chunks (the output is in
+    /// input order either way)
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+    /// Skip this many lines at the start of each file
+    #[arg(long, value_name = "M", default_value_t = 0)]
+    skip: usize,
+    /// Process at most this many lines of each file, after any skipped
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+    /// The byte ending each line: `newline` (a carriage return before it is dropped), `nul`, `cr`,
+    /// `tab`, or a single ASCII character
+    #[arg(long, value_name = "DELIMITER", default_value = "newline", value_parser = parse_record_delimiter)]
+    record_delimiter: u8,
+    /// Read each value from a fixed-width field of each line, at --offset, instead of the whole
+    /// line
+    #[arg(long, requires = "offset")]
+    fixed_width: bool,
+    /// The (zero-based) byte offset of the field in each line
+    #[arg(long, requires = "fixed_width")]
+    offset: Option<usize>,
+    /// The length of the field in bytes
+    #[arg(long, default_value_t = 9, requires = "fixed_width")]
+    length: usize,
+    /// Decode the field from EBCDIC (code page 037) instead of ASCII
+    #[arg(long, requires = "fixed_width")]
+    ebcdic: bool,
+}
+
+/// The same values as the clap defaults, for running `validate` when no subcommand is given.
+impl Default for InputArgs {
+    fn default() -> Self {
+        Self {
+            loose: false,
+            jobs: 1,
+            skip: 0,
+            limit: None,
+            record_delimiter: b'\n',
+            fixed_width: false,
+            offset: None,
+            length: 9,
+            ebcdic: false,
+        }
+    }
+}
+
+impl InputArgs {
+    /// Returns the value as it should be parsed: trimmed and uppercased if loose.
+    fn prepare<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        if self.loose {
+            Cow::Owned(value.trim().to_ascii_uppercase())
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+
+    /// Parses the value strictly, or as `CUSIP::parse_loose()` does if loose.
+    fn parse(&self, value: &str) -> Result<CUSIP, CUSIPError> {
+        if self.loose {
+            CUSIP::parse_loose(value)
+        } else {
+            CUSIP::parse(value)
+        }
+    }
+
+    /// Returns which lines of each source to process.
+    fn window(&self) -> Window {
+        Window {
+            skip: self.skip,
+            limit: self.limit,
+            delimiter: self.record_delimiter,
+        }
+    }
+
+    /// Returns where to find the value in each line, if it is a fixed-width field.
+    fn field(&self) -> Option<Field> {
+        self.fixed_width.then(|| Field {
+            offset: self.offset.unwrap_or(0),
+            length: self.length,
+            ebcdic: self.ebcdic,
+        })
+    }
+
+    /// Calls `f` with the location, content and parsed value of each line of the source, parsing
+    /// on `jobs` threads.
+    fn for_each_parsed(
+        &self,
+        source: &mut Source,
+        f: impl FnMut(At<'_>, &str, Result<CUSIP, CUSIPError>),
+    ) {
+        for_each_mapped(source, self, |line| self.parse(line), f);
+    }
+}
+
+/// Which lines of each source to process, from `--skip` and `--limit`, and the byte ending each
+/// line, from `--record-delimiter`. The lines keep their line numbers.
+#[derive(Clone, Copy)]
+struct Window {
+    skip: usize,
+    limit: Option<usize>,
+    delimiter: u8,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Self {
+            skip: 0,
+            limit: None,
+            delimiter: b'\n',
+        }
+    }
+}
+
+/// A fixed-width field holding the value in each line, from the `--fixed-width` options.
+#[derive(Clone, Copy)]
+struct Field {
+    offset: usize,
+    length: usize,
+    ebcdic: bool,
+}
+
+impl Field {
+    /// Returns the field of the line, decoded. If the line is too short, the field is what there
+    /// is of it, so the value is reported as too short rather than skipped.
+    fn extract<'a>(&self, line: &'a [u8]) -> Cow<'a, str> {
+        let start = self.offset.min(line.len());
+        let end = self.offset.saturating_add(self.length).min(line.len());
+        let field = &line[start..end];
+        if self.ebcdic {
+            Cow::Owned(field.iter().map(|&b| ebcdic_char(b)).collect())
+        } else {
+            String::from_utf8_lossy(field)
+        }
+    }
+}
+
+/// Decodes a byte of EBCDIC (code page 037): the letters, digits, space and the characters used
+/// in CUSIPs, with anything else decoded as U+FFFD so it is reported as invalid.
+fn ebcdic_char(b: u8) -> char {
+    match b {
+        0x40 => ' ',
+        0x5C => '*',
+        0x7B => '#',
+        0x7C => '@',
+        0x81..=0x89 => char::from(b'a' + (b - 0x81)),
+        0x91..=0x99 => char::from(b'j' + (b - 0x91)),
+        0xA2..=0xA9 => char::from(b's' + (b - 0xA2)),
+        0xC1..=0xC9 => char::from(b'A' + (b - 0xC1)),
+        0xD1..=0xD9 => char::from(b'J' + (b - 0xD1)),
+        0xE2..=0xE9 => char::from(b'S' + (b - 0xE2)),
+        0xF0..=0xF9 => char::from(b'0' + (b - 0xF0)),
+        _ => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+/// Decodes a line (without its line break) as the value to process: the fixed-width field if
+/// there is one, or else the whole line, less any carriage return.
+fn decode_line<'a>(field: Option<&Field>, line: &'a [u8]) -> Cow<'a, str> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    match field {
+        Some(field) => field.extract(line),
+        None => String::from_utf8_lossy(line),
+    }
+}
+
+#[doc(hidden)]
+#[derive(Args, Default)]
+struct Files {
+    /// Files to read in order, instead of standard input (`-` reads standard input)
+    #[arg(value_name = "FILE")]
+    files: Vec<PathBuf>,
+}
+
+impl Files {
+    /// Returns true if there is more than one file, so each gets its own summary.
+    fn several(&self) -> bool {
+        self.files.len() > 1
+    }
+
+    /// Calls `f` with each file in order, or with standard input if there are none. Exits with
+    /// status 2 if a file cannot be opened.
+    fn for_each(&self, mut f: impl FnMut(&mut Source)) {
+        if self.files.is_empty() {
+            let reader = Box::new(io::stdin().lock());
+            return f(&mut Source { name: None, reader });
+        }
+        for path in &self.files {
+            f(&mut Self::open(path));
+        }
+    }
+
+    /// Opens all the files at once, or standard input if there are none. Exits with status 2 if a
+    /// file cannot be opened.
+    fn open_all(&self) -> Vec<Source> {
+        if self.files.is_empty() {
+            let reader = Box::new(io::stdin().lock());
+            return vec![Source { name: None, reader }];
+        }
+        self.files.iter().map(|path| Self::open(path)).collect()
+    }
+
+    /// Opens the file (or standard input, for `-`). Exits with status 2 if it cannot be opened.
+    fn open(path: &Path) -> Source {
+        let (name, reader): (String, Box<dyn BufRead>) = if path.as_os_str() == "-" {
+            ("<stdin>".to_string(), Box::new(io::stdin().lock()))
+        } else {
+            match File::open(path) {
+                Ok(file) => (
+                    path.display().to_string(),
+                    Box::new(io::BufReader::new(file)),
+                ),
+                Err(err) => {
+                    eprintln!("Error opening {}: {err}", path.display());
+                    exit(2);
+                }
+            }
+        };
+        Reporter::get().note(format_args!("Reading {name}"));
+        Source {
+            name: Some(name),
+            reader,
+        }
+    }
+
+    /// Calls `f` with the only file, read as it grows (see `Follow`). Exits with status 2 if there
+    /// is not exactly one file or it cannot be opened.
+    fn follow(&self, f: impl FnOnce(&mut Source)) {
+        let [path] = self.files.as_slice() else {
+            eprintln!("Exactly one file can be followed");
+            exit(2);
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Error opening {}: {err}", path.display());
+                exit(2);
+            }
+        };
+        let name = path.display().to_string();
+        Reporter::get().note(format_args!("Following {name}"));
+        f(&mut Source {
+            name: Some(name),
+            reader: Box::new(io::BufReader::new(Follow { file, pos: 0 })),
+        });
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl Files {
+    /// Calls `f` with each Parquet file in order, read as lines of the values in the column (see
+    /// `ParquetLines`). Exits with status 2 if there are no files or a file cannot be read as
+    /// Parquet, or with status 1 if a file has no such column.
+    fn for_each_parquet(&self, column: &str, mut f: impl FnMut(&mut Source)) {
+        if self.files.is_empty() || self.files.iter().any(|path| path.as_os_str() == "-") {
+            eprintln!("Parquet files cannot be read from standard input");
+            exit(2);
+        }
+        for path in &self.files {
+            let name = path.display().to_string();
+            let builder = File::open(path)
+                .map_err(|err| err.to_string())
+                .and_then(|file| {
+                    ParquetRecordBatchReaderBuilder::try_new(file).map_err(|err| err.to_string())
+                })
+                .unwrap_or_else(|err| {
+                    eprintln!("Error reading {name}: {err}");
+                    exit(2);
+                });
+            let Some((index, _)) = builder.schema().column_with_name(column) else {
+                eprintln!("{name}: No column named {column:?}");
+                exit(1);
+            };
+            let projection = ProjectionMask::roots(builder.parquet_schema(), [index]);
+            let batches = builder
+                .with_projection(projection)
+                .build()
+                .unwrap_or_else(|err| {
+                    eprintln!("Error reading {name}: {err}");
+                    exit(2);
+                });
+            Reporter::get().note(format_args!("Reading {name}"));
+            let lines = ParquetLines {
+                batches,
+                text: Vec::new(),
+                pos: 0,
+            };
+            f(&mut Source {
+                name: Some(name),
+                reader: Box::new(io::BufReader::new(lines)),
+            });
+        }
+    }
+}
+
+#[cfg(not(feature = "arrow"))]
+impl Files {
+    /// Exits with status 2: reading Parquet needs the `arrow` feature.
+    fn for_each_parquet(&self, _column: &str, _f: impl FnMut(&mut Source)) {
+        eprintln!("Reading Parquet needs cusip-tool built with the arrow feature");
+        exit(2);
+    }
+}
+
+#[cfg(feature = "xlsx")]
+impl Files {
+    /// Calls `f` with each workbook in order, read as lines of the values in the column (named in
+    /// the header row) of the sheet, or of the first sheet. Values that are not text are written
+    /// as they would be displayed (so numbers have lost any leading zeros), empty cells are read as
+    /// empty lines, and line breaks within values are read as spaces. Exits with status 2 if there
+    /// are no files or a file cannot be read as a workbook, or with status 1 if a workbook has no
+    /// such sheet or column.
+    fn for_each_workbook(&self, column: &str, sheet: Option<&str>, mut f: impl FnMut(&mut Source)) {
+        if self.files.is_empty() || self.files.iter().any(|path| path.as_os_str() == "-") {
+            eprintln!("Workbooks cannot be read from standard input");
+            exit(2);
+        }
+        for path in &self.files {
+            let name = path.display().to_string();
+            let mut workbook = open_workbook_auto(path).unwrap_or_else(|err| {
+                eprintln!("Error reading {name}: {err}");
+                exit(2);
+            });
+            let range = match sheet {
+                Some(sheet) => Some(workbook.worksheet_range(sheet)),
+                None => workbook.worksheet_range_at(0),
+            };
+            let range = match range {
+                Some(Ok(range)) => range,
+                Some(Err(err)) => {
+                    eprintln!("Error reading {name}: {err}");
+                    exit(if sheet.is_some() { 1 } else { 2 });
+                }
+                None => {
+                    eprintln!("{name}: No sheets");
+                    exit(1);
+                }
+            };
+            let mut rows = range.rows();
+            let index = rows
+                .next()
+                .and_then(|header| {
+                    header
+                        .iter()
+                        .position(|cell| cell.to_string().trim() == column)
+                })
+                .unwrap_or_else(|| {
+                    eprintln!("{name}: No column named {column:?}");
+                    exit(1);
+                });
+            let mut text = Vec::new();
+            for row in rows {
+                let value = row.get(index).map(ToString::to_string).unwrap_or_default();
+                text.extend(value.replace(['\n', '\r'], " ").bytes());
+                text.push(b'\n');
+            }
+            Reporter::get().note(format_args!("Reading {name}"));
+            f(&mut Source {
+                name: Some(name),
+                reader: Box::new(io::Cursor::new(text)),
+            });
+        }
+    }
+}
+
+#[cfg(not(feature = "xlsx"))]
+impl Files {
+    /// Exits with status 2: reading workbooks needs the `xlsx` feature.
+    fn for_each_workbook(&self, _column: &str, _sheet: Option<&str>, _f: impl FnMut(&mut Source)) {
+        eprintln!("Reading workbooks needs cusip-tool built with the xlsx feature");
+        exit(2);
+    }
+}
+
+/// Reads the values in one column of a Parquet file as text, a value on each line, so the row
+/// numbers are the line numbers. Values that are not strings are cast to strings, nulls are read
+/// as empty lines, and line breaks within values are read as spaces, so each of these is still
+/// reported as invalid on its own line.
+#[cfg(feature = "arrow")]
+struct ParquetLines {
+    batches: ParquetRecordBatchReader,
+    /// The lines of the current batch, and how much of them has been read
+    text: Vec<u8>,
+    pos: usize,
+}
+
+#[cfg(feature = "arrow")]
+impl Read for ParquetLines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.text.len() {
+            let Some(batch) = self.batches.next() else {
+                return Ok(0);
+            };
+            let values = cast(batch.map_err(io::Error::other)?.column(0), &DataType::Utf8)
+                .map_err(io::Error::other)?;
+            self.text.clear();
+            self.pos = 0;
+            for value in values.as_string::<i32>() {
+                let value = value.unwrap_or_default().bytes();
+                self.text
+                    .extend(value.map(|b| if b == b'\n' || b == b'\r' { b' ' } else { b }));
+                self.text.push(b'\n');
+            }
+        }
+        let n = (&self.text[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// How long `Follow` waits before looking for more data at the end of the file.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads a file as it grows: at the end of the file, it waits for more data instead of returning
+/// end of file. If the file is truncated, it starts again from the beginning.
+struct Follow {
+    file: File,
+    pos: u64,
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 {
+                self.pos += n as u64;
+                return Ok(n);
+            }
+            if self.file.metadata()?.len() < self.pos {
+                Reporter::get().note("The file was truncated; reading it from the start");
+                self.pos = self.file.seek(SeekFrom::Start(0))?;
+                continue;
+            }
+            thread::sleep(FOLLOW_INTERVAL);
+        }
+    }
+}
+
+/// An input to read values from, one per line.
+struct Source {
+    /// The name of the file, or `None` when reading standard input because no files were named
+    name: Option<String>,
+    reader: Box<dyn BufRead>,
+}
+
+impl Source {
+    /// Returns what the source is, for messages.
+    fn describe(&self) -> &str {
+        self.name.as_deref().unwrap_or("standard input")
+    }
+}
+
+/// Where a value was read: a (one-based) line number, and the file name if files were named.
+#[derive(Clone, Copy)]
+struct At<'a> {
+    name: Option<&'a str>,
+    line: usize,
+}
+
+impl fmt::Display for At<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "{name}: Line {}", self.line),
+            None => write!(f, "Line {}", self.line),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Args)]
+struct Predicates {
+    /// Only CINS identifiers
+    #[arg(long)]
+    cins_only: bool,
+    /// Only CINS identifiers with this Country Code (a letter)
+    #[arg(long, value_parser = parse_country_code)]
+    country: Option<CinsCountryCode>,
+    /// Only CUSIPs with a private-use Issuer Number or Issue Number
+    #[arg(long)]
+    private_use: bool,
+    /// Only CUSIPs whose Issuer Number starts with this
+    #[arg(long)]
+    issuer_prefix: Option<String>,
+    /// Only CUSIPs with this kind of Issue Number
+    #[arg(long, value_enum)]
+    issue_kind: Option<IssueKind>,
+}
+
+impl Predicates {
+    /// Returns true if the CUSIP matches all the predicates.
+    fn matches(&self, cusip: &CUSIP) -> bool {
+        (!self.cins_only || cusip.is_cins())
+            && self.country.is_none_or(|code| cusip.is_cins_for(code))
+            && (!self.private_use || cusip.is_private_use())
+            && self
+                .issuer_prefix
+                .as_ref()
+                .is_none_or(|prefix| cusip.issuer_num().starts_with(prefix.as_str()))
+            && self
+                .issue_kind
+                .is_none_or(|kind| cusip.issue_kind() == kind.into())
+    }
+}
+
+fn parse_country_code(value: &str) -> Result<CinsCountryCode, String> {
+    let mut chars = value.chars();
+    match (
+        chars.next().and_then(CinsCountryCode::from_char),
+        chars.next(),
+    ) {
+        (Some(code), None) => Ok(code),
+        _ => Err("expected a single uppercase ASCII letter".to_string()),
+    }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum IssueKind {
+    /// An Issue Number of two digits (the convention for equities)
+    Equity,
+    /// An Issue Number containing letters (the convention for fixed income)
+    FixedIncome,
+}
+
+impl From<IssueKind> for cusip::IssueKind {
+    fn from(kind: IssueKind) -> Self {
+        match kind {
+            IssueKind::Equity => cusip::IssueKind::Equity,
+            IssueKind::FixedIncome => cusip::IssueKind::FixedIncome,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Text, with a value on each line
+    Lines,
+    /// Parquet, with the values in the column given by --column
+    Parquet,
+    /// Excel workbooks (.xlsx, .xls or .ods), with the values in the column given by --column
+    Xlsx,
+}
+
+/// How `validate` reads its files.
+enum Reading<'a> {
+    /// Each file once, a value on each line
+    Lines,
+    /// The one file, as it grows
+    Follow,
+    /// Each Parquet file, taking the values from this column
+    Parquet(&'a str),
+    /// Each workbook, taking the values from this column of this sheet (or the first)
+    Xlsx(&'a str, Option<&'a str>),
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// Report only the failures, as text on standard error
+    #[default]
+    Text,
+    /// Write a JSON object for every value to standard output, one per line
+    Jsonl,
+    /// Write a CSV row of the parsed fields of every valid value to standard output, and report
+    /// the failures as text on standard error
+    Csv,
+}
+
+/// Parses `--record-delimiter`: a name for a common delimiter, or a single ASCII character.
+fn parse_record_delimiter(value: &str) -> Result<u8, String> {
+    match value {
+        "newline" => Ok(b'\n'),
+        "nul" => Ok(0),
+        "cr" => Ok(b'\r'),
+        "tab" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => Err("expected `newline`, `nul`, `cr`, `tab` or a single ASCII character".to_string()),
+    }
+}
+
+fn parse_delimiter(value: &str) -> Result<char, String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        _ if value == "tab" => Ok('\t'),
+        (Some(c), None) if c != '"' && c != '\n' && c != '\r' => Ok(c),
+        _ => Err(
+            "expected a single character other than a quote or line break, or `tab`".to_string(),
+        ),
+    }
+}
+
+/// The header row for `Output::Csv`.
+const CSV_HEADER: &str = "cusip,issuer_num,issue_num,check_digit,is_cins,cins_country_code,\
+                          country,has_private_issuer,is_private_issue,is_private_use";
+
+/// Renders the parsed fields of the CUSIP as a row for `Output::Csv`. None of the fields can
+/// contain a comma or quote, so none need quoting.
+fn csv_row(cusip: &CUSIP) -> String {
+    let (code, country) = match cusip.domicile() {
+        Domicile::NorthAmerica => (String::new(), "North America"),
+        Domicile::Cins(code) => (code.as_char().to_string(), code.region_name()),
+    };
+    format!(
+        "{cusip},{},{},{},{},{code},{country},{},{},{}",
+        cusip.issuer_num(),
+        cusip.issue_num(),
+        cusip.check_digit(),
+        cusip.is_cins(),
+        cusip.has_private_issuer(),
+        cusip.is_private_issue(),
+        cusip.is_private_use()
+    )
+}
+
+/// Calls `f` with the location and content of each line of the source in the window (see
+/// `decode_line()`). Lines that are not UTF-8 cannot be valid, but are still passed on so they are
+/// reported and counted.
+fn for_each_line(
+    source: &mut Source,
+    field: Option<&Field>,
+    window: Window,
+    mut f: impl FnMut(At<'_>, &str),
+) {
+    let name = source.name.as_deref();
+    let lines = source.reader.as_mut().split(window.delimiter).enumerate();
+    let lines = lines
+        .skip(window.skip)
+        .take(window.limit.unwrap_or(usize::MAX));
+    for (i, line) in lines {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                let what = name.unwrap_or("standard input");
+                eprintln!("Error reading {what} after line {i}: {err}");
+                exit(2);
+            }
+        };
+        f(At { name, line: i + 1 }, &decode_line(field, &line));
+    }
+}
+
+/// About how many bytes are read at a time when processing on more than one thread.
+const CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Like `for_each_line()`, but calls `f` with the result of `map` on each line as well. With more
+/// than one job, the source is read in chunks of whole lines, each chunk is divided between
+/// `jobs` threads that split and decode the lines and call `map`, and then `f` is called for each
+/// line of the chunk in order.
+fn for_each_mapped<T: Send>(
+    source: &mut Source,
+    input: &InputArgs,
+    map: impl Fn(&str) -> T + Sync,
+    mut f: impl FnMut(At<'_>, &str, T),
+) {
+    let (jobs, field, window) = (input.jobs, input.field(), input.window());
+    let field = field.as_ref();
+    if jobs <= 1 {
+        for_each_line(source, field, window, |at, line| f(at, line, map(line)));
+        return;
+    }
+
+    let map = &map;
+    let name = source.name.as_deref();
+    let reader = source.reader.as_mut();
+    let delimiter = window.delimiter;
+    let mut chunk = Vec::new();
+    let mut read = 0;
+    while read < window.skip {
+        chunk.clear();
+        match reader.read_until(delimiter, &mut chunk) {
+            Ok(0) => return,
+            Ok(_) => read += 1,
+            Err(err) => {
+                let what = name.unwrap_or("standard input");
+                eprintln!("Error reading {what} after line {read}: {err}");
+                exit(2);
+            }
+        }
+    }
+    let mut remaining = window.limit.unwrap_or(usize::MAX);
+    while remaining > 0 {
+        chunk.clear();
+        let filled = (&mut *reader)
+            .take(CHUNK_BYTES)
+            .read_to_end(&mut chunk)
+            .and_then(|_| reader.read_until(delimiter, &mut chunk));
+        if let Err(err) = filled {
+            let what = name.unwrap_or("standard input");
+            eprintln!("Error reading {what} after line {read}: {err}");
+            exit(2);
+        }
+        if chunk.is_empty() {
+            break;
+        }
+
+        let mapped: Vec<Vec<(String, T)>> = thread::scope(|scope| {
+            let handles: Vec<_> = split_lines_evenly(&chunk, jobs, delimiter)
+                .map(|part| {
+                    scope.spawn(move || {
+                        let part = part.strip_suffix(&[delimiter]).unwrap_or(part);
+                        part.split(|&b| b == delimiter)
+                            .map(|line| {
+                                let line = decode_line(field, line).into_owned();
+                                let value = map(&line);
+                                (line, value)
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for (line, value) in mapped.into_iter().flatten().take(remaining) {
+            read += 1;
+            remaining -= 1;
+            f(At { name, line: read }, &line, value);
+        }
+    }
+}
+
+/// Divides the bytes into at most `parts` non-empty pieces of about the same size, each ending
+/// with the delimiter except perhaps the last.
+fn split_lines_evenly(bytes: &[u8], parts: usize, delimiter: u8) -> impl Iterator<Item = &[u8]> {
+    let target = bytes.len().div_ceil(parts).max(1);
+    let mut rest = bytes;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = match rest.iter().skip(target - 1).position(|&b| b == delimiter) {
+            Some(i) => target + i,
+            None => rest.len(),
+        };
+        let (part, tail) = rest.split_at(end);
+        rest = tail;
+        Some(part)
+    })
+}
+
+/// Exits with status 1 after reporting the failures, if there were any, and more than
+/// `--max-errors` allows.
+fn finish(failed: u64) {
+    if failed == 0 {
+        return;
+    }
+    let reporter = Reporter::get();
+    match reporter.max_errors {
+        Some(max) if failed <= max => reporter.summary(format_args!(
+            "{failed} values failed, within the limit of {max}."
+        )),
+        _ => {
+            reporter.summary(format_args!("{failed} values failed."));
+            exit(1);
+        }
+    }
+}
+
+/// Counts of the values read, for summaries.
+#[derive(Clone, Copy, Default)]
+struct Tally {
+    good: u64,
+    bad: u64,
+    fixed: u64,
+}
+
+impl Tally {
+    fn add(&mut self, other: Tally) {
+        self.good += other.good;
+        self.bad += other.bad;
+        self.fixed += other.fixed;
+    }
+
+    /// Returns the summary, including what was fixed and omitted if fixing.
+    fn summary(&self, fix: bool) -> String {
+        let summary = format!(
+            "Read {} values; {} were valid CUSIPs and {} were not.",
+            self.good + self.bad,
+            self.good,
+            self.bad
+        );
+        if fix {
+            format!(
+                "{summary} Fixed {}; Omitted {}.",
+                self.fixed,
+                self.bad - self.fixed
+            )
+        } else {
+            summary
+        }
+    }
+}
+
+fn validate(files: &Files, input: &InputArgs, output: Output, fix: bool, reading: Reading<'_>) {
+    let reporter = Reporter::get();
+    let mut total = Tally::default();
+    let mut out = match reading {
+        Reading::Follow => Out::eager(),
+        _ => Out::new(),
+    };
+
+    if output == Output::Csv {
+        out.line(CSV_HEADER);
+    }
+    let process = |source: &mut Source| {
+        let mut tally = Tally::default();
+        input.for_each_parsed(source, |at, line, result| match result {
+            result if output == Output::Jsonl => {
+                out.line(render_json_line(line, &result));
+                match result {
+                    Ok(_) => tally.good += 1,
+                    Err(err) => {
+                        // Reporting may stop the run, so the output so far must be written first
+                        out.flush();
+                        reporter.failure_in_output(at, line, err);
+                        tally.bad += 1;
+                    }
+                }
+            }
+            Ok(cusip) => {
+                tally.good += 1;
+                if output == Output::Csv {
+                    out.line(csv_row(&cusip));
+                } else if fix || reporter.has_failures_file() {
+                    out.line(cusip);
+                }
+            }
+            Err(CUSIPError::IncorrectCheckDigit { .. }) if fix => {
+                tally.bad += 1;
+                let value = input.prepare(line);
+                let payload = &value.as_bytes()[0..8]; // We know it was the right length
+                let payload = unsafe { from_utf8_unchecked(payload) }; // We know it is ASCII
+
+                // We know the Check Digit was the only problem, so we can safely unwrap()
+                let cusip = cusip::build_from_payload(payload).unwrap();
+                out.line(cusip);
+                reporter.note(format_args!("{at}: fixed {line:?} as {cusip}"));
+                tally.fixed += 1;
+            }
+            Err(err) => {
+                out.flush();
+                Reporter::get().failure(at, line, err);
+                tally.bad += 1;
+            }
+        });
+        if files.several() {
+            reporter.summary(format_args!(
+                "{}: {}",
+                source.describe(),
+                tally.summary(fix)
+            ));
+        }
+        total.add(tally);
+    };
+    match reading {
+        Reading::Lines => files.for_each(process),
+        Reading::Follow => files.follow(process),
+        Reading::Parquet(column) => files.for_each_parquet(column, process),
+        Reading::Xlsx(column, sheet) => files.for_each_workbook(column, sheet, process),
+    }
+    out.flush();
+
+    reporter.summary(total.summary(fix));
+    finish(total.bad - total.fixed);
+}
+
+fn build(input: &InputArgs, first: &str, issue_num: Option<&str>) {
+    let first = input.prepare(first);
+    let result = match issue_num {
+        Some(issue_num) => cusip::build_from_parts(&first, &input.prepare(issue_num)),
+        None => cusip::build_from_payload(&first),
+    };
+    match result {
+        Ok(cusip) => {
+            let mut out = Out::new();
+            out.line(cusip);
+            out.flush();
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
+        }
+    }
+}
+
+/// Where `build_all` takes the values to build CUSIPs from.
+enum BuildFrom<'a> {
+    /// Each line is a _Payload_
+    Lines,
+    /// CSV with _Payloads_ in the named column
+    PayloadColumn(&'a str),
+    /// CSV with _Issuer Numbers_ and _Issue Numbers_ in the named columns
+    PartColumns(&'a str, &'a str),
+}
+
+impl BuildFrom<'_> {
+    /// Returns the names of the columns to find in the header row.
+    fn columns(&self) -> Vec<&str> {
+        match *self {
+            BuildFrom::Lines => vec![],
+            BuildFrom::PayloadColumn(payload) => vec![payload],
+            BuildFrom::PartColumns(issuer, issue) => vec![issuer, issue],
+        }
+    }
+}
+
+fn build_all(input: &InputArgs, from: BuildFrom<'_>) {
+    let columns = from.columns();
+    let mut indexes: Option<Vec<usize>> = None;
+    let mut bad = 0u64;
+    let split = |line: &str| (!columns.is_empty()).then(|| csv::split_record(line, ','));
+    let mut out = Out::new();
+    Files::default().for_each(|source| {
+        for_each_mapped(source, input, split, |at, line, fields| {
+            let Some(fields) = fields else {
+                match cusip::build_from_payload(&input.prepare(line)) {
+                    Ok(cusip) => out.line(cusip),
+                    Err(err) => {
+                        Reporter::get().failure(at, line, err);
+                        bad += 1;
+                    }
+                }
+                return;
+            };
+            let Some(indexes) = &indexes else {
+                let found = columns.iter().map(|name| {
+                    fields
+                        .iter()
+                        .position(|f| f.trim() == *name)
+                        .unwrap_or_else(|| {
+                            eprintln!("No column named {name:?}");
+                            exit(1);
+                        })
+                });
+                indexes = Some(found.collect());
+                Reporter::get().quarantine_header(line);
+                return;
+            };
+            if line.trim().is_empty() {
+                return;
+            }
+            let field = |i: usize| input.prepare(fields.get(indexes[i]).map_or("", String::as_str));
+            let (value, result) = match from {
+                BuildFrom::PartColumns(..) => (
+                    line.to_string(),
+                    cusip::build_from_parts(&field(0), &field(1)),
+                ),
+                _ => {
+                    let payload = field(0);
+                    let result = cusip::build_from_payload(&payload);
+                    (payload.into_owned(), result)
+                }
+            };
+            match result {
+                Ok(cusip) => out.line(cusip),
+                Err(err) => {
+                    Reporter::get().failure_in_row(at, &value, line, err);
+                    bad += 1;
+                }
+            }
+        });
+    });
+    out.flush();
+    finish(bad);
+}
+
+/// Returns the name of the region, for `explain`.
+fn region_name(region: Region) -> &'static str {
+    match region {
+        Region::NorthAmerica => "North America",
+        Region::SouthAmerica => "South America",
+        Region::Europe => "Europe",
+        Region::MiddleEastAndAfrica => "Middle East and Africa",
+        Region::AsiaPacific => "Asia-Pacific",
+    }
+}
+
+/// Prints a breakdown of the value, returning true if it is a valid CUSIP.
+fn explain_one(out: &mut Out, input: &InputArgs, value: &str) -> bool {
+    let (cusip, check_digit, valid) = match input.parse(value) {
+        Ok(cusip) if cusip.to_string() == value => {
+            out.line(format_args!("{value:?} is a valid CUSIP"));
+            (cusip, format!("{} (correct)", cusip.check_digit()), true)
+        }
+        Ok(cusip) => {
+            out.line(format_args!(
+                "{value:?} is a valid CUSIP, {cusip} in canonical form"
+            ));
+            (cusip, format!("{} (correct)", cusip.check_digit()), true)
+        }
+        Err(err @ CUSIPError::IncorrectCheckDigit { was, expected }) => {
+            out.line(format_args!("{value:?} is not a valid CUSIP: {err}"));
+            let value = input.prepare(value);
+            let payload = &value.as_bytes()[0..8]; // We know it was the right length
+            let payload = unsafe { from_utf8_unchecked(payload) }; // We know it is ASCII
+
+            // We know the Check Digit was the only problem, so we can safely unwrap()
+            let cusip = cusip::build_from_payload(payload).unwrap();
+            let check_digit = format!(
+                "{} (incorrect: expecting {})",
+                char::from(was),
+                char::from(expected)
+            );
+            (cusip, check_digit, false)
+        }
+        Err(err) => {
+            out.line(format_args!("{value:?} is not a valid CUSIP: {err}"));
+            return false;
+        }
+    };
+
+    let issue_kind = match cusip.issue_kind() {
+        cusip::IssueKind::Equity => "all digits: the convention for equities",
+        cusip::IssueKind::FixedIncome => "has letters: the convention for fixed income",
+    };
+    let (format, country) = match cusip.domicile() {
+        Domicile::NorthAmerica => ("Domestic (North America)", "none".to_string()),
+        Domicile::Cins(code) if code.is_unused() => {
+            ("CINS", format!("{} (unused)", code.as_char()))
+        }
+        Domicile::Cins(code) => ("CINS", format!("{} ({code})", code.as_char())),
+    };
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let rows = [
+        ("Issuer Number", cusip.issuer_num().to_string()),
+        (
+            "Issue Number",
+            format!("{} ({issue_kind})", cusip.issue_num()),
+        ),
+        ("Check Digit", check_digit),
+        ("Format", format.to_string()),
+        ("Country Code", country),
+        (
+            "Region",
+            cusip
+                .region()
+                .map_or("none (unused Country Code)", region_name)
+                .to_string(),
+        ),
+        (
+            "Private Issuer",
+            yes_no(cusip.has_private_issuer()).to_string(),
+        ),
+        (
+            "Private Issue",
+            yes_no(cusip.is_private_issue()).to_string(),
+        ),
+    ];
+    for (label, value) in rows {
+        out.line(format_args!("  {label:<17}  {value}"));
+    }
+    valid
+}
+
+/// A SplitMix64 generator: not for cryptography, but small and repeatable from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn alphanumeric(&mut self, len: usize) -> String {
+        const CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+        (0..len)
+            .map(|_| char::from(CHARS[(self.next() % 36) as usize]))
+            .collect()
+    }
+}
+
+fn explain(input: &InputArgs, values: &[String]) {
+    let mut bad = 0u64;
+    let mut out = Out::new();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.line("");
+        }
+        if !explain_one(&mut out, input, value) {
+            bad += 1;
+        }
+    }
+    out.flush();
+    finish(bad);
+}
+
+fn gen(count: usize, seed: Option<u64>, issuer: Option<&str>) {
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let mut rng = Rng(seed);
+    let mut out = Out::new();
+    for _ in 0..count {
+        let issuer_num = match issuer {
+            Some(issuer) => issuer.to_string(),
+            None => rng.alphanumeric(6),
+        };
+        match cusip::build_from_parts(&issuer_num, &rng.alphanumeric(2)) {
+            Ok(cusip) => out.line(cusip),
+            Err(err) => {
+                eprintln!("{err}");
+                exit(1);
+            }
+        }
+    }
+    out.flush();
+}
+
+/// Checks an ISIN and, if it is CUSIP-based (see `ISIN::cusip()`), the CUSIP embedded in it,
+/// explaining any problem. Returns the CUSIP, or `None` if the ISIN is valid but not CUSIP-based.
+/// An ISIN with country code `US` or `CA` must embed a valid CUSIP.
+fn check_isin(value: &str) -> Result<Option<CUSIP>, String> {
+    match CUSIP::from_isin_str(value) {
+        Ok(cusip) => Ok(Some(cusip)),
+        Err(FromIsinError::NotCusipBased { .. }) => Ok(None),
+        Err(err) => Err(explain_isin_error(value, err)),
+    }
+}
+
+/// Extracts the CUSIP from a CUSIP-based ISIN, checking both the ISIN _Check Digit_ and the
+/// embedded CUSIP, and explaining any problem.
+fn cusip_from_isin(value: &str) -> Result<CUSIP, String> {
+    CUSIP::from_isin_str(value).map_err(|err| explain_isin_error(value, err))
+}
+
+/// Explains why `CUSIP::from_isin_str()` rejected the value, saying what is wrong with an invalid
+/// ISIN and which CUSIP is invalid.
+fn explain_isin_error(value: &str, err: FromIsinError) -> String {
+    match err {
+        FromIsinError::InvalidIsin => {
+            match value
+                .get(0..11)
+                .filter(|_| value.len() == 12)
+                .and_then(isin::compute_check_digit)
+            {
+                Some(expected) => format!(
+                    "incorrect ISIN Check Digit '{}' when expecting '{expected}'",
+                    char::from(value.as_bytes()[11])
+                ),
+                None => {
+                    "not 2 uppercase ASCII letters followed by 10 uppercase ASCII alphanumeric \
+                         characters"
+                        .to_string()
+                }
+            }
+        }
+        FromIsinError::InvalidCusip(err) => {
+            format!("embedded CUSIP {:?} is invalid: {err}", &value[2..11])
+        }
+        err => err.to_string(),
+    }
+}
+
+fn check_isins(files: &Files, input: &InputArgs) {
+    let reporter = Reporter::get();
+    let (mut consistent, mut other, mut bad) = (0u64, 0u64, 0u64);
+    files.for_each(|source| {
+        let check = |line: &str| check_isin(&input.prepare(line));
+        for_each_mapped(source, input, check, |at, line, checked| match checked {
+            Ok(Some(_)) => consistent += 1,
+            Ok(None) => {
+                reporter.note(format_args!("{at}: {line:?}: not CUSIP-based"));
+                other += 1;
+            }
+            Err(err) => {
+                reporter.failure(at, line, err);
+                bad += 1;
+            }
+        });
+    });
+    reporter.summary(format_args!(
+        "Read {} ISINs; {consistent} were CUSIP-based and consistent, {other} were not \
+         CUSIP-based, and {bad} were not valid.",
+        consistent + other + bad
+    ));
+    finish(bad);
+}
+
+/// What `convert` converts values to.
+#[derive(Clone, Copy)]
+enum Conversion<'a> {
+    /// CUSIPs to ISINs with this country code
+    ToIsin(&'a str),
+    /// CUSIP-based ISINs to CUSIPs
+    FromIsin,
+    /// CUSIPs to their Payloads
+    ToPayload,
+}
+
+fn convert(files: &Files, input: &InputArgs, conversion: Conversion<'_>) {
+    if let Conversion::ToIsin(country) = conversion {
+        if country.len() != 2 || !country.bytes().all(|b| b.is_ascii_uppercase()) {
+            eprintln!("Country code {country:?} is not two uppercase ASCII letters");
+            exit(1);
+        }
+    }
+
+    let convert = |line: &str| match conversion {
+        Conversion::ToIsin(country) => {
+            input
+                .parse(line)
+                .map_err(|err| err.to_string())
+                // The country code was checked above
+                .map(|cusip| cusip.to_isin(country).unwrap().to_string())
+        }
+        Conversion::FromIsin => {
+            cusip_from_isin(&input.prepare(line)).map(|cusip| cusip.to_string())
+        }
+        Conversion::ToPayload => input
+            .parse(line)
+            .map_err(|err| err.to_string())
+            .map(|cusip| cusip.payload().to_string()),
+    };
+
+    let mut bad = 0u64;
+    let mut out = Out::new();
+    files.for_each(|source| {
+        for_each_mapped(
+            source,
+            input,
+            convert,
+            |at, line, converted| match converted {
+                Ok(converted) => out.line(converted),
+                Err(err) => {
+                    Reporter::get().failure(at, line, err);
+                    bad += 1;
+                }
+            },
+        );
+    });
+    out.flush();
+    finish(bad);
+}
+
+fn filter(files: &Files, input: &InputArgs, matches: impl Fn(&CUSIP) -> bool) {
+    let mut bad = 0u64;
+    let mut out = Out::new();
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| match result {
+            Ok(cusip) => {
+                if matches(&cusip) {
+                    out.line(cusip);
+                }
+            }
+            Err(err) => {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+            }
+        });
+    });
+    out.flush();
+    finish(bad);
+}
+
+fn normalize(files: &Files, input: &InputArgs, drop_invalid: bool) {
+    let mut bad = 0u64;
+    let mut out = Out::new();
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| match result {
+            Ok(cusip) => out.line(cusip),
+            Err(err) => {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+                if !drop_invalid {
+                    out.line(line);
+                }
+            }
+        });
+    });
+    out.flush();
+    finish(bad);
+}
+
+/// The names of the columns `annotate` adds.
+const ANNOTATIONS: [&str; 6] = [
+    "cusip_valid",
+    "cusip_normalized",
+    "cusip_format",
+    "cusip_country",
+    "cusip_private_use",
+    "cusip_error",
+];
+
+/// Returns the field, quoted if it contains the delimiter, a quote or a line break.
+fn quote_field(field: &str, delimiter: char) -> Cow<'_, str> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// The rows of a metadata file for `join`, by CUSIP.
+struct Metadata {
+    name: String,
+    header: String,
+    rows: HashMap<CUSIP, Vec<String>>,
+}
+
+impl Metadata {
+    /// Reads the metadata file, normalizing the CUSIPs in the column. Rows whose value is not a
+    /// CUSIP, even normalized, are skipped (and noted, if verbose). Exits with status 2 if the file
+    /// cannot be read, or with status 1 if it has no such column.
+    fn load(path: &Path, column: &str, delimiter: char) -> Self {
+        let file = File::open(path).unwrap_or_else(|err| {
+            eprintln!("Error opening {}: {err}", path.display());
+            exit(2);
+        });
+        let mut source = Source {
+            name: Some(path.display().to_string()),
+            reader: Box::new(io::BufReader::new(file)),
+        };
+        let reporter = Reporter::get();
+        let mut header: Option<(String, usize)> = None;
+        let mut rows: HashMap<CUSIP, Vec<String>> = HashMap::new();
+        let mut skipped = 0u64;
+        for_each_line(&mut source, None, Window::default(), |at, line| {
+            let fields = csv::split_record(line, delimiter);
+            let Some((_, index)) = &header else {
+                let Some(i) = fields.iter().position(|f| f.trim() == column) else {
+                    eprintln!("{}: No column named {column:?}", path.display());
+                    exit(1);
+                };
+                header = Some((line.to_string(), i));
+                return;
+            };
+            if line.trim().is_empty() {
+                return;
+            }
+            let value = fields.get(*index).map_or("", String::as_str);
+            match CUSIP::parse_loose(value) {
+                Ok(cusip) => rows.entry(cusip).or_default().push(line.to_string()),
+                Err(err) => {
+                    reporter.note(format_args!("{at}: {value:?}: {err}; skipping the row"));
+                    skipped += 1;
+                }
+            }
+        });
+        if skipped > 0 {
+            reporter.summary(format_args!(
+                "{}: Skipped {skipped} rows without a valid CUSIP.",
+                path.display()
+            ));
+        }
+        Metadata {
+            name: path.display().to_string(),
+            header: header.map(|(header, _)| header).unwrap_or_default(),
+            rows,
+        }
+    }
+}
+
+fn join(files: &Files, input: &InputArgs, metadata: &Path, column: &str, delimiter: char) {
+    let metadata = Metadata::load(metadata, column, delimiter);
+    let reporter = Reporter::get();
+    let mut out = Out::new();
+    out.line(&metadata.header);
+
+    let (mut read, mut matched, mut bad) = (0u64, 0u64, 0u64);
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| {
+            read += 1;
+            match result.map(|cusip| metadata.rows.get(&cusip)) {
+                Ok(Some(rows)) => {
+                    matched += 1;
+                    rows.iter().for_each(|row| out.line(row));
+                }
+                Ok(None) => {
+                    reporter.failure(at, line, format_args!("not found in {}", metadata.name));
+                    bad += 1;
+                }
+                Err(err) => {
+                    reporter.failure(at, line, err);
+                    bad += 1;
+                }
+            }
+        });
+    });
+    out.flush();
+
+    reporter.summary(format_args!(
+        "Read {read} values; {matched} were found in {} and {bad} were not.",
+        metadata.name
+    ));
+    finish(bad);
+}
+
+/// Returns the values of the `ANNOTATIONS` columns for the result of parsing a value.
+fn annotations(result: &Result<CUSIP, CUSIPError>) -> [String; 6] {
+    match result {
+        Ok(cusip) => {
+            let country = match cusip.domicile() {
+                Domicile::NorthAmerica => "North America",
+                Domicile::Cins(code) => code.region_name(),
+            };
+            [
+                "true".to_string(),
+                cusip.to_string(),
+                if cusip.is_cins() { "cins" } else { "domestic" }.to_string(),
+                country.to_string(),
+                cusip.is_private_use().to_string(),
+                String::new(),
+            ]
+        }
+        Err(err) => [
+            "false".to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            err.to_string(),
+        ],
+    }
+}
+
+/// The number of records `annotate --detect-column` samples from the start of each file.
+const DETECT_SAMPLE: usize = 1000;
+
+/// Samples the start of the source, reporting the column picked by `detect_cusip_column()` on
+/// standard error, and returns its index. The sampled lines (ending with `record`) are put back,
+/// to be read again. Exits with status 1 if no column holds a valid CUSIP, or 2 if the source
+/// cannot be read.
+fn detect_column(source: &mut Source, delimiter: char, record: u8) -> usize {
+    let describe = source.describe().to_string();
+    let mut sample = Vec::new();
+    for _ in 0..DETECT_SAMPLE {
+        match source.reader.read_until(record, &mut sample) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("Error reading {describe}: {err}");
+                exit(2);
+            }
+        }
+    }
+    let lines: Cow<[u8]> = if record == b'\n' {
+        Cow::Borrowed(&sample)
+    } else {
+        Cow::Owned(
+            sample
+                .iter()
+                .map(|&b| if b == record { b'\n' } else { b })
+                .collect(),
+        )
+    };
+    let guess = detect_cusip_column(&lines[..], delimiter, DETECT_SAMPLE).unwrap_or_else(|err| {
+        eprintln!("Error reading {describe}: {err}");
+        exit(2);
+    });
+    let Some(guess) = guess else {
+        eprintln!("{describe}: No column holds a valid CUSIP in the first {DETECT_SAMPLE} rows");
+        exit(1);
+    };
+    let header = lines.split(|&b| b == b'\n').next().unwrap_or_default();
+    let header = csv::split_record(String::from_utf8_lossy(header).trim_end(), delimiter);
+    let name = header.get(guess.index).map_or("", |name| name.trim());
+    eprintln!(
+        "{describe}: Using column {} ({name:?}), valid in {:.1}% of {} sampled rows",
+        guess.index + 1,
+        guess.confidence * 100.0,
+        guess.sampled
+    );
+
+    let rest = std::mem::replace(&mut source.reader, Box::new(io::empty()));
+    source.reader = Box::new(io::Cursor::new(sample).chain(rest));
+    guess.index
+}
+
+fn annotate(files: &Files, input: &InputArgs, column: Option<&str>, delimiter: char) {
+    let mut out = Out::new();
+
+    let mut first_header: Option<String> = None;
+    let split = |line: &str| csv::split_record(line, delimiter);
+    files.for_each(|source| {
+        let record = input.record_delimiter;
+        let mut detected = column
+            .is_none()
+            .then(|| detect_column(source, delimiter, record));
+        let mut index = None;
+        let describe = source.describe().to_string();
+        for_each_mapped(source, input, split, |_, line, fields| {
+            let Some(i) = index else {
+                let found = detected.take().or_else(|| {
+                    let column = column.unwrap_or_default();
+                    fields.iter().position(|f| f.trim() == column)
+                });
+                let Some(i) = found else {
+                    eprintln!(
+                        "{describe}: No column named {:?}",
+                        column.unwrap_or_default()
+                    );
+                    exit(1);
+                };
+                index = Some(i);
+                match &first_header {
+                    None => {
+                        let mut header = line.to_string();
+                        for name in ANNOTATIONS {
+                            header.push(delimiter);
+                            header.push_str(name);
+                        }
+                        out.line(&header);
+                        first_header = Some(line.to_string());
+                    }
+                    Some(first) if first == line => {}
+                    Some(_) => {
+                        eprintln!("{describe}: The header row differs from the first file's");
+                        exit(1);
+                    }
+                }
+                return;
+            };
+            if line.trim().is_empty() {
+                out.line(line);
+                return;
+            }
+            let value = fields.get(i).map_or("", String::as_str);
+            let mut row = line.to_string();
+            for field in annotations(&input.parse(value)) {
+                row.push(delimiter);
+                row.push_str(&quote_field(&field, delimiter));
+            }
+            out.line(&row);
+        });
+    });
+    out.flush();
+}
+
+fn scan(files: &Files) {
+    let mut out = Out::new();
+    let (mut lines, mut found) = (0u64, 0u64);
+    files.for_each(|source| {
+        let name = source.name.as_deref();
+        for (i, line) in source.reader.as_mut().split(b'\n').enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    let what = name.unwrap_or("standard input");
+                    eprintln!("Error reading {what} after line {i}: {err}");
+                    exit(2);
+                }
+            };
+            lines += 1;
+            for (offset, cusip) in find_cusips(&line) {
+                found += 1;
+                match name {
+                    Some(name) => out.line(format_args!("{name}:{}:{}:{cusip}", i + 1, offset + 1)),
+                    None => out.line(format_args!("{}:{}:{cusip}", i + 1, offset + 1)),
+                }
+            }
+        }
+    });
+    out.flush();
+    Reporter::get().summary(format_args!("Scanned {lines} lines; found {found} CUSIPs."));
+}
+
+fn sort(files: &Files, input: &InputArgs, chunk_size: usize, temp_dir: Option<PathBuf>) {
+    let mut sorter = ExternalSorter::new().with_chunk_size(chunk_size);
+    if let Some(dir) = temp_dir {
+        sorter = sorter.with_temp_dir(dir);
+    }
+
+    let fail = |err: io::Error| -> ! {
+        eprintln!("Error sorting: {err}");
+        exit(2);
+    };
+    let mut good = 0u64;
+    let mut bad = 0u64;
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| match result {
+            Ok(cusip) => {
+                good += 1;
+                sorter.push(cusip).unwrap_or_else(|err| fail(err));
+            }
+            Err(err) => {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+            }
+        });
+    });
+
+    let mut distinct = 0u64;
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    for cusip in sorter.finish().unwrap_or_else(|err| fail(err)) {
+        let cusip = cusip.unwrap_or_else(|err| fail(err));
+        writeln!(out, "{cusip}").unwrap_or_else(|err| fail(err));
+        distinct += 1;
+    }
+    out.flush().unwrap_or_else(|err| fail(err));
+
+    Reporter::get().summary(format_args!(
+        "Read {} values; {} were valid CUSIPs ({} distinct) and {} were not.",
+        good + bad,
+        good,
+        distinct,
+        bad
+    ));
+    finish(bad);
+}
+
+fn split(files: &Files, input: &InputArgs, shards: u32, prefix: &str) {
+    let width = (shards - 1).to_string().len();
+    let mut outputs: Vec<(String, io::BufWriter<File>)> = (0..shards)
+        .map(|i| {
+            let path = format!("{prefix}{i:0width$}.txt");
+            match File::create(&path) {
+                Ok(file) => (path, io::BufWriter::new(file)),
+                Err(err) => {
+                    eprintln!("Error creating {path}: {err}");
+                    exit(2);
+                }
+            }
+        })
+        .collect();
+
+    let (mut written, mut bad) = (0u64, 0u64);
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| match result {
+            Ok(cusip) => {
+                let (path, out) = &mut outputs[cusip.partition_key(shards) as usize];
+                if let Err(err) = writeln!(out, "{cusip}") {
+                    eprintln!("Error writing {path}: {err}");
+                    exit(2);
+                }
+                written += 1;
+            }
+            Err(err) => {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+            }
+        });
+    });
+    for (path, out) in &mut outputs {
+        if let Err(err) = out.flush() {
+            eprintln!("Error writing {path}: {err}");
+            exit(2);
+        }
+    }
+    Reporter::get().summary(format_args!("Wrote {written} values to {shards} shards."));
+    finish(bad);
+}
+
+/// The valid values of a sorted source for `merge`, reporting the values that are not valid or
+/// not in ascending order, and skipping them.
+struct SortedValues<'a> {
+    source: Source,
+    input: &'a InputArgs,
+    field: Option<Field>,
+    window: Window,
+    line: usize,
+    last: Option<CUSIP>,
+    bad: &'a Cell<u64>,
+}
+
+impl Iterator for SortedValues<'_> {
+    type Item = CUSIP;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = Vec::new();
+        let end = self
+            .window
+            .skip
+            .saturating_add(self.window.limit.unwrap_or(usize::MAX));
+        while self.line < end {
+            bytes.clear();
+            match self
+                .source
+                .reader
+                .read_until(self.window.delimiter, &mut bytes)
+            {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(err) => {
+                    let what = self.source.describe();
+                    eprintln!("Error reading {what} after line {}: {err}", self.line);
+                    exit(2);
+                }
+            }
+            self.line += 1;
+            if self.line <= self.window.skip {
+                continue;
+            }
+            let bytes = bytes
+                .strip_suffix(&[self.window.delimiter])
+                .unwrap_or(&bytes);
+            let line = decode_line(self.field.as_ref(), bytes);
+            let at = At {
+                name: self.source.name.as_deref(),
+                line: self.line,
+            };
+            let failure = match self.input.parse(&line) {
+                Ok(cusip) if self.last.is_none_or(|last| last <= cusip) => {
+                    self.last = Some(cusip);
+                    return Some(cusip);
+                }
+                Ok(_) => format!("out of order after {}", self.last.unwrap()),
+                Err(err) => err.to_string(),
+            };
+            Reporter::get().failure(at, &line, failure);
+            self.bad.set(self.bad.get() + 1);
+        }
+        None
+    }
+}
+
+fn merge(files: &Files, input: &InputArgs) {
+    let bad = Cell::new(0u64);
+    let sources = files.open_all().into_iter().map(|source| SortedValues {
+        source,
+        input,
+        field: input.field(),
+        window: input.window(),
+        line: 0,
+        last: None,
+        bad: &bad,
+    });
+    let mut out = Out::new();
+    let mut written = 0u64;
+    for cusip in merge_sorted(sources.collect::<Vec<_>>()) {
+        out.line(cusip);
+        written += 1;
+    }
+    out.flush();
+    Reporter::get().summary(format_args!("Wrote {written} distinct values."));
+    finish(bad.get());
+}
+
+/// Prints a section of labelled counts, aligned, with a blank line before it. Empty sections are
+/// left out.
+fn print_counts<L: std::fmt::Display>(
+    out: &mut Out,
+    title: &str,
+    counts: impl IntoIterator<Item = (L, u64)>,
+) {
+    let counts: Vec<(String, u64)> = counts
+        .into_iter()
+        .map(|(label, n)| (label.to_string(), n))
+        .collect();
+    if counts.is_empty() {
+        return;
+    }
+    let width = counts
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    out.line(format_args!("\n{title}:"));
+    for (label, n) in counts {
+        out.line(format_args!("  {label:<width$}  {n}"));
+    }
+}
+
+/// What `stats` reports about the values read.
+#[derive(Default)]
+struct Summary {
+    report: ValidationReport,
+    domiciles: BTreeMap<Domicile, u64>,
+    private_issuer: u64,
+    private_issue: u64,
+    private_use: u64,
+}
+
+impl Summary {
+    fn record(&mut self, line: &str, result: Result<CUSIP, CUSIPError>) {
+        if let Ok(cusip) = &result {
+            *self.domiciles.entry(cusip.domicile()).or_insert(0) += 1;
+            self.private_issuer += u64::from(cusip.has_private_issuer());
+            self.private_issue += u64::from(cusip.is_private_issue());
+            self.private_use += u64::from(cusip.is_private_use());
+        }
+        self.report.record(line, result);
+    }
+
+    fn print(&self, out: &mut Out) {
+        let report = &self.report;
+        out.line(format_args!(
+            "Read {} values: {} valid, {} invalid",
+            report.total(),
+            report.valid(),
+            report.invalid()
+        ));
+        print_counts(
+            out,
+            "Errors by kind",
+            report
+                .counts_by_kind()
+                .into_iter()
+                .map(|(kind, n)| (kind, n as u64)),
+        );
+        if report.valid() > 0 {
+            let domestic = self
+                .domiciles
+                .get(&Domicile::NorthAmerica)
+                .copied()
+                .unwrap_or(0);
+            print_counts(
+                out,
+                "Valid by format",
+                [
+                    ("Domestic", domestic),
+                    ("CINS", report.valid() as u64 - domestic),
+                ],
+            );
+            print_counts(
+                out,
+                "Valid by country",
+                self.domiciles.iter().map(|(domicile, n)| {
+                    let label = match domicile {
+                        Domicile::NorthAmerica => "North America (domestic)".to_string(),
+                        Domicile::Cins(code) => format!("{} {code}", code.as_char()),
+                    };
+                    (label, *n)
+                }),
+            );
+            print_counts(
+                out,
+                "Private use",
+                [
+                    ("Private Issuer Number", self.private_issuer),
+                    ("Private Issue Number", self.private_issue),
+                    ("Either", self.private_use),
+                ],
+            );
+        }
+    }
+}
+
+fn stats(files: &Files, input: &InputArgs) {
+    let mut total = Summary::default();
+    let mut bad = 0u64;
+    let mut out = Out::new();
+    files.for_each(|source| {
+        let mut summary = Summary::default();
+        input.for_each_parsed(source, |at, line, result| {
+            if let Err(err) = &result {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+            }
+            if files.several() {
+                summary.record(line, result.clone());
+            }
+            total.record(line, result);
+        });
+        if files.several() {
+            out.line(format_args!("== {} ==", source.describe()));
+            summary.print(&mut out);
+            out.line("");
+        }
+    });
+    if files.several() {
+        out.line("== Total ==");
+    }
+    total.print(&mut out);
+    out.flush();
+    finish(bad);
+}
+
+fn top_issuers(files: &Files, input: &InputArgs, count: usize, names: Option<&Path>) {
+    let names = names.map(|path| {
+        File::open(path)
+            .map_err(LoadError::from)
+            .and_then(|file| InMemoryIssuerResolver::from_csv(io::BufReader::new(file)))
+            .unwrap_or_else(|err| {
+                eprintln!("Error reading {}: {err}", path.display());
+                exit(2);
+            })
+    });
+
+    let mut counter = CusipCounter::new();
+    let mut bad = 0u64;
+    files.for_each(|source| {
+        input.for_each_parsed(source, |at, line, result| match result {
+            Ok(cusip) => counter.add(&cusip),
+            Err(err) => {
+                Reporter::get().failure(at, line, err);
+                bad += 1;
+            }
+        });
+    });
+
+    let total = counter.total();
+    let mut rows = vec![[
+        "Issuer".to_string(),
+        "Count".to_string(),
+        "Share".to_string(),
+        "Name".to_string(),
+    ]];
+    for (issuer_num, n) in counter.top_issuers(count) {
+        let name = names
+            .as_ref()
+            .and_then(|names| names.resolve(&issuer_num))
+            .map(|info| info.name)
+            .unwrap_or_default();
+        let share = n as f64 * 100.0 / total as f64;
+        rows.push([
+            issuer_num.to_string(),
+            n.to_string(),
+            format!("{share:.2}%"),
+            name,
+        ]);
+    }
+    let columns = if names.is_some() { 4 } else { 3 };
+    let mut out = Out::new();
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| rows.iter().map(|row| row[i].len()).max().unwrap_or(0))
+        .collect();
+    for row in &rows {
+        let line = (0..columns)
+            .map(|i| match i {
+                1 | 2 => format!("{:>width$}", row[i], width = widths[i]),
+                _ => format!("{:<width$}", row[i], width = widths[i]),
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.line(line.trim_end());
+    }
+    out.flush();
+    Reporter::get().summary(format_args!(
+        "Read {} valid values from {} Issuers.",
+        total,
+        counter.distinct_issuers()
+    ));
+    finish(bad);
+}
+
+/// The largest request body `serve` accepts.
+const MAX_REQUEST_BODY: usize = 1024 * 1024;
+
+/// The longest request line or header line `serve` accepts.
+const MAX_REQUEST_LINE: u64 = 8 * 1024;
+
+/// How long `serve` waits for a client to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Decodes the `%XX` escapes in a URL path segment, or returns `None` if it is malformed.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'%' {
+            let hex = from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Reads one request from the stream and answers it, returning the request line and status.
+fn respond(stream: &TcpStream, input: &InputArgs) -> io::Result<(String, &'static str)> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut out = stream;
+    let mut reader = io::BufReader::new(stream);
+    let mut read_line = |line: &mut String| {
+        line.clear();
+        reader.by_ref().take(MAX_REQUEST_LINE).read_line(line)
+    };
+
+    let mut request = String::new();
+    read_line(&mut request)?;
+    let request = request.trim_end().to_string();
+    let mut content_length = 0;
+    let mut expect_continue = false;
+    let mut header = String::new();
+    while read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(usize::MAX);
+            } else if name.eq_ignore_ascii_case("expect") {
+                expect_continue = value.eq_ignore_ascii_case("100-continue");
+            }
+        }
+    }
+
+    let mut parts = request.split(' ');
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split_once('?').map_or(target, |(path, _)| path);
+    let (status, body) = match (method, path.strip_prefix("/validate")) {
+        ("GET", Some(segment)) if segment.starts_with('/') => match percent_decode(&segment[1..]) {
+            Some(value) => ("200 OK", render_json_line(&value, &input.parse(&value))),
+            None => (
+                "400 Bad Request",
+                error_json("malformed percent-encoding in the value"),
+            ),
+        },
+        ("POST", Some("")) if content_length > MAX_REQUEST_BODY => (
+            "413 Payload Too Large",
+            error_json(&format!(
+                "the request body must be at most {MAX_REQUEST_BODY} bytes"
+            )),
+        ),
+        ("POST", Some("")) => {
+            if expect_continue {
+                out.write_all(b"HTTP/1.1 100 Continue\r\n\r\n")?;
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            match serde_json::from_slice::<Vec<String>>(&body) {
+                Ok(values) => {
+                    let objects: Vec<String> = values
+                        .iter()
+                        .map(|value| render_json_line(value, &input.parse(value)))
+                        .collect();
+                    ("200 OK", format!("[{}]", objects.join(",")))
+                }
+                Err(err) => (
+                    "400 Bad Request",
+                    error_json(&format!("expected a JSON array of strings: {err}")),
+                ),
+            }
+        }
+        (_, Some("")) => (
+            "405 Method Not Allowed",
+            error_json("use POST with a JSON array of values"),
+        ),
+        (_, Some(segment)) if segment.starts_with('/') => (
+            "405 Method Not Allowed",
+            error_json("use GET for a single value"),
+        ),
+        _ => ("404 Not Found", error_json("no such endpoint")),
+    };
+
+    write!(
+        out,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    out.flush()?;
+    Ok((request, status))
+}
+
+fn serve(bind: &str, input: &InputArgs) {
+    let listener = TcpListener::bind(bind).unwrap_or_else(|err| {
+        eprintln!("Error listening on {bind}: {err}");
+        exit(2);
+    });
+    let reporter = Reporter::get();
+    if let Ok(addr) = listener.local_addr() {
+        reporter.summary(format_args!("Listening on http://{addr}"));
+    }
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    reporter.note(format_args!("Error accepting a connection: {err}"));
+                    continue;
+                }
+            };
+            scope.spawn(move || {
+                let peer = stream
+                    .peer_addr()
+                    .map_or_else(|_| "?".to_string(), |addr| addr.to_string());
+                match respond(&stream, input) {
+                    Ok((request, status)) => {
+                        reporter.note(format_args!("{peer}: {request}: {status}"))
+                    }
+                    Err(err) => reporter.note(format_args!("{peer}: {err}")),
+                }
+            });
+        }
+    });
+}
+
+#[doc(hidden)]
+fn main() {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let default = if cli.fix {
+        Command::Fix {
+            files: Files::default(),
+            input: InputArgs::default(),
+        }
+    } else {
+        Command::Validate {
+            files: Files::default(),
+            input: InputArgs::default(),
+            output: Output::default(),
+            follow: false,
+            format: Format::Lines,
+            column: None,
+            sheet: None,
+        }
+    };
+    if cli.fix && cli.command.is_some() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--fix cannot be used with a subcommand (use the fix subcommand)",
+            )
+            .exit();
+    }
+    let command = cli.command.unwrap_or(default);
+    if let Some(option) = cli.reporting.failure_option() {
+        if !command.reports_failures() {
+            let name = matches.subcommand_name().unwrap_or_default();
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    format!("{option} does not apply to {name} with these arguments"),
+                )
+                .exit();
+        }
+    }
+    Reporter::init(&cli.reporting);
+    match command {
+        Command::Validate {
+            files,
+            input,
+            output,
+            follow,
+            format,
+            column,
+            sheet,
+        } => {
+            let reading = match (format, &column) {
+                (Format::Parquet, Some(column)) => Reading::Parquet(column),
+                (Format::Xlsx, Some(column)) => Reading::Xlsx(column, sheet.as_deref()),
+                _ if follow => Reading::Follow,
+                _ => Reading::Lines,
+            };
+            validate(&files, &input, output, false, reading)
+        }
+        Command::Fix { files, input } => {
+            validate(&files, &input, Output::Text, true, Reading::Lines)
+        }
+        Command::Build {
+            first: Some(first),
+            issue_num,
+            input,
+            ..
+        } => build(&input, &first, issue_num.as_deref()),
+        Command::Build {
+            first: None,
+            column,
+            issuer_column,
+            issue_column,
+            input,
+            ..
+        } => {
+            let from = match (&column, &issuer_column, &issue_column) {
+                (Some(payload), _, _) => BuildFrom::PayloadColumn(payload),
+                (None, Some(issuer), Some(issue)) => BuildFrom::PartColumns(issuer, issue),
+                _ => BuildFrom::Lines,
+            };
+            build_all(&input, from)
+        }
+        Command::Explain { values, input } => explain(&input, &values),
+        Command::Gen {
+            count,
+            seed,
+            issuer,
+        } => gen(count, seed, issuer.as_deref()),
+        Command::Convert {
+            to_isin,
+            to_payload,
+            files,
+            input,
+            ..
+        } => {
+            let conversion = match to_isin.as_deref() {
+                Some(country) => Conversion::ToIsin(country),
+                None if to_payload => Conversion::ToPayload,
+                None => Conversion::FromIsin,
+            };
+            convert(&files, &input, conversion)
+        }
+        Command::CheckIsins { files, input } => check_isins(&files, &input),
+        Command::Normalize {
+            drop_invalid,
+            files,
+            mut input,
+        } => {
+            input.loose = true;
+            normalize(&files, &input, drop_invalid)
+        }
+        Command::Filter {
+            predicates,
+            files,
+            input,
+        } => filter(&files, &input, |cusip| predicates.matches(cusip)),
+        Command::Grep {
+            pattern,
+            invert_match,
+            files,
+            input,
+        } => filter(&files, &input, |cusip| {
+            pattern.matches(cusip) != invert_match
+        }),
+        Command::Annotate {
+            column,
+            detect_column: _,
+            delimiter,
+            files,
+            input,
+        } => annotate(&files, &input, column.as_deref(), delimiter),
+        Command::Join {
+            metadata,
+            column,
+            delimiter,
+            files,
+            mut input,
+        } => {
+            input.loose = true;
+            join(&files, &input, &metadata, &column, delimiter)
+        }
+        Command::Scan { files } => scan(&files),
+        Command::Sort {
+            chunk_size,
+            temp_dir,
+            files,
+            input,
+        } => sort(&files, &input, chunk_size, temp_dir),
+        Command::Split {
+            shards,
+            prefix,
+            files,
+            input,
+        } => split(&files, &input, shards, &prefix),
+        Command::Merge { files, input } => merge(&files, &input),
+        Command::Stats { files, input } => stats(&files, &input),
+        Command::TopIssuers {
+            count,
+            names,
+            files,
+            input,
+        } => top_issuers(&files, &input, count, names.as_deref()),
+        Command::Serve { bind, loose } => serve(
+            &bind,
+            &InputArgs {
+                loose,
+                ..InputArgs::default()
+            },
+        ),
+        Command::Completions { shell } => {
+            // generate() panics if it cannot write, so the script is written out here instead
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "cusip-tool", &mut script);
+            let mut out = Out::new();
+            out.line(String::from_utf8_lossy(&script).trim_end());
+            out.flush();
+        }
     }
 }
@@ -0,0 +1,231 @@
+#![warn(missing_docs)]
+//! # cusip::builder
+//!
+//! Building CUSIPs field by field, with the _Check Digit_ computed.
+//!
+//! `build_from_parts()` covers the simple case. A `CusipBuilder` also takes a CINS _Country Code_,
+//! which becomes the first character of the _Issuer Number_, and can require that the result be
+//! reserved for private use. `build()` checks every field and reports which one is wrong with a
+//! `BuildError`:
+//!
+//! ```
+//! use cusip::builder::{BuildError, CusipBuilder};
+//! use cusip::country::CinsCountryCode;
+//!
+//! let cusip = CusipBuilder::new()
+//!     .country(CinsCountryCode::Germany)
+//!     .issuer_num("0712D")
+//!     .issue_num("16")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(cusip.to_string(), "D0712D163");
+//!
+//! let internal = CusipBuilder::new()
+//!     .issuer_num("037833")
+//!     .issue_num("10")
+//!     .private_issue()
+//!     .build();
+//! assert!(matches!(internal, Err(BuildError::NotPrivateIssue(_))));
+//! ```
+
+use core::fmt;
+
+use crate::country::CinsCountryCode;
+use crate::payload::{IssueNum, IssuerNum, Payload};
+use crate::{CUSIPError, CUSIP};
+
+/// All the ways `CusipBuilder::build()` could fail.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// No _Issuer Number_ was given.
+    MissingIssuerNum,
+    /// No _Issue Number_ was given.
+    MissingIssueNum,
+    /// The _Issuer Number_ (including the _Country Code_, if one was given) is not valid.
+    IssuerNum(CUSIPError),
+    /// The _Issue Number_ is not valid.
+    IssueNum(CUSIPError),
+    /// A private _Issuer Number_ was required, but this one is not (see
+    /// `CUSIP::has_private_issuer()`).
+    NotPrivateIssuer(IssuerNum),
+    /// A private _Issue Number_ was required, but this one is not (see
+    /// `CUSIP::is_private_issue()`).
+    NotPrivateIssue(IssueNum),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingIssuerNum => f.write_str("no Issuer Number was given"),
+            BuildError::MissingIssueNum => f.write_str("no Issue Number was given"),
+            BuildError::IssuerNum(err) | BuildError::IssueNum(err) => write!(f, "{err}"),
+            BuildError::NotPrivateIssuer(issuer) => {
+                write!(
+                    f,
+                    "Issuer Number {:?} is not reserved for private use",
+                    issuer.as_str()
+                )
+            }
+            BuildError::NotPrivateIssue(issue) => {
+                write!(
+                    f,
+                    "Issue Number {:?} is not reserved for private use",
+                    issue.as_str()
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+/// Builds a `CUSIP` from its fields. See the module documentation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CusipBuilder<'a> {
+    issuer_num: Option<&'a str>,
+    issue_num: Option<&'a str>,
+    country: Option<CinsCountryCode>,
+    private_issuer: bool,
+    private_issue: bool,
+}
+
+impl<'a> CusipBuilder<'a> {
+    /// Constructs a builder with no fields set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the _Issuer Number_: 6 characters, or the 5 after the _Country Code_ if `country()` is
+    /// set.
+    pub fn issuer_num(mut self, issuer_num: &'a str) -> Self {
+        self.issuer_num = Some(issuer_num);
+        self
+    }
+
+    /// Sets the _Issue Number_: 2 characters.
+    pub fn issue_num(mut self, issue_num: &'a str) -> Self {
+        self.issue_num = Some(issue_num);
+        self
+    }
+
+    /// Builds a CINS identifier with this _Country Code_ as the first character of the _Issuer
+    /// Number_.
+    pub fn country(mut self, country: CinsCountryCode) -> Self {
+        self.country = Some(country);
+        self
+    }
+
+    /// Requires the _Issuer Number_ to be reserved for private use.
+    pub fn private_issuer(mut self) -> Self {
+        self.private_issuer = true;
+        self
+    }
+
+    /// Requires the _Issue Number_ to be reserved for private use.
+    pub fn private_issue(mut self) -> Self {
+        self.private_issue = true;
+        self
+    }
+
+    /// Checks the fields and builds the `CUSIP`, computing its _Check Digit_.
+    pub fn build(&self) -> Result<CUSIP, BuildError> {
+        let issuer_num = self.issuer_num.ok_or(BuildError::MissingIssuerNum)?;
+        let issuer = match self.country {
+            None => IssuerNum::parse(issuer_num),
+            Some(country) => {
+                let rest = issuer_num.as_bytes();
+                if rest.len() != 5 {
+                    Err(CUSIPError::InvalidIssuerNumLength {
+                        was: rest.len() + 1,
+                    })
+                } else {
+                    let mut bytes = [country as u8; 6];
+                    bytes[1..].copy_from_slice(rest);
+                    IssuerNum::from_bytes(&bytes)
+                }
+            }
+        }
+        .map_err(BuildError::IssuerNum)?;
+
+        let issue_num = self.issue_num.ok_or(BuildError::MissingIssueNum)?;
+        let issue = IssueNum::parse(issue_num).map_err(BuildError::IssueNum)?;
+
+        let cusip = Payload::from_parts(issuer, issue).to_cusip();
+        if self.private_issuer && !cusip.has_private_issuer() {
+            return Err(BuildError::NotPrivateIssuer(issuer));
+        }
+        if self.private_issue && !cusip.is_private_issue() {
+            return Err(BuildError::NotPrivateIssue(issue));
+        }
+        Ok(cusip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_like_build_from_parts() {
+        let cusip = CusipBuilder::new()
+            .issuer_num("09739D")
+            .issue_num("10")
+            .build();
+        assert_eq!(cusip.ok(), crate::build_from_parts("09739D", "10").ok());
+
+        let cins = CusipBuilder::new()
+            .country(CinsCountryCode::SouthAfrica)
+            .issuer_num("08000")
+            .issue_num("AA")
+            .build()
+            .unwrap();
+        assert_eq!(cins, CUSIP::parse("S08000AA9").unwrap());
+    }
+
+    #[test]
+    fn private_use() {
+        let builder = CusipBuilder::new().issuer_num("037833").issue_num("9A");
+        assert!(builder.private_issue().build().unwrap().is_private_issue());
+        assert_eq!(
+            builder.private_issuer().build(),
+            Err(BuildError::NotPrivateIssuer(
+                IssuerNum::parse("037833").unwrap()
+            ))
+        );
+        let private = builder.issuer_num("123990").private_issuer().build();
+        assert!(private.unwrap().has_private_issuer());
+    }
+
+    #[test]
+    fn field_errors() {
+        assert_eq!(
+            CusipBuilder::new().issue_num("10").build(),
+            Err(BuildError::MissingIssuerNum)
+        );
+        assert_eq!(
+            CusipBuilder::new().issuer_num("037833").build(),
+            Err(BuildError::MissingIssueNum)
+        );
+        assert_eq!(
+            CusipBuilder::new()
+                .country(CinsCountryCode::Germany)
+                .issuer_num("0712DX")
+                .issue_num("16")
+                .build(),
+            Err(BuildError::IssuerNum(CUSIPError::InvalidIssuerNumLength {
+                was: 7
+            }))
+        );
+        assert_eq!(
+            CusipBuilder::new()
+                .issuer_num("037833")
+                .issue_num("1a")
+                .build(),
+            Err(BuildError::IssueNum(CUSIPError::InvalidIssueNum {
+                was: *b"1a"
+            }))
+        );
+    }
+}
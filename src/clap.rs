@@ -0,0 +1,117 @@
+#![warn(missing_docs)]
+//! # cusip::clap
+//!
+//! Integration with [clap](https://crates.io/crates/clap) command line parsing (requires the
+//! `clap` feature).
+//!
+//! `CUSIP` implements `ValueParserFactory`, so an argument of type `CUSIP` is validated by clap
+//! itself, and a bad value is reported with this crate's diagnostic:
+//!
+//! ```
+//! use clap::{value_parser, Arg, Command};
+//! use cusip::CUSIP;
+//!
+//! let cmd = Command::new("lookup").arg(
+//!     Arg::new("cusip")
+//!         .long("cusip")
+//!         .value_parser(value_parser!(CUSIP)),
+//! );
+//!
+//! let matches = cmd.clone().try_get_matches_from(["lookup", "--cusip", "037833100"]).unwrap();
+//! let cusip: &CUSIP = matches.get_one("cusip").unwrap();
+//! assert_eq!(cusip.to_string(), "037833100");
+//!
+//! let err = cmd.try_get_matches_from(["lookup", "--cusip", "037833101"]).unwrap_err();
+//! assert!(err.to_string().contains("incorrect Check Digit '1' when expecting '0'"));
+//! ```
+
+use std::ffi::OsStr;
+
+use ::clap::builder::{StringValueParser, TypedValueParser, ValueParserFactory};
+use ::clap::{Arg, Command, Error};
+
+use crate::CUSIP;
+
+/// A clap `TypedValueParser` producing a `CUSIP`.
+///
+/// By default values are parsed with `CUSIP::parse_loose`, the same as the `FromStr`
+/// implementation. Use `strict()` to require the exact canonical form via `CUSIP::parse`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CUSIPValueParser {
+    strict: bool,
+}
+
+impl CUSIPValueParser {
+    /// Constructs a parser that accepts leading or trailing whitespace and lowercase letters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a parser that requires the value to be exactly nine uppercase alphanumeric
+    /// characters.
+    pub fn strict(self) -> Self {
+        Self { strict: true }
+    }
+}
+
+impl TypedValueParser for CUSIPValueParser {
+    type Value = CUSIP;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, Error> {
+        let strict = self.strict;
+        StringValueParser::new()
+            .try_map(move |s| {
+                if strict {
+                    CUSIP::parse(&s)
+                } else {
+                    CUSIP::parse_loose(&s)
+                }
+            })
+            .parse_ref(cmd, arg, value)
+    }
+}
+
+impl ValueParserFactory for CUSIP {
+    type Parser = CUSIPValueParser;
+
+    fn value_parser() -> Self::Parser {
+        CUSIPValueParser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::clap::error::ErrorKind;
+    use ::clap::value_parser;
+
+    fn command(parser: CUSIPValueParser) -> Command {
+        Command::new("test").arg(Arg::new("cusip").value_parser(parser))
+    }
+
+    #[test]
+    fn loose_by_default() {
+        let matches = Command::new("test")
+            .arg(Arg::new("cusip").value_parser(value_parser!(CUSIP)))
+            .try_get_matches_from(["test", " 09739d100 "])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<CUSIP>("cusip"),
+            Some(&CUSIP::parse("09739D100").unwrap())
+        );
+    }
+
+    #[test]
+    fn strict_rejects_lowercase() {
+        let err = command(CUSIPValueParser::new().strict())
+            .try_get_matches_from(["test", "09739d100"])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ValueValidation);
+        assert!(err.to_string().contains("Issuer Number"));
+    }
+}
@@ -0,0 +1,353 @@
+#![warn(missing_docs)]
+//! # cusip::column
+//!
+//! Columnar storage for many CUSIPs, with bulk operations.
+//!
+//! A `CusipColumn` stores its CUSIPs contiguously, 9 bytes each with no padding or separators, so
+//! the whole column can be handed to batch kernels as one `&[u8]` with `as_bytes()`. Raw columns
+//! from elsewhere (fixed-width files, network buffers, other columnar formats) can be validated in
+//! bulk with `validate_raw()` or loaded with `CusipColumn::try_from_raw()`. Columns can also be
+//! converted to and from packed `u64` values (see `CUSIP::pack()`).
+//!
+//! ```
+//! use cusip::column::CusipColumn;
+//!
+//! let mut column = CusipColumn::try_from_raw(b"S08000AA9037833100594918104".to_vec()).unwrap();
+//! assert_eq!(column.len(), 3);
+//!
+//! column.sort();
+//! assert_eq!(column.as_bytes(), b"037833100594918104S08000AA9");
+//!
+//! let packed = column.pack();
+//! assert_eq!(CusipColumn::unpack(&packed), Some(column));
+//! ```
+//!
+//! With the `arrow` feature, a column converts to an Arrow `FixedSizeBinaryArray` of 9-byte values
+//! (sharing the layout of `as_bytes()`) or a `StringArray`, and loads from either with `TryFrom`,
+//! validating every value. Nulls are rejected, as they are not CUSIPs.
+
+use std::error::Error;
+use std::fmt;
+use std::slice;
+#[cfg(feature = "arrow")]
+use std::str::from_utf8_unchecked;
+
+#[cfg(feature = "arrow")]
+use arrow::array::{FixedSizeBinaryArray, StringArray};
+#[cfg(feature = "arrow")]
+use arrow::buffer::Buffer;
+
+use crate::{CUSIPError, CUSIP};
+
+/// A contiguous column of CUSIPs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CusipColumn {
+    // CUSIP is a transparent wrapper around [u8; 9], so this is 9 bytes per CUSIP, contiguously
+    cusips: Vec<CUSIP>,
+}
+
+/// All the ways loading a raw column could fail.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColumnError {
+    /// The length of the raw column is not a multiple of 9 bytes.
+    RaggedLength {
+        /// The length we found
+        was: usize,
+    },
+    /// One of the values in an Arrow array is null.
+    Null {
+        /// The (zero-based) position of the first null value
+        index: usize,
+    },
+    /// One of the values in the raw column is not a valid CUSIP.
+    Invalid {
+        /// The (zero-based) position of the first invalid value
+        index: usize,
+        /// Why the value is invalid
+        error: CUSIPError,
+    },
+}
+
+impl fmt::Display for ColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnError::RaggedLength { was } => {
+                write!(f, "column length {was} bytes is not a multiple of 9")
+            }
+            ColumnError::Null { index } => write!(f, "value {index}: null"),
+            ColumnError::Invalid { index, error } => write!(f, "value {index}: {error}"),
+        }
+    }
+}
+
+impl Error for ColumnError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ColumnError::RaggedLength { .. } | ColumnError::Null { .. } => None,
+            ColumnError::Invalid { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Validates each 9-byte value of a raw column, in order. A trailing partial value (if the length
+/// is not a multiple of 9) is reported as an `InvalidCUSIPLength` error.
+pub fn validate_raw(bytes: &[u8]) -> impl Iterator<Item = Result<CUSIP, CUSIPError>> + '_ {
+    let chunks = bytes.chunks_exact(9);
+    let remainder = chunks.remainder();
+    chunks
+        .map(CUSIP::from_bytes)
+        .chain((!remainder.is_empty()).then(|| CUSIP::from_bytes(remainder)))
+}
+
+impl CusipColumn {
+    /// Constructs an empty column.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs an empty column with room for `capacity` CUSIPs.
+    pub fn with_capacity(capacity: usize) -> Self {
+        CusipColumn {
+            cusips: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Loads a column from raw bytes, 9 per CUSIP, validating every value.
+    #[allow(clippy::manual_is_multiple_of)] // usize::is_multiple_of() needs Rust 1.87
+    pub fn try_from_raw(bytes: Vec<u8>) -> Result<Self, ColumnError> {
+        if bytes.len() % 9 != 0 {
+            return Err(ColumnError::RaggedLength { was: bytes.len() });
+        }
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = validate_raw(&bytes)
+            .enumerate()
+            .map(|(index, result)| result.map_err(|error| ColumnError::Invalid { index, error }))
+            .collect::<Result<Vec<_>, _>>();
+        #[cfg(feature = "metrics")]
+        {
+            match &result {
+                Ok(cusips) => crate::metrics::record_successes(cusips.len()),
+                Err(ColumnError::Invalid { index, error }) => {
+                    crate::metrics::record_successes(*index);
+                    crate::metrics::record_failure(error);
+                }
+                Err(_) => {}
+            }
+            crate::metrics::record_batch("column", bytes.len() / 9, started);
+        }
+        Ok(CusipColumn { cusips: result? })
+    }
+
+    /// Returns the number of CUSIPs in the column.
+    pub fn len(&self) -> usize {
+        self.cusips.len()
+    }
+
+    /// Returns true if the column holds no CUSIPs.
+    pub fn is_empty(&self) -> bool {
+        self.cusips.is_empty()
+    }
+
+    /// Appends the CUSIP to the column.
+    pub fn push(&mut self, cusip: CUSIP) {
+        self.cusips.push(cusip);
+    }
+
+    /// Returns the CUSIP at the (zero-based) index, if there is one.
+    pub fn get(&self, index: usize) -> Option<&CUSIP> {
+        self.cusips.get(index)
+    }
+
+    /// Returns the CUSIPs in the column.
+    pub fn as_slice(&self) -> &[CUSIP] {
+        &self.cusips
+    }
+
+    /// Returns the whole column as raw bytes, 9 per CUSIP.
+    pub fn as_bytes(&self) -> &[u8] {
+        // This is safe because CUSIP is a transparent wrapper around [u8; 9], so a slice of them
+        // is a slice of 9 times as many bytes
+        unsafe { slice::from_raw_parts(self.cusips.as_ptr().cast::<u8>(), self.cusips.len() * 9) }
+    }
+
+    /// Returns an iterator over the CUSIPs in the column.
+    pub fn iter(&self) -> slice::Iter<'_, CUSIP> {
+        self.cusips.iter()
+    }
+
+    /// Sorts the column in ascending order.
+    pub fn sort(&mut self) {
+        self.cusips.sort_unstable();
+    }
+
+    /// Removes consecutive repeated CUSIPs (all repeats, if the column is sorted).
+    pub fn dedup(&mut self) {
+        self.cusips.dedup();
+    }
+
+    /// Packs every CUSIP in the column into a `u64` with `CUSIP::pack()`.
+    pub fn pack(&self) -> Vec<u64> {
+        self.cusips.iter().map(CUSIP::pack).collect()
+    }
+
+    /// Builds a column from packed values, recomputing each _Check Digit_. Returns `None` if any
+    /// of the values could not have been produced by `CUSIP::pack()`.
+    pub fn unpack(packed: &[u64]) -> Option<Self> {
+        let cusips = packed
+            .iter()
+            .map(|&p| CUSIP::unpack(p))
+            .collect::<Option<_>>()?;
+        Some(CusipColumn { cusips })
+    }
+}
+
+impl From<Vec<CUSIP>> for CusipColumn {
+    fn from(cusips: Vec<CUSIP>) -> Self {
+        CusipColumn { cusips }
+    }
+}
+
+impl From<CusipColumn> for Vec<CUSIP> {
+    fn from(column: CusipColumn) -> Self {
+        column.cusips
+    }
+}
+
+impl Extend<CUSIP> for CusipColumn {
+    fn extend<I: IntoIterator<Item = CUSIP>>(&mut self, iter: I) {
+        self.cusips.extend(iter);
+    }
+}
+
+impl FromIterator<CUSIP> for CusipColumn {
+    fn from_iter<I: IntoIterator<Item = CUSIP>>(iter: I) -> Self {
+        CusipColumn {
+            cusips: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a CusipColumn {
+    type Item = &'a CUSIP;
+    type IntoIter = slice::Iter<'a, CUSIP>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<&CusipColumn> for FixedSizeBinaryArray {
+    fn from(column: &CusipColumn) -> Self {
+        FixedSizeBinaryArray::new(9, Buffer::from(column.as_bytes()), None)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<&CusipColumn> for StringArray {
+    fn from(column: &CusipColumn) -> Self {
+        // This is safe because we know each CUSIP is ASCII
+        StringArray::from_iter_values(column.iter().map(|c| unsafe { from_utf8_unchecked(&c.0) }))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl TryFrom<&FixedSizeBinaryArray> for CusipColumn {
+    type Error = ColumnError;
+
+    fn try_from(array: &FixedSizeBinaryArray) -> Result<Self, Self::Error> {
+        array
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let value = value.ok_or(ColumnError::Null { index })?;
+                CUSIP::from_bytes(value).map_err(|error| ColumnError::Invalid { index, error })
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl TryFrom<&StringArray> for CusipColumn {
+    type Error = ColumnError;
+
+    fn try_from(array: &StringArray) -> Result<Self, Self::Error> {
+        array
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let value = value.ok_or(ColumnError::Null { index })?;
+                CUSIP::parse(value).map_err(|error| ColumnError::Invalid { index, error })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_raw_values() {
+        let results: Vec<_> = validate_raw(b"037833100037833101S0800").collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(CUSIPError::IncorrectCheckDigit { .. })
+        ));
+        assert_eq!(results[2], Err(CUSIPError::InvalidCUSIPLength { was: 5 }));
+    }
+
+    #[test]
+    fn reject_raw_columns() {
+        assert_eq!(
+            CusipColumn::try_from_raw(b"0378331000".to_vec()),
+            Err(ColumnError::RaggedLength { was: 10 })
+        );
+        assert!(matches!(
+            CusipColumn::try_from_raw(b"037833100037833101".to_vec()),
+            Err(ColumnError::Invalid { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn bytes_match_cusips() {
+        let mut column: CusipColumn = ["594918104", "037833100", "594918104"]
+            .iter()
+            .map(|s| CUSIP::parse(s).unwrap())
+            .collect();
+        column.sort();
+        column.dedup();
+        assert_eq!(column.as_bytes(), b"037833100594918104");
+        assert_eq!(column.get(1).unwrap().to_string(), "594918104");
+        assert_eq!(CusipColumn::unpack(&[u64::MAX]), None);
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn arrow_round_trip() {
+        let column = CusipColumn::try_from_raw(b"037833100S08000AA9".to_vec()).unwrap();
+
+        let binary = FixedSizeBinaryArray::from(&column);
+        assert_eq!(binary.value(1), b"S08000AA9");
+        assert_eq!(CusipColumn::try_from(&binary), Ok(column.clone()));
+
+        let strings = StringArray::from(&column);
+        assert_eq!(strings.value(0), "037833100");
+        assert_eq!(CusipColumn::try_from(&strings), Ok(column));
+
+        let with_null = StringArray::from(vec![Some("037833100"), None]);
+        assert_eq!(
+            CusipColumn::try_from(&with_null),
+            Err(ColumnError::Null { index: 1 })
+        );
+        let invalid = StringArray::from(vec!["037833100", "037833101"]);
+        assert!(matches!(
+            CusipColumn::try_from(&invalid),
+            Err(ColumnError::Invalid { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,179 @@
+#![warn(missing_docs)]
+//! # cusip::concurrent_set
+//!
+//! A set of CUSIPs that many threads can insert into and query at once.
+//!
+//! `ConcurrentCusipSet` splits its contents across independently locked shards, chosen by a hash
+//! of the CUSIP, so threads only contend when they touch the same shard. Each CUSIP is stored as
+//! its packed `u64` (see `CUSIP::pack()`). This suits deduplicating identifiers while ingesting
+//! several files in parallel.
+//!
+//! ```
+//! use cusip::concurrent_set::ConcurrentCusipSet;
+//! use cusip::CUSIP;
+//!
+//! let set = ConcurrentCusipSet::new();
+//! let files = [["037833100", "594918104"], ["594918104", "09739D100"]];
+//!
+//! std::thread::scope(|scope| {
+//!     for file in &files {
+//!         let set = &set;
+//!         scope.spawn(move || {
+//!             for s in file {
+//!                 set.insert(CUSIP::parse(s).unwrap());
+//!             }
+//!         });
+//!     }
+//! });
+//!
+//! assert_eq!(set.len(), 3);
+//! assert!(set.contains(&CUSIP::parse("09739D100").unwrap()));
+//! ```
+
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::CUSIP;
+
+/// The number of shards `ConcurrentCusipSet::new()` uses.
+pub const DEFAULT_SHARDS: usize = 64;
+
+/// A thread-safe set of CUSIPs, sharded to reduce lock contention.
+#[derive(Debug)]
+pub struct ConcurrentCusipSet {
+    shards: Box<[Mutex<HashSet<u64>>]>,
+}
+
+impl Default for ConcurrentCusipSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConcurrentCusipSet {
+    /// Constructs an empty set with `DEFAULT_SHARDS` shards.
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Constructs an empty set with the given number of shards (at least one).
+    pub fn with_shards(shards: usize) -> Self {
+        ConcurrentCusipSet {
+            shards: (0..shards.max(1)).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    fn shard(&self, packed: u64) -> MutexGuard<'_, HashSet<u64>> {
+        // Consecutive packed values (issues of one issuer) should land in different shards, so
+        // mix the bits before picking one
+        let mixed = packed.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+        lock(&self.shards[(mixed % self.shards.len() as u64) as usize])
+    }
+
+    /// Adds the CUSIP to the set, returning true if it was not already present.
+    pub fn insert(&self, cusip: CUSIP) -> bool {
+        let packed = cusip.pack();
+        self.shard(packed).insert(packed)
+    }
+
+    /// Removes the CUSIP from the set, returning true if it was present.
+    pub fn remove(&self, cusip: &CUSIP) -> bool {
+        let packed = cusip.pack();
+        self.shard(packed).remove(&packed)
+    }
+
+    /// Returns true if the set contains the CUSIP.
+    pub fn contains(&self, cusip: &CUSIP) -> bool {
+        let packed = cusip.pack();
+        self.shard(packed).contains(&packed)
+    }
+
+    /// Returns the number of CUSIPs in the set. Concurrent inserts and removes may or may not be
+    /// counted.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    /// Returns true if the set holds no CUSIPs.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| lock(shard).is_empty())
+    }
+
+    /// Consumes the set, returning its CUSIPs in ascending order.
+    pub fn into_sorted_vec(self) -> Vec<CUSIP> {
+        let mut packed: Vec<u64> = self
+            .shards
+            .into_vec()
+            .into_iter()
+            .flat_map(|shard| shard.into_inner().unwrap_or_else(|p| p.into_inner()))
+            .collect();
+        // Packing preserves order, so sorting the packed values sorts the CUSIPs
+        packed.sort_unstable();
+        packed
+            .into_iter()
+            .map(|p| CUSIP::unpack(p).expect("only packed CUSIPs are stored"))
+            .collect()
+    }
+}
+
+/// Locks a shard. A thread that panicked while holding the lock cannot have left the `HashSet` in
+/// a state that matters here, so this carries on past poisoning.
+fn lock(shard: &Mutex<HashSet<u64>>) -> MutexGuard<'_, HashSet<u64>> {
+    shard
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Extend<CUSIP> for ConcurrentCusipSet {
+    fn extend<I: IntoIterator<Item = CUSIP>>(&mut self, iter: I) {
+        for cusip in iter {
+            self.insert(cusip);
+        }
+    }
+}
+
+impl FromIterator<CUSIP> for ConcurrentCusipSet {
+    fn from_iter<I: IntoIterator<Item = CUSIP>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_from_parts;
+
+    #[test]
+    fn parallel_inserts_deduplicate() {
+        let set = ConcurrentCusipSet::with_shards(4);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for n in 0..100 {
+                        set.insert(build_from_parts("037833", &format!("{n:02}")).unwrap());
+                    }
+                });
+            }
+        });
+        assert_eq!(set.len(), 100);
+
+        let sorted = set.into_sorted_vec();
+        assert_eq!(sorted.len(), 100);
+        assert!(sorted.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let apple = CUSIP::parse("037833100").unwrap();
+        let set = ConcurrentCusipSet::with_shards(0);
+        assert!(set.is_empty());
+        assert!(set.insert(apple));
+        assert!(!set.insert(apple));
+        assert!(set.contains(&apple));
+        assert!(set.remove(&apple));
+        assert!(!set.remove(&apple));
+        assert!(set.is_empty());
+    }
+}
@@ -0,0 +1,308 @@
+#![warn(missing_docs)]
+//! # cusip::country
+//!
+//! Classifying CUSIPs by where their _Issuer_ is domiciled.
+//!
+//! `CUSIP::domicile()` returns a `Domicile`: either North American (a conventional CUSIP) or CINS
+//! with the `CinsCountryCode` from its first character, so routing logic can be a single `match`.
+//!
+//! ```
+//! use cusip::country::{CinsCountryCode, Domicile};
+//! use cusip::CUSIP;
+//!
+//! let route = |cusip: &str| match CUSIP::parse(cusip).unwrap().domicile() {
+//!     Domicile::NorthAmerica => "domestic desk",
+//!     Domicile::Cins(CinsCountryCode::UnitedKingdom) => "London desk",
+//!     Domicile::Cins(_) => "international desk",
+//! };
+//! assert_eq!(route("037833100"), "domestic desk");
+//! assert_eq!(route("G1151C101"), "London desk");
+//! assert_eq!(route("S08000AA9"), "international desk");
+//! ```
+//!
+//! `CUSIP::is_cins_for()` checks for a single country code, and `CUSIP::region()` groups
+//! domiciles into broad `Region`s. `in_country()` and `in_region()` filter any collection or
+//! iterator of CUSIPs:
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//!
+//! use cusip::country::{in_region, Region};
+//! use cusip::CUSIP;
+//!
+//! let held: BTreeSet<CUSIP> = ["037833100", "G1151C101", "S08000AA9", "D0712D163"]
+//!     .iter()
+//!     .map(|s| CUSIP::parse(s).unwrap())
+//!     .collect();
+//! let europe: Vec<String> = in_region(&held, Region::Europe)
+//!     .map(|cusip| cusip.to_string())
+//!     .collect();
+//! assert_eq!(europe, ["D0712D163", "G1151C101"]);
+//! ```
+
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::CUSIP;
+
+/// A CINS _Country Code_: the letter that starts a CINS identifier, identifying the country or
+/// geographic region of the _Issuer_.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum CinsCountryCode {
+    /// `A`
+    Austria = b'A',
+    /// `B`
+    Belgium = b'B',
+    /// `C`
+    Canada = b'C',
+    /// `D`
+    Germany = b'D',
+    /// `E`
+    Spain = b'E',
+    /// `F`
+    France = b'F',
+    /// `G`
+    UnitedKingdom = b'G',
+    /// `H`
+    Switzerland = b'H',
+    /// `I` (unused by The Standard)
+    UnusedI = b'I',
+    /// `J`
+    Japan = b'J',
+    /// `K`
+    Denmark = b'K',
+    /// `L`
+    Luxembourg = b'L',
+    /// `M`
+    MiddleEast = b'M',
+    /// `N`
+    Netherlands = b'N',
+    /// `O` (unused by The Standard)
+    UnusedO = b'O',
+    /// `P`
+    SouthAmerica = b'P',
+    /// `Q`
+    Australia = b'Q',
+    /// `R`
+    Norway = b'R',
+    /// `S`
+    SouthAfrica = b'S',
+    /// `T`
+    Italy = b'T',
+    /// `U`
+    UnitedStates = b'U',
+    /// `V`
+    AfricaOther = b'V',
+    /// `W`
+    Sweden = b'W',
+    /// `X`
+    EuropeOther = b'X',
+    /// `Y`
+    Asia = b'Y',
+    /// `Z` (unused by The Standard)
+    UnusedZ = b'Z',
+}
+
+/// Every `CinsCountryCode`, in alphabetical order of letter.
+const ALL: [CinsCountryCode; 26] = {
+    use CinsCountryCode::*;
+    [
+        Austria,
+        Belgium,
+        Canada,
+        Germany,
+        Spain,
+        France,
+        UnitedKingdom,
+        Switzerland,
+        UnusedI,
+        Japan,
+        Denmark,
+        Luxembourg,
+        MiddleEast,
+        Netherlands,
+        UnusedO,
+        SouthAmerica,
+        Australia,
+        Norway,
+        SouthAfrica,
+        Italy,
+        UnitedStates,
+        AfricaOther,
+        Sweden,
+        EuropeOther,
+        Asia,
+        UnusedZ,
+    ]
+};
+
+impl CinsCountryCode {
+    /// Returns the country code for the letter, or `None` if it is not an uppercase ASCII letter.
+    pub fn from_char(c: char) -> Option<CinsCountryCode> {
+        c.is_ascii_uppercase()
+            .then(|| ALL[usize::from(c as u8 - b'A')])
+    }
+
+    /// Returns the letter.
+    pub fn as_char(self) -> char {
+        char::from(self as u8)
+    }
+
+    /// Returns true for `I`, `O` and `Z`, which The Standard leaves unused.
+    pub fn is_unused(self) -> bool {
+        matches!(
+            self,
+            CinsCountryCode::UnusedI | CinsCountryCode::UnusedO | CinsCountryCode::UnusedZ
+        )
+    }
+
+    /// Returns the broad region of the country code, or `None` for an unused one.
+    pub fn region(self) -> Option<Region> {
+        use CinsCountryCode::*;
+        match self {
+            Canada | UnitedStates => Some(Region::NorthAmerica),
+            SouthAmerica => Some(Region::SouthAmerica),
+            Austria | Belgium | Germany | Spain | France | UnitedKingdom | Switzerland
+            | Denmark | Luxembourg | Netherlands | Norway | Italy | Sweden | EuropeOther => {
+                Some(Region::Europe)
+            }
+            MiddleEast | SouthAfrica | AfricaOther => Some(Region::MiddleEastAndAfrica),
+            Japan | Australia | Asia => Some(Region::AsiaPacific),
+            UnusedI | UnusedO | UnusedZ => None,
+        }
+    }
+
+    /// Returns the name of the country or region, as The Standard gives it.
+    pub fn region_name(self) -> &'static str {
+        use CinsCountryCode::*;
+        match self {
+            Austria => "Austria",
+            Belgium => "Belgium",
+            Canada => "Canada",
+            Germany => "Germany",
+            Spain => "Spain",
+            France => "France",
+            UnitedKingdom => "United Kingdom",
+            Switzerland => "Switzerland",
+            UnusedI | UnusedO | UnusedZ => "(Unused)",
+            Japan => "Japan",
+            Denmark => "Denmark",
+            Luxembourg => "Luxembourg",
+            MiddleEast => "Mid-East",
+            Netherlands => "Netherlands",
+            SouthAmerica => "South America",
+            Australia => "Australia",
+            Norway => "Norway",
+            SouthAfrica => "South Africa",
+            Italy => "Italy",
+            UnitedStates => "United States",
+            AfricaOther => "Africa - Other",
+            Sweden => "Sweden",
+            EuropeOther => "Europe - Other",
+            Asia => "Asia",
+        }
+    }
+}
+
+impl fmt::Display for CinsCountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.region_name())
+    }
+}
+
+/// Where the _Issuer_ of a CUSIP is domiciled, as far as the CUSIP itself tells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Domicile {
+    /// A conventional (non-CINS) CUSIP, assigned to a North American _Issuer_.
+    NorthAmerica,
+    /// A CINS identifier, with its _Country Code_.
+    Cins(CinsCountryCode),
+}
+
+impl Domicile {
+    /// Returns the broad region of the domicile, or `None` for an unused CINS country code.
+    pub fn region(self) -> Option<Region> {
+        match self {
+            Domicile::NorthAmerica => Some(Region::NorthAmerica),
+            Domicile::Cins(code) => code.region(),
+        }
+    }
+}
+
+/// A broad grouping of domiciles, for regional reporting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Region {
+    /// Conventional CUSIPs, and CINS country codes `C` (Canada) and `U` (United States).
+    NorthAmerica,
+    /// CINS country code `P`.
+    SouthAmerica,
+    /// CINS country codes `A`, `B`, `D`, `E`, `F`, `G`, `H`, `K`, `L`, `N`, `R`, `T`, `W` and `X`.
+    Europe,
+    /// CINS country codes `M` (Mid-East), `S` (South Africa) and `V` (Africa - Other).
+    MiddleEastAndAfrica,
+    /// CINS country codes `J` (Japan), `Q` (Australia) and `Y` (Asia).
+    AsiaPacific,
+}
+
+/// Returns an iterator over the CINS identifiers with the country code.
+pub fn in_country<I>(cusips: I, code: CinsCountryCode) -> impl Iterator<Item = I::Item>
+where
+    I: IntoIterator,
+    I::Item: Borrow<CUSIP>,
+{
+    cusips
+        .into_iter()
+        .filter(move |cusip| cusip.borrow().is_cins_for(code))
+}
+
+/// Returns an iterator over the CUSIPs whose domicile is in the region.
+pub fn in_region<I>(cusips: I, region: Region) -> impl Iterator<Item = I::Item>
+where
+    I: IntoIterator,
+    I::Item: Borrow<CUSIP>,
+{
+    cusips
+        .into_iter()
+        .filter(move |cusip| cusip.borrow().region() == Some(region))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_round_trip() {
+        for (i, code) in ALL.iter().enumerate() {
+            assert_eq!(code.as_char(), char::from(b'A' + i as u8));
+            assert_eq!(CinsCountryCode::from_char(code.as_char()), Some(*code));
+        }
+        assert_eq!(CinsCountryCode::from_char('a'), None);
+        assert_eq!(CinsCountryCode::from_char('0'), None);
+        assert_eq!(ALL.iter().filter(|code| code.is_unused()).count(), 3);
+    }
+
+    #[test]
+    fn regions_and_filters() {
+        for code in ALL {
+            assert_eq!(code.region().is_none(), code.is_unused());
+        }
+        let cusips: Vec<CUSIP> = ["037833100", "U00000007", "I08000AA0", "S08000AA9"]
+            .iter()
+            .map(|s| CUSIP::parse(s).unwrap())
+            .collect();
+        assert_eq!(in_region(&cusips, Region::NorthAmerica).count(), 2);
+        assert_eq!(
+            in_region(cusips.iter(), Region::MiddleEastAndAfrica).count(),
+            1
+        );
+        assert_eq!(
+            in_country(cusips.clone(), CinsCountryCode::UnusedI).count(),
+            1
+        );
+        assert_eq!(
+            in_country(&cusips, CinsCountryCode::UnitedStates).count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! # cusip::csv
+//!
+//! Minimal internal support for reading delimited text, shared by the loaders in this crate.
+
+/// Splits one record of delimited text into its fields. Fields may be enclosed in double quotes,
+/// in which case they may contain the delimiter, and a doubled double quote stands for a literal
+/// one. Records spanning multiple lines are not supported.
+pub(crate) fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' && field.is_empty() {
+            in_quotes = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields() {
+        assert_eq!(split_record("a,b,,c", ','), ["a", "b", "", "c"]);
+        assert_eq!(split_record("a\tb", '\t'), ["a", "b"]);
+        assert_eq!(split_record("", ','), [""]);
+    }
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(
+            split_record(r#""Apple, Inc.",037833,"say ""hi""""#, ','),
+            ["Apple, Inc.", "037833", r#"say "hi""#]
+        );
+    }
+}
@@ -0,0 +1,230 @@
+#![warn(missing_docs)]
+//! # cusip::datafusion
+//!
+//! Scalar UDFs for validating and decomposing CUSIPs in
+//! [DataFusion](https://datafusion.apache.org/) SQL queries (requires the `datafusion` feature).
+//!
+//! Register them all on a `SessionContext` with `register_udfs()`:
+//!
+//! * `cusip_valid(text)`: Whether the value is a valid CUSIP (as for `validate()`).
+//! * `cusip_issuer(text)`: The _Issuer Number_ of a valid CUSIP.
+//! * `cusip_is_cins(text)`: Whether a valid CUSIP is a CINS identifier.
+//! * `cusip_country(text)`: The CINS _Country Code_ of a valid CINS identifier.
+//!
+//! The functions accept any string type (`Utf8`, `LargeUtf8` or `Utf8View`). Each returns NULL
+//! for a NULL input, and all except `cusip_valid` also return NULL for an input that is not a
+//! valid CUSIP (or, for `cusip_country`, not a CINS identifier).
+//!
+//! ```no_run
+//! use datafusion::prelude::SessionContext;
+//!
+//! # async fn example() -> datafusion::error::Result<()> {
+//! let ctx = SessionContext::new();
+//! cusip::datafusion::register_udfs(&ctx);
+//! let df = ctx
+//!     .sql("SELECT cusip, cusip_issuer(cusip) FROM positions WHERE NOT cusip_valid(cusip)")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use ::datafusion::arrow::array::{ArrayRef, BooleanArray, StringArray};
+use ::datafusion::arrow::compute::cast;
+use ::datafusion::arrow::datatypes::DataType;
+use ::datafusion::common::cast::as_string_array;
+use ::datafusion::common::{Result, ScalarValue};
+use ::datafusion::logical_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility,
+};
+use ::datafusion::prelude::SessionContext;
+
+use crate::{validate, CUSIP};
+
+/// Which of the UDFs a `CusipUdf` implements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+    Valid,
+    Issuer,
+    IsCins,
+    Country,
+}
+
+/// The shared implementation of all the UDFs in this module.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct CusipUdf {
+    kind: Kind,
+    signature: Signature,
+}
+
+impl CusipUdf {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            signature: Signature::string(1, Volatility::Immutable),
+        }
+    }
+
+    /// Computes the output array for an array of input strings.
+    fn evaluate(&self, strings: &StringArray) -> ArrayRef {
+        let parsed = || strings.iter().map(|s| s.map(CUSIP::parse));
+        match self.kind {
+            Kind::Valid => Arc::new(
+                strings
+                    .iter()
+                    .map(|s| s.map(validate))
+                    .collect::<BooleanArray>(),
+            ),
+            Kind::Issuer => Arc::new(
+                parsed()
+                    .map(|r| r.and_then(Result::ok))
+                    .map(|c| c.map(|c| c.issuer_num().to_string()))
+                    .collect::<StringArray>(),
+            ),
+            Kind::IsCins => Arc::new(
+                parsed()
+                    .map(|r| r.and_then(Result::ok).map(|c| c.is_cins()))
+                    .collect::<BooleanArray>(),
+            ),
+            Kind::Country => Arc::new(
+                parsed()
+                    .map(|r| r.and_then(Result::ok))
+                    .map(|c| {
+                        c.and_then(|c| c.as_cins().map(|cins| cins.country_code().to_string()))
+                    })
+                    .collect::<StringArray>(),
+            ),
+        }
+    }
+}
+
+impl ScalarUDFImpl for CusipUdf {
+    fn name(&self) -> &str {
+        match self.kind {
+            Kind::Valid => "cusip_valid",
+            Kind::Issuer => "cusip_issuer",
+            Kind::IsCins => "cusip_is_cins",
+            Kind::Country => "cusip_country",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(match self.kind {
+            Kind::Valid | Kind::IsCins => DataType::Boolean,
+            Kind::Issuer | Kind::Country => DataType::Utf8,
+        })
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let scalar_input = matches!(args.args[0], ColumnarValue::Scalar(_));
+        let arrays = ColumnarValue::values_to_arrays(&args.args)?;
+        let strings = cast(&arrays[0], &DataType::Utf8)?;
+        let result = self.evaluate(as_string_array(&strings)?);
+        if scalar_input {
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &result, 0,
+            )?))
+        } else {
+            Ok(ColumnarValue::Array(result))
+        }
+    }
+}
+
+/// Returns the `cusip_valid` UDF.
+pub fn cusip_valid() -> ScalarUDF {
+    ScalarUDF::new_from_impl(CusipUdf::new(Kind::Valid))
+}
+
+/// Returns the `cusip_issuer` UDF.
+pub fn cusip_issuer() -> ScalarUDF {
+    ScalarUDF::new_from_impl(CusipUdf::new(Kind::Issuer))
+}
+
+/// Returns the `cusip_is_cins` UDF.
+pub fn cusip_is_cins() -> ScalarUDF {
+    ScalarUDF::new_from_impl(CusipUdf::new(Kind::IsCins))
+}
+
+/// Returns the `cusip_country` UDF.
+pub fn cusip_country() -> ScalarUDF {
+    ScalarUDF::new_from_impl(CusipUdf::new(Kind::Country))
+}
+
+/// Returns all the UDFs in this module.
+pub fn udfs() -> Vec<ScalarUDF> {
+    vec![
+        cusip_valid(),
+        cusip_issuer(),
+        cusip_is_cins(),
+        cusip_country(),
+    ]
+}
+
+/// Registers all the UDFs in this module on the context.
+pub fn register_udfs(ctx: &SessionContext) {
+    for udf in udfs() {
+        ctx.register_udf(udf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::datafusion::arrow::array::RecordBatch;
+    use ::datafusion::arrow::datatypes::{Field, Schema};
+    use ::datafusion::arrow::util::pretty::pretty_format_batches;
+
+    async fn query(sql: &str) -> String {
+        let ctx = SessionContext::new();
+        register_udfs(&ctx);
+
+        let schema = Arc::new(Schema::new(vec![Field::new("cusip", DataType::Utf8, true)]));
+        let cusips: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("037833100"),
+            Some("S08000AA9"),
+            Some("037833101"),
+            None,
+        ]));
+        let batch = RecordBatch::try_new(schema, vec![cusips]).unwrap();
+        ctx.register_batch("t", batch).unwrap();
+
+        let batches = ctx.sql(sql).await.unwrap().collect().await.unwrap();
+        pretty_format_batches(&batches).unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn decompose_column() {
+        let actual = query(
+            "SELECT cusip, cusip_valid(cusip) AS valid, cusip_issuer(cusip) AS issuer, \
+             cusip_is_cins(cusip) AS cins, cusip_country(cusip) AS country FROM t",
+        )
+        .await;
+        let expected = "\
++-----------+-------+--------+-------+---------+
+| cusip     | valid | issuer | cins  | country |
++-----------+-------+--------+-------+---------+
+| 037833100 | true  | 037833 | false |         |
+| S08000AA9 | true  | S08000 | true  | S       |
+| 037833101 | false |        |       |         |
+|           |       |        |       |         |
++-----------+-------+--------+-------+---------+";
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn scalar_argument() {
+        let actual = query("SELECT cusip_country('S08000AA9') AS country").await;
+        let expected = "\
++---------+
+| country |
++---------+
+| S       |
++---------+";
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,135 @@
+#![warn(missing_docs)]
+//! # cusip::dedup
+//!
+//! Deduplicating raw values that spell the same CUSIP differently.
+//!
+//! Files merged from several vendors often hold the same identifier with different case or
+//! padding. A `Deduplicator` canonicalizes each raw value as `CUSIP::parse_loose()` does, yields
+//! each CUSIP only the first time it is seen, and remembers which distinct raw spellings mapped to
+//! each one, so the cleaning can be audited.
+//!
+//! ```
+//! use cusip::dedup::Deduplicator;
+//!
+//! let mut dedup = Deduplicator::new();
+//! let unique: Vec<String> = dedup
+//!     .dedup(["037833100", " 037833100", "09739d100", "09739D100", "037833100"])
+//!     .map(|result| result.unwrap().to_string())
+//!     .collect();
+//! assert_eq!(unique, ["037833100", "09739D100"]);
+//!
+//! let apple = "037833100".parse().unwrap();
+//! assert_eq!(dedup.variants(&apple), ["037833100", " 037833100"]);
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{CUSIPError, CUSIP};
+
+/// Tracks the CUSIPs seen so far and the raw spellings of each.
+#[derive(Clone, Debug, Default)]
+pub struct Deduplicator {
+    variants: HashMap<CUSIP, Vec<String>>,
+}
+
+impl Deduplicator {
+    /// Constructs a deduplicator that has seen nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Canonicalizes the raw value, returning the CUSIP if it has not been seen before or `None`
+    /// if it has. The raw value is recorded as a variant of its CUSIP either way.
+    pub fn push(&mut self, raw: &str) -> Result<Option<CUSIP>, CUSIPError> {
+        let cusip = CUSIP::parse_loose(raw)?;
+        match self.variants.get_mut(&cusip) {
+            Some(variants) => {
+                if !variants.iter().any(|v| v == raw) {
+                    variants.push(raw.to_string());
+                }
+                Ok(None)
+            }
+            None => {
+                self.variants.insert(cusip, vec![raw.to_string()]);
+                Ok(Some(cusip))
+            }
+        }
+    }
+
+    /// Returns an iterator over the raw values that yields each CUSIP the first time it is seen,
+    /// and an error for each value that is not a valid CUSIP.
+    pub fn dedup<'a, I, S>(
+        &'a mut self,
+        values: I,
+    ) -> impl Iterator<Item = Result<CUSIP, CUSIPError>> + 'a
+    where
+        I: IntoIterator<Item = S>,
+        I::IntoIter: 'a,
+        S: AsRef<str>,
+    {
+        values
+            .into_iter()
+            .filter_map(move |raw| self.push(raw.as_ref()).transpose())
+    }
+
+    /// Returns the number of distinct CUSIPs seen.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    /// Returns true if no CUSIPs have been seen.
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Returns the distinct raw values that mapped to the CUSIP, in the order first seen. There
+    /// are none if the CUSIP has not been seen.
+    pub fn variants(&self, cusip: &CUSIP) -> &[String] {
+        self.variants.get(cusip).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the CUSIPs that were spelled more than one way, with their spellings, in ascending
+    /// order of CUSIP.
+    pub fn with_multiple_variants(&self) -> Vec<(CUSIP, &[String])> {
+        let mut found: Vec<_> = self
+            .variants
+            .iter()
+            .filter(|(_, variants)| variants.len() > 1)
+            .map(|(cusip, variants)| (*cusip, variants.as_slice()))
+            .collect();
+        found.sort_unstable_by_key(|(cusip, _)| *cusip);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_reports_first_sighting() {
+        let mut dedup = Deduplicator::new();
+        let bcc = CUSIP::parse("09739D100").unwrap();
+        assert_eq!(dedup.push("09739d100"), Ok(Some(bcc)));
+        assert_eq!(dedup.push("09739d100"), Ok(None));
+        assert_eq!(dedup.push("09739D100\t"), Ok(None));
+        assert!(dedup.push("09739D101").is_err());
+        assert_eq!(dedup.len(), 1);
+        assert_eq!(dedup.variants(&bcc), ["09739d100", "09739D100\t"]);
+    }
+
+    #[test]
+    fn errors_pass_through() {
+        let mut dedup = Deduplicator::new();
+        let results: Vec<_> = dedup.dedup(["bad", "S08000AA9", "s08000aa9"]).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+
+        let multiple = dedup.with_multiple_variants();
+        assert_eq!(multiple.len(), 1);
+        assert_eq!(multiple[0].1, ["S08000AA9", "s08000aa9"]);
+        assert!(dedup
+            .variants(&CUSIP::parse("037833100").unwrap())
+            .is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+#![warn(missing_docs)]
+//! # cusip::defmt
+//!
+//! `defmt::Format` implementations for `CUSIP` and `CUSIPError` (requires the `defmt` feature),
+//! so embedded targets can log identifiers and errors through [defmt](https://defmt.ferrous-systems.com)
+//! without pulling in `core::fmt`. This feature does not need `std`.
+//!
+//! ```
+//! use cusip::CUSIP;
+//!
+//! fn log_received(cusip: &CUSIP) {
+//!     defmt::info!("received {}", cusip);
+//! }
+//! ```
+//!
+//! The messages match the `Display` output of each type.
+
+use core::str::from_utf8_unchecked;
+
+use ::defmt::{write, Format, Formatter};
+
+use crate::{CUSIPError, CUSIP};
+
+impl Format for CUSIP {
+    fn format(&self, f: Formatter<'_>) {
+        let s = unsafe { from_utf8_unchecked(self.as_bytes()) }; // This is safe because we know it is ASCII
+        write!(f, "{=str}", s)
+    }
+}
+
+impl Format for CUSIPError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            CUSIPError::InvalidCUSIPLength { was } => {
+                write!(
+                    f,
+                    "invalid CUSIP length {=usize} bytes when expecting 9",
+                    was
+                )
+            }
+            CUSIPError::InvalidPayloadLength { was } => {
+                write!(
+                    f,
+                    "invalid Payload length {=usize} bytes when expecting 8",
+                    was
+                )
+            }
+            CUSIPError::InvalidIssuerNumLength { was } => {
+                write!(
+                    f,
+                    "invalid Issuer Number length {=usize} bytes when expecting 6",
+                    was
+                )
+            }
+            CUSIPError::InvalidIssueNumLength { was } => {
+                write!(
+                    f,
+                    "invalid Issue Number length {=usize} bytes when expecting 2",
+                    was
+                )
+            }
+            CUSIPError::InvalidIssuerNum { was } => write!(
+                f,
+                "Issuer Number {=[u8]:a} is not six uppercase ASCII alphanumeric characters",
+                &was[..]
+            ),
+            CUSIPError::InvalidIssueNum { was } => write!(
+                f,
+                "Issue Number {=[u8]:a} is not two uppercase ASCII alphanumeric characters",
+                &was[..]
+            ),
+            CUSIPError::InvalidCheckDigit { was } => write!(
+                f,
+                "Check Digit {=char} is not one ASCII decimal digit",
+                *was as char
+            ),
+            CUSIPError::IncorrectCheckDigit { was, expected } => write!(
+                f,
+                "incorrect Check Digit {=char} when expecting {=char}",
+                *was as char, *expected as char
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Actually emitting defmt frames needs a global logger from the target's runtime, so this
+    // only checks the implementations are there.
+    fn assert_format<T: Format>() {}
+
+    #[test]
+    fn types_implement_format() {
+        assert_format::<CUSIP>();
+        assert_format::<CUSIPError>();
+    }
+}
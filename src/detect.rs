@@ -0,0 +1,126 @@
+#![warn(missing_docs)]
+//! # cusip::detect
+//!
+//! Finding the column of a delimited file that holds CUSIPs.
+//!
+//! Vendor files put their identifiers in different columns, under different headings (or none).
+//! `detect_cusip_column()` reads a sample of the records and picks the column where the most
+//! values are valid CUSIPs (as `CUSIP::parse_loose()` accepts them), reporting the fraction of
+//! sampled records that were valid there as its confidence.
+//!
+//! ```
+//! use cusip::detect::detect_cusip_column;
+//!
+//! let file = "\
+//! name,id,price
+//! Apple Inc.,037833100,227.50
+//! Microsoft Corp.,594918104,415.10
+//! Boise Cascade,09739d100,140.00
+//! ";
+//!
+//! let guess = detect_cusip_column(file.as_bytes(), ',', 100).unwrap().unwrap();
+//! assert_eq!(guess.index, 1);
+//! assert_eq!(guess.sampled, 4);
+//! assert_eq!(guess.confidence, 0.75); // The header is not a CUSIP
+//! ```
+
+use std::io::{self, BufRead};
+
+use crate::csv::split_record;
+use crate::CUSIP;
+
+/// The column `detect_cusip_column()` picked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnGuess {
+    /// The (zero-based) index of the column.
+    pub index: usize,
+    /// The fraction of sampled records with a valid CUSIP in the column, from 0 to 1.
+    pub confidence: f64,
+    /// The number of records sampled (not counting blank lines).
+    pub sampled: usize,
+}
+
+/// Reads up to `max_records` records of delimited text and returns the column most likely to hold
+/// CUSIPs, or `None` if no sampled value in any column is a valid CUSIP. Ties go to the leftmost
+/// column. Blank lines are skipped. Fields may be quoted as in CSV, but records may not span
+/// lines.
+pub fn detect_cusip_column<R: BufRead>(
+    reader: R,
+    delimiter: char,
+    max_records: usize,
+) -> io::Result<Option<ColumnGuess>> {
+    let mut hits: Vec<usize> = Vec::new();
+    let mut sampled = 0;
+
+    for line in reader.lines() {
+        if sampled == max_records {
+            break;
+        }
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        sampled += 1;
+
+        let fields = split_record(&line, delimiter);
+        if hits.len() < fields.len() {
+            hits.resize(fields.len(), 0);
+        }
+        for (index, field) in fields.iter().enumerate() {
+            if CUSIP::parse_loose(field).is_ok() {
+                hits[index] += 1;
+            }
+        }
+    }
+
+    let best = hits
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .max_by(|(ia, a), (ib, b)| a.cmp(b).then(ib.cmp(ia)));
+    Ok(best.map(|(index, &count)| ColumnGuess {
+        index,
+        confidence: count as f64 / sampled as f64,
+        sampled,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_best_column() {
+        let file = "\
+037833100|x|594918104
+
+bad|x|037833100
+\"S08000AA9\"|x|S08000AA9
+";
+        let guess = detect_cusip_column(file.as_bytes(), '|', 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            guess,
+            ColumnGuess {
+                index: 2,
+                confidence: 1.0,
+                sampled: 3
+            }
+        );
+
+        let first_only = detect_cusip_column(file.as_bytes(), '|', 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first_only.index, 0);
+    }
+
+    #[test]
+    fn no_cusips() {
+        assert_eq!(
+            detect_cusip_column("a,b\nc,d\n".as_bytes(), ',', 10).unwrap(),
+            None
+        );
+        assert_eq!(detect_cusip_column("".as_bytes(), ',', 10).unwrap(), None);
+    }
+}
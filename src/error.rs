@@ -3,9 +3,8 @@
 //!
 //! Error type for CUSIP parsing and building.
 
-use std::error::Error;
-use std::fmt::Formatter;
-use std::fmt::{Debug, Display};
+use core::fmt::Formatter;
+use core::fmt::{Debug, Display};
 
 /// All the ways parsing or building could fail.
 #[non_exhaustive]
@@ -56,7 +55,7 @@ pub enum CUSIPError {
 }
 
 impl Debug for CUSIPError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             CUSIPError::InvalidCUSIPLength { was } => {
                 write!(f, "InvalidCUSIPLength {{ was: {was:?} }}")
@@ -70,7 +69,7 @@ impl Debug for CUSIPError {
             CUSIPError::InvalidIssueNumLength { was } => {
                 write!(f, "InvalidIssueNumLength {{ was: {was:?} }}")
             }
-            CUSIPError::InvalidIssuerNum { was } => match std::str::from_utf8(was) {
+            CUSIPError::InvalidIssuerNum { was } => match core::str::from_utf8(was) {
                 Ok(s) => {
                     write!(f, "InvalidIssuerNum {{ was: {s:?} }}")
                 }
@@ -78,7 +77,7 @@ impl Debug for CUSIPError {
                     write!(f, "InvalidIssuerNum {{ was: (invalid UTF-8) {was:?} }}")
                 }
             },
-            CUSIPError::InvalidIssueNum { was } => match std::str::from_utf8(was) {
+            CUSIPError::InvalidIssueNum { was } => match core::str::from_utf8(was) {
                 Ok(s) => {
                     write!(f, "InvalidIssueNum {{ was: {s:?} }}")
                 }
@@ -102,7 +101,7 @@ impl Debug for CUSIPError {
 }
 
 impl Display for CUSIPError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         match self {
             CUSIPError::InvalidCUSIPLength { was } => {
                 write!(f, "invalid CUSIP length {was} bytes when expecting 9")
@@ -122,7 +121,7 @@ impl Display for CUSIPError {
                     "invalid Issue Number length {was} bytes when expecting 2"
                 )
             }
-            CUSIPError::InvalidIssuerNum { was } => match std::str::from_utf8(was) {
+            CUSIPError::InvalidIssuerNum { was } => match core::str::from_utf8(was) {
                 Ok(s) => {
                     write!(
                         f,
@@ -135,7 +134,7 @@ impl Display for CUSIPError {
                     )
                 }
             },
-            CUSIPError::InvalidIssueNum { was } => match std::str::from_utf8(was) {
+            CUSIPError::InvalidIssueNum { was } => match core::str::from_utf8(was) {
                 Ok(s) => {
                     write!(
                         f,
@@ -167,4 +166,149 @@ impl Display for CUSIPError {
     }
 }
 
-impl Error for CUSIPError {}
+#[cfg(feature = "std")]
+impl std::error::Error for CUSIPError {}
+
+impl CUSIPError {
+    /// The name of the variant, such as `"IncorrectCheckDigit"`, for reports and metric labels.
+    #[cfg(feature = "std")]
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self {
+            CUSIPError::InvalidCUSIPLength { .. } => "InvalidCUSIPLength",
+            CUSIPError::InvalidPayloadLength { .. } => "InvalidPayloadLength",
+            CUSIPError::InvalidIssuerNumLength { .. } => "InvalidIssuerNumLength",
+            CUSIPError::InvalidIssueNumLength { .. } => "InvalidIssueNumLength",
+            CUSIPError::InvalidIssuerNum { .. } => "InvalidIssuerNum",
+            CUSIPError::InvalidIssueNum { .. } => "InvalidIssueNum",
+            CUSIPError::InvalidCheckDigit { .. } => "InvalidCheckDigit",
+            CUSIPError::IncorrectCheckDigit { .. } => "IncorrectCheckDigit",
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl CUSIPError {
+    /// Renders a two-line diagnostic for this error: the input, then a line with a caret under each
+    /// offending character followed by the error message. A missing character is marked with a
+    /// caret just past the end of the input. Tabs in the input are repeated on the caret line so
+    /// the carets stay aligned.
+    ///
+    /// The input should be the string that was parsed to produce this error. Leading whitespace is
+    /// accounted for if it was trimmed away, as `CUSIP::parse_loose()` does.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let input = "037833101";
+    /// let err = CUSIP::parse(input).unwrap_err();
+    /// assert_eq!(
+    ///     err.render(input),
+    ///     "037833101\n        ^ incorrect Check Digit '1' when expecting '0'"
+    /// );
+    /// ```
+    pub fn render(&self, input: &str) -> String {
+        // Errors from parse_loose() refer to the trimmed input, which has the length of the error
+        // (any error but a length error means there were 9 bytes to check)
+        let trimmed = input.trim();
+        let length = match self {
+            CUSIPError::InvalidCUSIPLength { was } => *was,
+            _ => 9,
+        };
+        let offset = if trimmed != input && trimmed.len() == length {
+            input.len() - input.trim_start().len()
+        } else {
+            0
+        };
+
+        let length_range = |was: usize, expected: usize| {
+            if was > expected {
+                expected..was
+            } else {
+                was..was + 1
+            }
+        };
+        let marked: Vec<usize> = match self {
+            CUSIPError::InvalidCUSIPLength { was } => length_range(*was, 9).collect(),
+            CUSIPError::InvalidPayloadLength { was } => length_range(*was, 8).collect(),
+            CUSIPError::InvalidIssuerNumLength { was } => length_range(*was, 6).collect(),
+            CUSIPError::InvalidIssueNumLength { was } => length_range(*was, 2).collect(),
+            CUSIPError::InvalidIssuerNum { was } => invalid_positions(was, 0),
+            CUSIPError::InvalidIssueNum { was } => invalid_positions(was, 6),
+            CUSIPError::InvalidCheckDigit { .. } | CUSIPError::IncorrectCheckDigit { .. } => {
+                vec![8]
+            }
+        };
+        let marked: Vec<usize> = marked.into_iter().map(|i| i + offset).collect();
+
+        let mut carets = String::new();
+        for (i, c) in input.char_indices() {
+            if (i..i + c.len_utf8()).any(|b| marked.contains(&b)) {
+                carets.push('^');
+            } else if c == '\t' {
+                carets.push('\t');
+            } else {
+                carets.push(' ');
+            }
+        }
+        if marked.iter().any(|&b| b >= input.len()) {
+            carets.push('^');
+        }
+
+        format!("{input}\n{} {self}", carets.trim_end())
+    }
+}
+
+/// Returns the positions of the bytes that are not uppercase ASCII alphanumerics, plus `start`.
+#[cfg(feature = "std")]
+fn invalid_positions(was: &[u8], start: usize) -> Vec<usize> {
+    was.iter()
+        .enumerate()
+        .filter(|(_, b)| !(b.is_ascii_digit() || b.is_ascii_uppercase()))
+        .map(|(i, _)| start + i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CUSIP;
+
+    fn render(input: &str) -> String {
+        CUSIP::parse_loose(input).unwrap_err().render(input)
+    }
+
+    #[test]
+    fn render_invalid_chars() {
+        assert_eq!(
+            render("0-78-3100"),
+            "0-78-3100\n ^  ^ Issuer Number \"0-78-3\" is not six uppercase ASCII alphanumeric characters"
+        );
+        assert_eq!(
+            render("037833$00"),
+            "037833$00\n      ^ Issue Number \"$0\" is not two uppercase ASCII alphanumeric characters"
+        );
+    }
+
+    #[test]
+    fn render_length() {
+        assert_eq!(
+            render("03783310"),
+            "03783310\n        ^ invalid CUSIP length 8 bytes when expecting 9"
+        );
+        assert_eq!(
+            render("0378331000"),
+            "0378331000\n         ^ invalid CUSIP length 10 bytes when expecting 9"
+        );
+        assert_eq!(
+            render(" 0378331 "),
+            " 0378331 \n        ^ invalid CUSIP length 7 bytes when expecting 9"
+        );
+    }
+
+    #[test]
+    fn render_loose_input() {
+        assert_eq!(
+            render("\t 037833101"),
+            "\t 037833101\n\t         ^ incorrect Check Digit '1' when expecting '0'"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+#![warn(missing_docs)]
+//! # cusip::grpc
+//!
+//! A ready-to-mount [tonic](https://docs.rs/tonic) gRPC service for validating CUSIPs (requires
+//! the `grpc` feature).
+//!
+//! The protocol is defined in `proto/cusip.proto` (package `cusip.v1`), and the generated message
+//! and server types are in the `proto` submodule. The `CusipService` service has three batched
+//! RPCs, each returning one result per input value, in order:
+//!
+//! * `Validate`: Whether each value is a valid CUSIP, and if not, why not.
+//! * `Explain`: The fields of each value that is a valid CUSIP.
+//! * `ComputeCheckDigit`: The _Check Digit_ for each 8-character _Payload_.
+//!
+//! An invalid value never fails the RPC as a whole; its result carries the error message instead.
+//! Mount the service returned by `service()` on a tonic `Server` to deploy a validation sidecar:
+//!
+//! ```ignore
+//! tonic::transport::Server::builder()
+//!     .add_service(cusip::grpc::service())
+//!     .serve("[::1]:50051".parse()?)
+//!     .await?;
+//! ```
+
+use ::tonic::{Request, Response, Status};
+
+use crate::{build_from_payload, CUSIP};
+
+/// The message and server types generated from `proto/cusip.proto`.
+#[allow(missing_docs)]
+pub mod proto {
+    ::tonic::include_proto!("cusip.v1");
+}
+
+use proto::cusip_service_server::{CusipService, CusipServiceServer};
+use proto::{
+    CheckDigitResult, ComputeCheckDigitRequest, ComputeCheckDigitResponse, ExplainRequest,
+    ExplainResponse, Explanation, ValidateRequest, ValidateResponse, ValidateResult,
+};
+
+/// An implementation of the `cusip.v1.CusipService` gRPC service.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CusipValidationService;
+
+/// Returns a `CusipValidationService` wrapped as a tonic service, ready to add to a `Server`.
+pub fn service() -> CusipServiceServer<CusipValidationService> {
+    CusipServiceServer::new(CusipValidationService)
+}
+
+#[::tonic::async_trait]
+impl CusipService for CusipValidationService {
+    async fn validate(
+        &self,
+        request: Request<ValidateRequest>,
+    ) -> Result<Response<ValidateResponse>, Status> {
+        let results = request
+            .into_inner()
+            .values
+            .into_iter()
+            .map(|value| {
+                let error = CUSIP::parse(&value).err().map(|err| err.to_string());
+                ValidateResult {
+                    valid: error.is_none(),
+                    value,
+                    error,
+                }
+            })
+            .collect();
+        Ok(Response::new(ValidateResponse { results }))
+    }
+
+    async fn explain(
+        &self,
+        request: Request<ExplainRequest>,
+    ) -> Result<Response<ExplainResponse>, Status> {
+        let explanations = request
+            .into_inner()
+            .values
+            .into_iter()
+            .map(|value| match CUSIP::parse(&value) {
+                Ok(cusip) => Explanation {
+                    issuer_num: cusip.issuer_num().to_string(),
+                    issue_num: cusip.issue_num().to_string(),
+                    check_digit: cusip.check_digit().to_string(),
+                    is_cins: cusip.is_cins(),
+                    cins_country_code: cusip.as_cins().map(|cins| cins.country_code().to_string()),
+                    is_private_use: cusip.is_private_use(),
+                    value,
+                    error: None,
+                },
+                Err(err) => Explanation {
+                    value,
+                    error: Some(err.to_string()),
+                    ..Default::default()
+                },
+            })
+            .collect();
+        Ok(Response::new(ExplainResponse { explanations }))
+    }
+
+    async fn compute_check_digit(
+        &self,
+        request: Request<ComputeCheckDigitRequest>,
+    ) -> Result<Response<ComputeCheckDigitResponse>, Status> {
+        let results = request
+            .into_inner()
+            .payloads
+            .into_iter()
+            .map(|payload| match build_from_payload(&payload) {
+                Ok(cusip) => CheckDigitResult {
+                    check_digit: cusip.check_digit().to_string(),
+                    cusip: cusip.to_string(),
+                    payload,
+                    error: None,
+                },
+                Err(err) => CheckDigitResult {
+                    payload,
+                    error: Some(err.to_string()),
+                    ..Default::default()
+                },
+            })
+            .collect();
+        Ok(Response::new(ComputeCheckDigitResponse { results }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn validate_batch() {
+        let request = Request::new(ValidateRequest {
+            values: strings(&["037833100", "037833101"]),
+        });
+        let results = CusipValidationService
+            .validate(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+        assert!(results[0].valid);
+        assert_eq!(results[0].error, None);
+        assert!(!results[1].valid);
+        assert_eq!(
+            results[1].error.as_deref(),
+            Some("incorrect Check Digit '1' when expecting '0'")
+        );
+    }
+
+    #[tokio::test]
+    async fn explain_batch() {
+        let request = Request::new(ExplainRequest {
+            values: strings(&["S08000AA9", "S08000AA"]),
+        });
+        let explanations = CusipValidationService
+            .explain(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .explanations;
+        assert_eq!(explanations[0].issuer_num, "S08000");
+        assert_eq!(explanations[0].cins_country_code.as_deref(), Some("S"));
+        assert!(explanations[1].error.is_some());
+        assert_eq!(explanations[1].issuer_num, "");
+    }
+
+    #[tokio::test]
+    async fn compute_check_digit_batch() {
+        let request = Request::new(ComputeCheckDigitRequest {
+            payloads: strings(&["03783310", "0378331"]),
+        });
+        let results = CusipValidationService
+            .compute_check_digit(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+        assert_eq!(results[0].check_digit, "0");
+        assert_eq!(results[0].cusip, "037833100");
+        assert!(results[1].error.is_some());
+    }
+}
@@ -0,0 +1,97 @@
+#![warn(missing_docs)]
+//! # cusip::heed
+//!
+//! Use `CUSIP` directly as a key (or value) codec in [heed](https://docs.rs/heed) LMDB databases
+//! (requires the `heed` feature).
+//!
+//! `CUSIP` implements `BytesEncode` and `BytesDecode`, storing each CUSIP as its 9 ASCII bytes.
+//! Since LMDB compares keys byte by byte, iteration order matches the `Ord` implementation of
+//! `CUSIP`. Decoding validates the bytes, so a corrupted key is reported as an error rather than
+//! producing an invalid `CUSIP`.
+//!
+//! ```
+//! use cusip::CUSIP;
+//! use heed::types::Str;
+//! use heed::{Database, EnvOpenOptions};
+//!
+//! # let dir = std::env::temp_dir().join(format!("cusip-heed-doc-{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! let env = unsafe { EnvOpenOptions::new().open(&dir) }.unwrap();
+//! let mut wtxn = env.write_txn().unwrap();
+//! let db: Database<CUSIP, Str> = env.create_database(&mut wtxn, None).unwrap();
+//!
+//! let apple = CUSIP::parse("037833100").unwrap();
+//! db.put(&mut wtxn, &apple, "Apple Inc.").unwrap();
+//! assert_eq!(db.get(&wtxn, &apple).unwrap(), Some("Apple Inc."));
+//! # drop(wtxn);
+//! # drop(env);
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::borrow::Cow;
+
+use ::heed::{BoxedError, BytesDecode, BytesEncode};
+
+use crate::CUSIP;
+
+impl<'a> BytesEncode<'a> for CUSIP {
+    type EItem = CUSIP;
+
+    fn bytes_encode(item: &'a CUSIP) -> Result<Cow<'a, [u8]>, BoxedError> {
+        Ok(Cow::Borrowed(item.as_bytes()))
+    }
+}
+
+impl<'a> BytesDecode<'a> for CUSIP {
+    type DItem = CUSIP;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<CUSIP, BoxedError> {
+        Ok(CUSIP::from_bytes(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ::heed::types::Unit;
+    use ::heed::{Database, EnvOpenOptions};
+
+    #[test]
+    fn round_trip_bytes() {
+        let cusip = CUSIP::parse("S08000AA9").unwrap();
+        let bytes = <CUSIP as BytesEncode>::bytes_encode(&cusip).unwrap();
+        assert_eq!(&bytes[..], b"S08000AA9");
+        assert_eq!(<CUSIP as BytesDecode>::bytes_decode(&bytes).unwrap(), cusip);
+        assert!(<CUSIP as BytesDecode>::bytes_decode(b"S08000AA8").is_err());
+    }
+
+    #[test]
+    fn keys_iterate_in_order() {
+        let dir = std::env::temp_dir().join(format!("cusip-heed-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let env = unsafe { EnvOpenOptions::new().open(&dir) }.unwrap();
+
+        let mut cusips: Vec<CUSIP> = ["S08000AA9", "594918104", "037833100", "09739D100"]
+            .iter()
+            .map(|s| CUSIP::parse(s).unwrap())
+            .collect();
+
+        let mut wtxn = env.write_txn().unwrap();
+        let db: Database<CUSIP, Unit> = env.create_database(&mut wtxn, None).unwrap();
+        for cusip in &cusips {
+            db.put(&mut wtxn, cusip, &()).unwrap();
+        }
+        let keys: Vec<CUSIP> = db
+            .iter(&wtxn)
+            .unwrap()
+            .map(|entry| entry.unwrap().0)
+            .collect();
+        drop(wtxn);
+        drop(env);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        cusips.sort();
+        assert_eq!(keys, cusips);
+    }
+}
@@ -0,0 +1,170 @@
+#![warn(missing_docs)]
+//! # cusip::identifier
+//!
+//! A single input type for systems that accept either a CUSIP or an ISIN.
+//!
+//! `CusipOrIsin::parse()` tells the two forms apart by length, validates whichever it finds, and
+//! keeps it as given, so formatting a parsed value reproduces the input. `normalize()` replaces a
+//! CUSIP-based ISIN with the CUSIP embedded in it, for callers that want one key per security.
+//!
+//! ```
+//! use cusip::identifier::CusipOrIsin;
+//!
+//! let from_cusip = CusipOrIsin::parse("037833100").unwrap();
+//! let from_isin = CusipOrIsin::parse("US0378331005").unwrap();
+//! assert_ne!(from_cusip, from_isin);
+//! assert_eq!(from_isin.to_string(), "US0378331005");
+//!
+//! assert_eq!(from_isin.normalize(), from_cusip);
+//! assert_eq!(from_isin.cusip(), from_cusip.cusip());
+//!
+//! let foreign = CusipOrIsin::parse("GB0002634946").unwrap();
+//! assert_eq!(foreign.normalize(), foreign);
+//! assert_eq!(foreign.cusip(), None);
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::isin::ISIN;
+use crate::{CUSIPError, CUSIP};
+
+/// All the ways parsing a `CusipOrIsin` could fail.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdentifierError {
+    /// The input is neither 9 bytes long (a CUSIP) nor 12 bytes long (an ISIN).
+    InvalidLength {
+        /// The length we found
+        was: usize,
+    },
+    /// The input is the length of a CUSIP, but not a valid one.
+    InvalidCusip(CUSIPError),
+    /// The input is the length of an ISIN, but not a valid one.
+    InvalidIsin,
+}
+
+impl fmt::Display for IdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentifierError::InvalidLength { was } => write!(
+                f,
+                "invalid length {was} bytes when expecting 9 (CUSIP) or 12 (ISIN)"
+            ),
+            IdentifierError::InvalidCusip(err) => write!(f, "invalid CUSIP: {err}"),
+            IdentifierError::InvalidIsin => write!(f, "invalid ISIN"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IdentifierError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IdentifierError::InvalidCusip(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Either a CUSIP or an ISIN, as given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CusipOrIsin {
+    /// A CUSIP.
+    Cusip(CUSIP),
+    /// An ISIN.
+    Isin(ISIN),
+}
+
+impl fmt::Display for CusipOrIsin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CusipOrIsin::Cusip(cusip) => cusip.fmt(f),
+            CusipOrIsin::Isin(isin) => isin.fmt(f),
+        }
+    }
+}
+
+impl FromStr for CusipOrIsin {
+    type Err = IdentifierError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<CUSIP> for CusipOrIsin {
+    fn from(cusip: CUSIP) -> Self {
+        CusipOrIsin::Cusip(cusip)
+    }
+}
+
+impl From<ISIN> for CusipOrIsin {
+    fn from(isin: ISIN) -> Self {
+        CusipOrIsin::Isin(isin)
+    }
+}
+
+impl CusipOrIsin {
+    /// Parses a CUSIP (9 bytes) or an ISIN (12 bytes). The input must be in exactly the form
+    /// `CUSIP::parse()` or `ISIN::parse()` requires.
+    pub fn parse(value: &str) -> Result<CusipOrIsin, IdentifierError> {
+        match value.len() {
+            9 => CUSIP::parse(value)
+                .map(CusipOrIsin::Cusip)
+                .map_err(IdentifierError::InvalidCusip),
+            12 => ISIN::parse(value)
+                .map(CusipOrIsin::Isin)
+                .ok_or(IdentifierError::InvalidIsin),
+            was => Err(IdentifierError::InvalidLength { was }),
+        }
+    }
+
+    /// Returns the CUSIP, or the CUSIP embedded in a CUSIP-based ISIN (see `ISIN::cusip()`).
+    pub fn cusip(&self) -> Option<CUSIP> {
+        match self {
+            CusipOrIsin::Cusip(cusip) => Some(*cusip),
+            CusipOrIsin::Isin(isin) => isin.cusip(),
+        }
+    }
+
+    /// Returns the CUSIP embedded in a CUSIP-based ISIN as a `CusipOrIsin::Cusip`, or the value
+    /// unchanged if it is a CUSIP or some other ISIN.
+    pub fn normalize(self) -> CusipOrIsin {
+        self.cusip().map_or(self, CusipOrIsin::Cusip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            CusipOrIsin::parse("03783310"),
+            Err(IdentifierError::InvalidLength { was: 8 })
+        );
+        assert!(matches!(
+            CusipOrIsin::parse("037833101"),
+            Err(IdentifierError::InvalidCusip(
+                CUSIPError::IncorrectCheckDigit { .. }
+            ))
+        ));
+        assert_eq!(
+            CusipOrIsin::parse("US0378331006"),
+            Err(IdentifierError::InvalidIsin)
+        );
+    }
+
+    #[test]
+    fn normalize_cins_based_isin() {
+        let cins = CUSIP::parse("G1151C101").unwrap();
+        let isin = CusipOrIsin::parse("BMG1151C1010").unwrap();
+        assert_eq!(isin.normalize(), CusipOrIsin::Cusip(cins));
+        assert_eq!(
+            CusipOrIsin::parse("US09739D1000").unwrap().normalize(),
+            CusipOrIsin::Cusip(CUSIP::parse("09739D100").unwrap())
+        );
+    }
+}
@@ -0,0 +1,82 @@
+#![warn(missing_docs)]
+//! # cusip::io
+//!
+//! Reading CUSIPs from byte streams, for binary protocols that embed identifiers at fixed
+//! positions.
+//!
+//! `read_cusip()` reads exactly 9 bytes and validates them. `read_cusip_delimited()` also consumes
+//! the delimiter byte that follows, for streams of delimited identifiers. A value that is not a
+//! valid CUSIP is reported as an `io::Error` of kind `InvalidData` wrapping the `CUSIPError`, so
+//! these compose with other `?`-based reading code.
+//!
+//! ```
+//! use cusip::io::{read_cusip, read_cusip_delimited};
+//!
+//! let mut stream: &[u8] = b"037833100|594918104|09739D100";
+//! assert_eq!(read_cusip_delimited(&mut stream, b'|').unwrap().to_string(), "037833100");
+//! assert_eq!(read_cusip_delimited(&mut stream, b'|').unwrap().to_string(), "594918104");
+//! assert_eq!(read_cusip(&mut stream).unwrap().to_string(), "09739D100");
+//! ```
+
+use std::io::{self, Read};
+
+use crate::CUSIP;
+
+/// Reads exactly 9 bytes and parses them as a CUSIP. A stream that ends first is reported as an
+/// `UnexpectedEof` error, and an invalid CUSIP as an `InvalidData` error wrapping the
+/// `CUSIPError`.
+pub fn read_cusip<R: Read + ?Sized>(reader: &mut R) -> io::Result<CUSIP> {
+    let mut bb = [0u8; 9];
+    reader.read_exact(&mut bb)?;
+    CUSIP::from_bytes(&bb).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Reads a CUSIP as `read_cusip()` does, then consumes the `delimiter` byte that follows it. The
+/// stream may end right after the CUSIP, as it would after the last of a series. Any other byte
+/// in place of the delimiter is reported as an `InvalidData` error.
+pub fn read_cusip_delimited<R: Read + ?Sized>(reader: &mut R, delimiter: u8) -> io::Result<CUSIP> {
+    let cusip = read_cusip(reader)?;
+    let mut b = [0u8; 1];
+    loop {
+        match reader.read(&mut b) {
+            Ok(0) => return Ok(cusip),
+            Ok(_) if b[0] == delimiter => return Ok(cusip),
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "expected delimiter {:?} after CUSIP {cusip}, found {:?}",
+                        delimiter as char, b[0] as char
+                    ),
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CUSIPError;
+
+    #[test]
+    fn invalid_data_wraps_cusip_error() {
+        let err = read_cusip(&mut &b"037833101"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let inner = err.into_inner().unwrap().downcast::<CUSIPError>().unwrap();
+        assert!(matches!(*inner, CUSIPError::IncorrectCheckDigit { .. }));
+    }
+
+    #[test]
+    fn short_and_misdelimited_streams() {
+        let err = read_cusip(&mut &b"03783310"[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut stream: &[u8] = b"037833100,594918104";
+        let err = read_cusip_delimited(&mut stream, b'\n').unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_cusip(&mut stream).unwrap().to_string(), "594918104");
+    }
+}
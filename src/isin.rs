@@ -0,0 +1,281 @@
+#![warn(missing_docs)]
+//! # cusip::isin
+//!
+//! Minimal support for International Securities Identification Numbers (ISINs), enough to check
+//! the ISINs that appear alongside CUSIPs. For full ISIN support, see the
+//! [isin](https://crates.io/crates/isin) crate.
+//!
+//! An ISIN is a 2-letter country code, a 9-character national identifier (for many countries, a
+//! CUSIP or CINS) and a _Check Digit_ computed with the Luhn algorithm over the digits of the
+//! first 11 characters, letters counting as the two digits of their values 10 to 35.
+//!
+//! ```
+//! use cusip::isin;
+//!
+//! assert!(isin::validate("US0378331005"));
+//! assert!(!isin::validate("US0378331006"));
+//! assert_eq!(isin::compute_check_digit("US037833100"), Some('5'));
+//! ```
+//!
+//! An `ISIN` holds a validated ISIN, and can extract the CUSIP embedded in a CUSIP-based one:
+//!
+//! ```
+//! use cusip::isin::ISIN;
+//!
+//! let isin = ISIN::parse("US0378331005").unwrap();
+//! assert_eq!(isin.country_code(), "US");
+//! assert_eq!(isin.cusip().unwrap().to_string(), "037833100");
+//! assert_eq!(ISIN::parse("GB0002634946").unwrap().cusip(), None);
+//! ```
+//!
+//! `CUSIP::to_isin()` goes the other way, wrapping a CUSIP in an ISIN for a country code.
+//! `CUSIP::from_isin_str()` parses an ISIN string straight to its CUSIP, accepting the same ISINs
+//! as `ISIN::cusip()` and explaining any problem with a `FromIsinError`:
+//!
+//! ```
+//! use cusip::isin::FromIsinError;
+//! use cusip::CUSIP;
+//!
+//! assert_eq!(CUSIP::from_isin_str("US0378331005").unwrap().to_string(), "037833100");
+//! assert_eq!(
+//!     CUSIP::from_isin_str("GB0002634946"),
+//!     Err(FromIsinError::NotCusipBased { country_code: *b"GB" })
+//! );
+//! ```
+
+use core::fmt;
+use core::str::from_utf8_unchecked;
+
+use crate::{CUSIPError, CUSIP};
+
+/// All the ways `CUSIP::from_isin_str()` could fail.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FromIsinError {
+    /// The value is not a valid ISIN: 2 uppercase ASCII letters, 9 uppercase ASCII alphanumeric
+    /// characters, and the correct _Check Digit_.
+    InvalidIsin,
+    /// The ISIN is valid but not CUSIP-based (see `ISIN::cusip()`): the country code is not `US`
+    /// or `CA`, and the national identifier is not a valid CINS identifier.
+    NotCusipBased {
+        /// The country code we found
+        country_code: [u8; 2],
+    },
+    /// The country code is `US` or `CA`, but the national identifier is not a valid CUSIP.
+    InvalidCusip(CUSIPError),
+}
+
+impl fmt::Display for FromIsinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromIsinError::InvalidIsin => f.write_str("not a valid ISIN"),
+            FromIsinError::NotCusipBased { country_code } => write!(
+                f,
+                "not CUSIP-based: country code {}{} is not US or CA and the national identifier \
+                 is not a CINS",
+                char::from(country_code[0]),
+                char::from(country_code[1])
+            ),
+            FromIsinError::InvalidCusip(err) => write!(f, "invalid embedded CUSIP: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromIsinError {}
+
+/// The value of an uppercase ASCII alphanumeric character, or `None` for any other character.
+fn char_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'Z' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Computes the Luhn _Check Digit_ over the characters, or returns `None` if any of them is not
+/// an uppercase ASCII alphanumeric character.
+pub(crate) fn luhn_check_digit(bytes: &[u8]) -> Option<u8> {
+    // Walk the expanded digits from the right, doubling every other one starting with the last
+    let mut sum: u32 = 0;
+    let mut double = true;
+    for &b in bytes.iter().rev() {
+        let v = char_value(b)?;
+        let digits: &[u8] = if v < 10 { &[v] } else { &[v % 10, v / 10] };
+        for &d in digits {
+            let d = if double { d * 2 } else { d };
+            sum += u32::from(d / 10 + d % 10);
+            double = !double;
+        }
+    }
+    Some(b'0' + ((10 - sum % 10) % 10) as u8)
+}
+
+/// Computes the _Check Digit_ for the first 11 characters of an ISIN. Returns `None` if the
+/// payload is not 2 uppercase ASCII letters followed by 9 uppercase ASCII alphanumeric characters.
+pub fn compute_check_digit(payload: &str) -> Option<char> {
+    let b = payload.as_bytes();
+    if b.len() != 11 || !b[0..2].iter().all(u8::is_ascii_uppercase) {
+        return None;
+    }
+    luhn_check_digit(b).map(char::from)
+}
+
+/// Returns true if the string is a valid ISIN: 2 uppercase ASCII letters, 9 uppercase ASCII
+/// alphanumeric characters, and the correct _Check Digit_.
+pub fn validate(value: &str) -> bool {
+    value.len() == 12
+        && value.is_char_boundary(11)
+        && compute_check_digit(&value[0..11]) == value[11..].chars().next()
+}
+
+/// A valid ISIN.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct ISIN([u8; 12]);
+
+impl fmt::Display for ISIN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for ISIN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ISIN({})", self.as_str())
+    }
+}
+
+impl ISIN {
+    /// Constructs the ISIN with the country code and national identifier, computing its _Check
+    /// Digit_, or returns `None` if the country code is not 2 uppercase ASCII letters.
+    pub(crate) fn from_parts(country_code: &str, nsin: &[u8; 9]) -> Option<ISIN> {
+        let code = country_code.as_bytes();
+        if code.len() != 2 || !code.iter().all(u8::is_ascii_uppercase) {
+            return None;
+        }
+        let mut bytes = [0_u8; 12];
+        bytes[0..2].copy_from_slice(code);
+        bytes[2..11].copy_from_slice(nsin);
+        bytes[11] = luhn_check_digit(&bytes[0..11])?;
+        Some(ISIN(bytes))
+    }
+
+    /// Parses an ISIN, returning `None` unless `validate()` accepts it.
+    pub fn parse(value: &str) -> Option<ISIN> {
+        if !validate(value) {
+            return None;
+        }
+        let mut bytes = [0_u8; 12];
+        bytes.copy_from_slice(value.as_bytes());
+        Some(ISIN(bytes))
+    }
+
+    /// Returns the ISIN as a string.
+    pub fn as_str(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.0) } // This is safe because we know it is ASCII
+    }
+
+    /// Returns the 2-letter country code.
+    pub fn country_code(&self) -> &str {
+        &self.as_str()[0..2]
+    }
+
+    /// Returns the 9-character national identifier.
+    pub fn nsin(&self) -> &str {
+        &self.as_str()[2..11]
+    }
+
+    /// Returns the _Check Digit_.
+    pub fn check_digit(&self) -> char {
+        char::from(self.0[11])
+    }
+
+    /// Returns the CUSIP embedded in a CUSIP-based ISIN, or `None` if this is not one. An ISIN
+    /// counts as CUSIP-based if its national identifier is a valid CUSIP and either its country
+    /// code is `US` or `CA`, or the national identifier is a CINS identifier (as for securities of
+    /// issuers outside North America that were given CUSIP-numbered ISINs).
+    pub fn cusip(&self) -> Option<CUSIP> {
+        let cusip = CUSIP::from_bytes(&self.0[2..11]).ok()?;
+        (matches!(self.country_code(), "US" | "CA") || cusip.is_cins()).then_some(cusip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_isins() {
+        for isin in [
+            "US0378331005", // Apple Inc.
+            "US5949181045", // Microsoft Corp.
+            "US09739D1000", // Boise Cascade Co.
+            "GB0002634946", // BAE Systems plc
+            "AU0000XVGZA3", // Letters in the national identifier
+        ] {
+            assert!(validate(isin), "{isin} should be valid");
+        }
+    }
+
+    #[test]
+    fn cusip_from_isin_str() {
+        for (isin, cusip) in [
+            ("US0378331005", "037833100"),
+            ("US09739D1000", "09739D100"),
+            ("CA0679011084", "067901108"),
+        ] {
+            assert_eq!(
+                CUSIP::from_isin_str(isin),
+                Ok(CUSIP::parse(cusip).unwrap()),
+                "{isin}"
+            );
+        }
+        assert_eq!(
+            CUSIP::from_isin_str("US0378331006"),
+            Err(FromIsinError::InvalidIsin)
+        );
+        assert_eq!(
+            CUSIP::from_isin_str("AU0000XVGZA3"),
+            Err(FromIsinError::NotCusipBased {
+                country_code: *b"AU"
+            })
+        );
+        // Outside the US and Canada only CINS identifiers count, whatever the country code
+        let arch = CUSIP::parse("G0450A105").unwrap();
+        assert_eq!(CUSIP::from_isin_str("BMG0450A1053"), Ok(arch));
+        assert_eq!(CUSIP::from_isin_str("GBG0450A1051"), Ok(arch));
+        assert_eq!(
+            CUSIP::from_isin_str("BM0378331004"),
+            Err(FromIsinError::NotCusipBased {
+                country_code: *b"BM"
+            })
+        );
+        // A valid ISIN whose national identifier has the wrong CUSIP Check Digit
+        let mut value = *b"US037833101?";
+        value[11] = luhn_check_digit(&value[0..11]).unwrap();
+        assert_eq!(
+            CUSIP::from_isin_str(core::str::from_utf8(&value).unwrap()),
+            Err(FromIsinError::InvalidCusip(
+                CUSIPError::IncorrectCheckDigit {
+                    was: b'1',
+                    expected: b'0'
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_isins() {
+        for isin in [
+            "US0378331006",
+            "us0378331005",
+            "U10378331005",
+            "US037833100",
+            "US03783310055",
+            "US0378331é5",
+        ] {
+            assert!(!validate(isin), "{isin} should be invalid");
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! # cusip::isin_crate
+//!
+//! Conversions to and from the `ISIN` of the [isin](https://crates.io/crates/isin) crate (requires
+//! the `isin` feature), so the two crates compose without going through strings.
+//!
+//! `CUSIP` implements `TryFrom<&isin::ISIN>`, accepting the same ISINs as
+//! `CUSIP::from_isin_str()`, and the ISIN returned by `CUSIP::to_isin()` converts into an
+//! `isin::ISIN` with `From`:
+//!
+//! ```
+//! use cusip::CUSIP;
+//!
+//! let cusip = CUSIP::parse("037833100").unwrap();
+//! let isin: isin::ISIN = cusip.to_isin("US").unwrap().into();
+//! assert_eq!(isin.to_string(), "US0378331005");
+//! assert_eq!(CUSIP::try_from(&isin), Ok(cusip));
+//!
+//! let bae = isin::parse("GB0002634946").unwrap();
+//! assert!(CUSIP::try_from(&bae).is_err());
+//! ```
+
+use crate::isin::{FromIsinError, ISIN};
+use crate::CUSIP;
+
+impl TryFrom<&::isin::ISIN> for CUSIP {
+    type Error = FromIsinError;
+
+    fn try_from(isin: &::isin::ISIN) -> Result<Self, Self::Error> {
+        CUSIP::from_isin_str(isin.as_ref())
+    }
+}
+
+impl From<ISIN> for ::isin::ISIN {
+    fn from(isin: ISIN) -> Self {
+        // Both crates accept exactly the ISINs with a correct Luhn Check Digit
+        ::isin::parse(isin.as_str()).expect("a valid ISIN is valid for the isin crate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (value, country_code) in [
+            ("037833100", "US"),
+            ("09739D100", "US"),
+            ("067901108", "CA"),
+        ] {
+            let cusip = CUSIP::parse(value).unwrap();
+            let isin: ::isin::ISIN = cusip.to_isin(country_code).unwrap().into();
+            assert_eq!(isin.prefix(), country_code);
+            assert_eq!(isin.basic_code(), value);
+            assert_eq!(CUSIP::try_from(&isin), Ok(cusip));
+        }
+    }
+
+    #[test]
+    fn not_cusip_based() {
+        let isin = ::isin::parse("AU0000XVGZA3").unwrap();
+        assert_eq!(
+            CUSIP::try_from(&isin),
+            Err(FromIsinError::NotCusipBased {
+                country_code: *b"AU"
+            })
+        );
+    }
+}
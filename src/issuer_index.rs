@@ -0,0 +1,188 @@
+#![warn(missing_docs)]
+//! # cusip::issuer_index
+//!
+//! An index from _Issuer Number_ to the CUSIPs observed for that _Issuer_.
+//!
+//! `IssuerIndex` keys on the fixed 6-byte `payload::IssuerNum` rather than a `String`, and keeps
+//! both the _Issuers_ and each _Issuer's_ CUSIPs in sorted order.
+//!
+//! ```
+//! use cusip::issuer_index::IssuerIndex;
+//! use cusip::payload::IssuerNum;
+//! use cusip::CUSIP;
+//!
+//! let index: IssuerIndex = ["037833100", "037833209", "594918104"]
+//!     .iter()
+//!     .map(|s| CUSIP::parse(s).unwrap())
+//!     .collect();
+//!
+//! let apple = IssuerNum::parse("037833").unwrap();
+//! let issues: Vec<String> = index.issues(&apple).map(|c| c.to_string()).collect();
+//! assert_eq!(issues, ["037833100", "037833209"]);
+//!
+//! let busy: Vec<_> = index.issuers_with_more_than(1).collect();
+//! assert_eq!(busy, [(apple, 2)]);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::payload::IssuerNum;
+use crate::CUSIP;
+
+/// A multimap from _Issuer Number_ to the set of CUSIPs observed for that _Issuer_.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IssuerIndex {
+    issuers: BTreeMap<IssuerNum, BTreeSet<CUSIP>>,
+    len: usize,
+}
+
+impl IssuerIndex {
+    /// Constructs an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the CUSIP to the index, returning true if it was not already present.
+    pub fn insert(&mut self, cusip: CUSIP) -> bool {
+        let added = self
+            .issuers
+            .entry(cusip.issuer())
+            .or_default()
+            .insert(cusip);
+        if added {
+            self.len += 1;
+        }
+        added
+    }
+
+    /// Removes the CUSIP from the index, returning true if it was present. An _Issuer_ with no
+    /// remaining CUSIPs is removed too.
+    pub fn remove(&mut self, cusip: &CUSIP) -> bool {
+        let key = cusip.issuer();
+        let Some(issues) = self.issuers.get_mut(&key) else {
+            return false;
+        };
+        let removed = issues.remove(cusip);
+        if removed {
+            self.len -= 1;
+            if issues.is_empty() {
+                self.issuers.remove(&key);
+            }
+        }
+        removed
+    }
+
+    /// Returns true if the index contains the CUSIP.
+    pub fn contains(&self, cusip: &CUSIP) -> bool {
+        self.issuers
+            .get(&cusip.issuer())
+            .is_some_and(|issues| issues.contains(cusip))
+    }
+
+    /// Returns the number of CUSIPs in the index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the index holds no CUSIPs.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of distinct _Issuers_ in the index.
+    pub fn issuer_count(&self) -> usize {
+        self.issuers.len()
+    }
+
+    /// Returns the CUSIPs in the index for the _Issuer Number_, in ascending order. There are none
+    /// for an _Issuer Number_ that is not in the index.
+    pub fn issues(&self, issuer: &IssuerNum) -> impl Iterator<Item = &CUSIP> + '_ {
+        self.issuers.get(issuer).into_iter().flatten()
+    }
+
+    /// Returns the number of CUSIPs in the index for the _Issuer Number_.
+    pub fn issue_count(&self, issuer: &IssuerNum) -> usize {
+        self.issuers.get(issuer).map_or(0, BTreeSet::len)
+    }
+
+    /// Returns the _Issuer Numbers_ in the index, in ascending order.
+    pub fn issuers(&self) -> impl Iterator<Item = IssuerNum> + '_ {
+        self.issuers.keys().copied()
+    }
+
+    /// Returns the _Issuer Numbers_ with more than `n` CUSIPs in the index, with their counts, in
+    /// ascending order of _Issuer Number_.
+    pub fn issuers_with_more_than(
+        &self,
+        n: usize,
+    ) -> impl Iterator<Item = (IssuerNum, usize)> + '_ {
+        self.issuers
+            .iter()
+            .filter(move |(_, issues)| issues.len() > n)
+            .map(|(issuer, issues)| (*issuer, issues.len()))
+    }
+
+    /// Returns all the CUSIPs in the index, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &CUSIP> + '_ {
+        self.issuers.values().flatten()
+    }
+}
+
+impl Extend<CUSIP> for IssuerIndex {
+    fn extend<I: IntoIterator<Item = CUSIP>>(&mut self, iter: I) {
+        for cusip in iter {
+            self.insert(cusip);
+        }
+    }
+}
+
+impl<'a> Extend<&'a CUSIP> for IssuerIndex {
+    fn extend<I: IntoIterator<Item = &'a CUSIP>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl FromIterator<CUSIP> for IssuerIndex {
+    fn from_iter<I: IntoIterator<Item = CUSIP>>(iter: I) -> Self {
+        let mut index = Self::new();
+        index.extend(iter);
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_from_parts;
+
+    #[test]
+    fn insert_and_remove() {
+        let a = build_from_parts("037833", "10").unwrap();
+        let b = build_from_parts("037833", "AB").unwrap();
+        let mut index = IssuerIndex::new();
+        assert!(index.insert(b));
+        assert!(index.insert(a));
+        assert!(!index.insert(a));
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.issue_count(&a.issuer()), 2);
+        assert_eq!(index.iter().collect::<Vec<_>>(), [&a, &b]);
+
+        assert!(index.remove(&a));
+        assert!(!index.remove(&a));
+        assert!(!index.contains(&a));
+        assert!(index.remove(&b));
+        assert!(index.is_empty());
+        assert_eq!(index.issuer_count(), 0);
+    }
+
+    #[test]
+    fn unknown_issuer() {
+        let mut index = IssuerIndex::new();
+        let cins = build_from_parts("S08000", "AA").unwrap();
+        index.extend(&[cins]);
+        let apple = IssuerNum::parse("037833").unwrap();
+        assert_eq!(index.issues(&apple).count(), 0);
+        assert_eq!(index.issue_count(&apple), 0);
+        assert_eq!(index.issuers().collect::<Vec<_>>(), [cins.issuer()]);
+    }
+}
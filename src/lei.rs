@@ -0,0 +1,309 @@
+#![warn(missing_docs)]
+//! # cusip::lei
+//!
+//! An index between CUSIPs and the Legal Entity Identifiers (LEIs) of their _Issuers_, built from
+//! the ISIN-to-LEI relationship files GLEIF publishes.
+//!
+//! [GLEIF](https://www.gleif.org/en/lei-data/lei-mapping/download-isin-to-lei-relationship-files)
+//! publishes a CSV file with columns `LEI` and `ISIN`. `LeiIndex::from_gleif_csv()` reads it,
+//! validates every LEI and ISIN, keeps the rows whose ISIN is CUSIP-based (see `ISIN::cusip()`),
+//! and builds an index answering both "which LEI issued this CUSIP?" and "which CUSIPs does this
+//! LEI have?" without a database.
+//!
+//! Storage is compact: each distinct LEI is stored once, and each CUSIP is stored packed (see
+//! `CUSIP::pack()`) with the index of its LEI, in sorted arrays searched by bisection.
+//!
+//! ```
+//! use cusip::lei::LeiIndex;
+//! use cusip::CUSIP;
+//!
+//! let csv = "\
+//! LEI,ISIN
+//! HWUPKR0MPOU8FGXBT394,US0378331005
+//! INR2EJN1ERAN0W5ZP974,US5949181045
+//! INR2EJN1ERAN0W5ZP974,US594918BC73
+//! INR2EJN1ERAN0W5ZP974,GB0002634946
+//! ";
+//! let index = LeiIndex::from_gleif_csv(csv.as_bytes()).unwrap();
+//!
+//! let apple = CUSIP::parse("037833100").unwrap();
+//! assert_eq!(index.lei(&apple), Some("HWUPKR0MPOU8FGXBT394"));
+//!
+//! let microsoft: Vec<String> = index
+//!     .cusips("INR2EJN1ERAN0W5ZP974")
+//!     .map(|cusip| cusip.to_string())
+//!     .collect();
+//! assert_eq!(microsoft, ["594918104", "594918BC7"]);
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str::from_utf8_unchecked;
+
+use crate::csv::split_record;
+use crate::isin::ISIN;
+use crate::CUSIP;
+
+/// Returns true if the string is a valid LEI: 20 uppercase ASCII alphanumeric characters whose
+/// last two digits are the ISO 17442 (ISO 7064 MOD 97-10) check digits.
+pub fn validate_lei(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 20 || !bytes[18..].iter().all(u8::is_ascii_digit) {
+        return false;
+    }
+    let mut remainder: u32 = 0;
+    for &b in bytes {
+        let v = match b {
+            b'0'..=b'9' => u32::from(b - b'0'),
+            b'A'..=b'Z' => u32::from(b - b'A' + 10),
+            _ => return false,
+        };
+        remainder = if v < 10 {
+            (remainder * 10 + v) % 97
+        } else {
+            (remainder * 100 + v) % 97
+        };
+    }
+    remainder == 1
+}
+
+/// All the ways loading a `LeiIndex` could fail.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum LeiIndexError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The header row does not have a column with this name.
+    MissingColumn(&'static str),
+    /// A row has a value that is not a valid LEI.
+    InvalidLei {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// The value we found
+        was: String,
+    },
+    /// A row has a value that is not a valid ISIN.
+    InvalidIsin {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// The value we found
+        was: String,
+    },
+}
+
+impl fmt::Display for LeiIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeiIndexError::Io(err) => write!(f, "error reading ISIN-to-LEI data: {err}"),
+            LeiIndexError::MissingColumn(name) => write!(f, "no column named {name:?}"),
+            LeiIndexError::InvalidLei { line, was } => {
+                write!(f, "line {line}: {was:?} is not a valid LEI")
+            }
+            LeiIndexError::InvalidIsin { line, was } => {
+                write!(f, "line {line}: {was:?} is not a valid ISIN")
+            }
+        }
+    }
+}
+
+impl Error for LeiIndexError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LeiIndexError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LeiIndexError {
+    fn from(err: io::Error) -> Self {
+        LeiIndexError::Io(err)
+    }
+}
+
+/// A read-only index between CUSIPs and LEIs. Each CUSIP has at most one LEI; each LEI may have
+/// any number of CUSIPs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeiIndex {
+    /// Each distinct LEI, in ascending order.
+    leis: Vec<[u8; 20]>,
+    /// Each packed CUSIP with the position of its LEI in `leis`, in ascending order of CUSIP.
+    by_cusip: Vec<(u64, u32)>,
+    /// The same pairs, in ascending order of LEI position and then CUSIP.
+    by_lei: Vec<(u32, u64)>,
+}
+
+impl LeiIndex {
+    /// Loads a GLEIF ISIN-to-LEI relationship file: comma-separated text with a header row, which
+    /// must have columns named `LEI` and `ISIN` (any other columns are ignored). Every LEI and ISIN
+    /// must be valid. Rows whose ISIN is not CUSIP-based are skipped, as are blank lines. If a
+    /// CUSIP appears in more than one row, the first row wins.
+    pub fn from_gleif_csv<R: BufRead>(reader: R) -> Result<Self, LeiIndexError> {
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(header) => split_record(&header?, ','),
+            None => return Ok(Self::default()),
+        };
+        let column = |name: &'static str| {
+            header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or(LeiIndexError::MissingColumn(name))
+        };
+        let lei_col = column("LEI")?;
+        let isin_col = column("ISIN")?;
+
+        let mut pairs = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split_record(&line, ',');
+            let field = |col: usize| fields.get(col).map_or("", |f| f.trim());
+
+            let lei = field(lei_col);
+            if !validate_lei(lei) {
+                return Err(LeiIndexError::InvalidLei {
+                    line: i + 2,
+                    was: lei.to_string(),
+                });
+            }
+            let isin = field(isin_col);
+            let isin = ISIN::parse(isin).ok_or_else(|| LeiIndexError::InvalidIsin {
+                line: i + 2,
+                was: isin.to_string(),
+            })?;
+            if let Some(cusip) = isin.cusip() {
+                let mut bytes = [0_u8; 20];
+                bytes.copy_from_slice(lei.as_bytes());
+                pairs.push((cusip, bytes));
+            }
+        }
+
+        Ok(Self::from_pairs(pairs))
+    }
+
+    /// Builds the index from (CUSIP, LEI) pairs, keeping the first pair for each CUSIP.
+    fn from_pairs(mut pairs: Vec<(CUSIP, [u8; 20])>) -> Self {
+        // A stable sort keeps pairs for the same CUSIP in input order, so dedup keeps the first
+        pairs.sort_by_key(|(cusip, _)| *cusip);
+        pairs.dedup_by_key(|(cusip, _)| *cusip);
+
+        let mut leis: Vec<[u8; 20]> = pairs.iter().map(|(_, lei)| *lei).collect();
+        leis.sort_unstable();
+        leis.dedup();
+
+        // Packing preserves order, so these stay sorted by CUSIP
+        let by_cusip: Vec<(u64, u32)> = pairs
+            .iter()
+            .map(|(cusip, lei)| {
+                let position = leis.binary_search(lei).expect("every LEI was collected");
+                (cusip.pack(), position as u32)
+            })
+            .collect();
+
+        let mut by_lei: Vec<(u32, u64)> = by_cusip
+            .iter()
+            .map(|(packed, position)| (*position, *packed))
+            .collect();
+        by_lei.sort_unstable();
+
+        Self {
+            leis,
+            by_cusip,
+            by_lei,
+        }
+    }
+
+    /// Returns the number of CUSIPs in the index.
+    pub fn len(&self) -> usize {
+        self.by_cusip.len()
+    }
+
+    /// Returns true if the index holds no CUSIPs.
+    pub fn is_empty(&self) -> bool {
+        self.by_cusip.is_empty()
+    }
+
+    /// Returns the number of distinct LEIs in the index.
+    pub fn lei_count(&self) -> usize {
+        self.leis.len()
+    }
+
+    fn lei_str(&self, position: u32) -> &str {
+        unsafe { from_utf8_unchecked(&self.leis[position as usize]) } // This is safe because we know it is ASCII
+    }
+
+    /// Returns the LEI of the CUSIP's _Issuer_, if the index has one.
+    pub fn lei(&self, cusip: &CUSIP) -> Option<&str> {
+        let packed = cusip.pack();
+        let i = self
+            .by_cusip
+            .binary_search_by_key(&packed, |(p, _)| *p)
+            .ok()?;
+        Some(self.lei_str(self.by_cusip[i].1))
+    }
+
+    /// Returns the CUSIPs issued by the LEI, in ascending order. There are none if the LEI is not
+    /// in the index.
+    pub fn cusips<'a>(&'a self, lei: &str) -> impl Iterator<Item = CUSIP> + 'a {
+        let found = self
+            .leis
+            .binary_search_by(|candidate| candidate.as_slice().cmp(lei.as_bytes()))
+            .ok()
+            .map(|position| {
+                let position = position as u32;
+                let start = self.by_lei.partition_point(|(p, _)| *p < position);
+                let end = self.by_lei.partition_point(|(p, _)| *p <= position);
+                &self.by_lei[start..end]
+            })
+            .unwrap_or(&[]);
+        found
+            .iter()
+            .map(|(_, packed)| CUSIP::unpack(*packed).expect("packed CUSIPs are in range"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_leis() {
+        assert!(validate_lei("HWUPKR0MPOU8FGXBT394"));
+        assert!(validate_lei("S6XOOCT0IEG5ABCC6L87"));
+        assert!(!validate_lei("HWUPKR0MPOU8FGXBT395"));
+        assert!(!validate_lei("hwupkr0mpou8fgxbt394"));
+        assert!(!validate_lei("HWUPKR0MPOU8FGXBT39"));
+    }
+
+    #[test]
+    fn first_row_wins_and_errors() {
+        let csv = "ISIN,LEI\n\
+                   US0378331005,HWUPKR0MPOU8FGXBT394\n\
+                   US0378331005,INR2EJN1ERAN0W5ZP974\n";
+        let index = LeiIndex::from_gleif_csv(csv.as_bytes()).unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.lei_count(), 1);
+        assert_eq!(
+            index.lei(&CUSIP::parse("037833100").unwrap()),
+            Some("HWUPKR0MPOU8FGXBT394")
+        );
+        assert_eq!(index.cusips("INR2EJN1ERAN0W5ZP974").count(), 0);
+        assert_eq!(index.cusips("not an LEI").count(), 0);
+
+        let csv = "LEI,ISIN\nHWUPKR0MPOU8FGXBT394,US0378331006\n";
+        assert!(matches!(
+            LeiIndex::from_gleif_csv(csv.as_bytes()),
+            Err(LeiIndexError::InvalidIsin { line: 2, .. })
+        ));
+        let csv = "LEI,ISIN\n\nHWUPKR0MPOU8FGXBT395,US0378331005\n";
+        assert!(matches!(
+            LeiIndex::from_gleif_csv(csv.as_bytes()),
+            Err(LeiIndexError::InvalidLei { line: 3, .. })
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 //! # cusip
 //!
@@ -65,6 +66,10 @@
 //! let is_valid_cusip = cusip::validate(some_string);
 //! ```
 //!
+//! For values missing their _Check Digit_, `payload::Payload` checks the 8 characters that are
+//! there and completes them into a `CUSIP`. To build CUSIPs from their fields, including CINS
+//! identifiers and private-use CUSIPs, use `build_from_parts()` or a `builder::CusipBuilder`.
+//!
 //! ## CUSIP
 //!
 //! Since its adoption in 1968, CUSIP has been the standard security identifier for:
@@ -140,6 +145,9 @@
 //! via `CINS::issue_num`, though its the same as for the CUSIP. See the CINS documentation for
 //! more details.
 //!
+//! To branch on the country code, use `CUSIP::domicile()`, which returns a `country::Domicile`
+//! holding a `country::CinsCountryCode` for CINS identifiers.
+//!
 //! The country codes are:
 //!
 //! |code|region        |code|region     |code|region       |code|region         |
@@ -168,9 +176,61 @@
 //! the "PPN System". They are '`*`' (value 36), '`@`' (value 37) and '`#`' (value 38) (see section
 //! A.3 "Treatment of Alphabetic Characters".
 //!
-//! CUSIPs using these extended characters are not supported by this crate because the extended
-//! characters are not supported by ISINs, and CUSIPs are incorporated as the _Security Identifier_
-//! for ISINs for certain _Country Codes_.
+//! CUSIPs using these extended characters are not supported by the `CUSIP` type because the
+//! extended characters are not supported by ISINs, and CUSIPs are incorporated as the _Security
+//! Identifier_ for ISINs for certain _Country Codes_. The `ppn` module provides a separate `PPN`
+//! type that accepts them, for systems that process private placements.
+//!
+//! ## Optional features
+//!
+//! * `std` (enabled by default): Everything that needs the standard library, which includes all
+//!   the modules that allocate and every other optional feature. Without it the crate is
+//!   `no_std` and never allocates: `CUSIP`, `CINS`, `CUSIPError`, the `checksum` and `pattern`
+//!   modules, the fixed-capacity `array_set::CusipArraySet` and the compile-time `cusip_set!`
+//!   macro are all still available.
+//! * `avro`: Conversions between `CUSIP` and Apache Avro values, plus a canonical schema fragment
+//!   (see the `avro` module).
+//! * `regex`: A lazily compiled `Regex` for `CUSIP_PATTERN` and an `is_match_and_valid()` helper
+//!   (see the `regex` module).
+//! * `clap`: A clap value parser so command line arguments can be declared as `CUSIP` (see the
+//!   `clap` module).
+//! * `cli`: Builds the `cusip-tool` command line tool, for validating, fixing, building and
+//!   converting CUSIPs in bulk. Implies `clap`.
+//! * `rocket`: `FromParam` and `FromFormField` implementations so Rocket routes can take a `CUSIP`
+//!   directly (see the `rocket` module).
+//! * `rusqlite`: Registers `cusip_valid()` and `cusip_check_digit()` SQL functions on a SQLite
+//!   connection (see the `rusqlite` module).
+//! * `datafusion`: Scalar UDFs for validating and decomposing CUSIPs in DataFusion SQL (see the
+//!   `datafusion` module).
+//! * `polars`: A `cusip` namespace on Polars string `Series` and `Expr` values (see the `polars`
+//!   module).
+//! * `tokio`: Async `Stream` combinators and an `AsyncBufRead` line reader for parsing CUSIPs (see
+//!   the `tokio` module).
+//! * `grpc`: A ready-to-mount tonic gRPC service with batched `Validate`, `Explain` and
+//!   `ComputeCheckDigit` RPCs (see the `grpc` module).
+//! * `serde`: `Serialize` and `Deserialize` for `CUSIP` (as its string form), for `CINS`
+//!   (serialize only), for the `IssuerNum`, `IssueNum` and `Payload` parts, for the
+//!   `CusipArraySet`, `CusipColumn` and `IssuerIndex` collections, for `tagged::TaggedCusip`, and
+//!   for `profile::ValidationProfile`, so validation policies can be loaded from TOML or JSON
+//!   files.
+//! * `heed`: `BytesEncode` and `BytesDecode` for `CUSIP`, so LMDB databases opened with heed can
+//!   be keyed directly by CUSIP (see the `heed` module).
+//! * `defmt`: `defmt::Format` for `CUSIP` and `CUSIPError`, for logging on embedded targets. Unlike
+//!   the other optional features it does not need `std` (see the `defmt` module).
+//! * `mmap`: `MappedCusipSet`, which queries a persisted sorted set of CUSIPs in place through a
+//!   memory map, so very large sets open instantly (see the `mmap` module).
+//! * `metrics`: Counters and histograms of parse outcomes, batch sizes and validation latency,
+//!   emitted from the bulk validation APIs through the metrics facade (see the `metrics` module).
+//! * `arrow`: Conversions between `column::CusipColumn` and Arrow `FixedSizeBinaryArray` and
+//!   `StringArray` values, and lets `cusip-tool validate` read the values from a column of Parquet
+//!   files, with `--format parquet --column NAME`.
+//! * `xlsx`: Lets `cusip-tool validate` read the values from a column of Excel (or OpenDocument)
+//!   workbooks, with `--format xlsx --column NAME`. Only useful with `cli`.
+//! * `isin`: Conversions between this crate's types and the `ISIN` of the
+//!   [isin](https://crates.io/crates/isin) crate: `TryFrom<&isin::ISIN>` for `CUSIP`, and
+//!   `From<cusip::isin::ISIN>` for `isin::ISIN`, so `CUSIP::to_isin()` can produce one.
+//! * `testing`: Categorized known-valid CUSIPs for use as fixtures in downstream tests (see the
+//!   `testing` module). Like `defmt`, it does not need `std`.
 //!
 //! ## Related crates
 //!
@@ -182,9 +242,9 @@
 //! * [LEI](https://crates.io/crates/lei): Legal Entity Identifier (ISO 17442:2020)
 //!
 
-use std::fmt;
-use std::str::from_utf8_unchecked;
-use std::str::FromStr;
+use core::fmt;
+use core::str::from_utf8_unchecked;
+use core::str::FromStr;
 
 pub mod checksum;
 
@@ -193,6 +253,141 @@ use checksum::checksum_table;
 pub mod error;
 pub use error::CUSIPError;
 
+#[cfg(feature = "std")]
+pub mod analytics;
+
+pub mod array_set;
+
+pub mod builder;
+
+#[cfg(feature = "std")]
+pub mod column;
+
+#[cfg(feature = "std")]
+pub mod concurrent_set;
+
+pub mod country;
+
+#[cfg(feature = "std")]
+mod csv;
+
+#[cfg(feature = "std")]
+pub mod dedup;
+
+#[cfg(feature = "std")]
+pub mod detect;
+
+pub mod identifier;
+
+#[cfg(feature = "std")]
+pub mod io;
+
+pub mod isin;
+
+#[cfg(feature = "std")]
+pub mod issuer_index;
+
+#[cfg(feature = "std")]
+pub mod lei;
+
+pub mod pattern;
+
+pub mod payload;
+
+pub mod ppn;
+
+#[cfg(feature = "std")]
+pub mod profile;
+
+#[cfg(feature = "std")]
+pub mod quality;
+
+#[cfg(feature = "std")]
+pub mod remap;
+
+#[cfg(feature = "std")]
+pub mod report;
+
+#[cfg(feature = "std")]
+pub mod resolver;
+
+#[cfg(feature = "std")]
+pub mod sample;
+
+pub mod scan;
+
+#[cfg(feature = "std")]
+pub mod sort;
+
+pub mod static_set;
+
+#[cfg(feature = "std")]
+pub mod tagged;
+
+#[cfg(feature = "std")]
+pub mod vectors;
+
+#[cfg(feature = "std")]
+pub mod xref;
+
+#[cfg(feature = "avro")]
+pub mod avro;
+
+#[cfg(feature = "regex")]
+pub mod regex;
+
+#[cfg(feature = "clap")]
+pub mod clap;
+
+#[cfg(feature = "rocket")]
+pub mod rocket;
+
+#[cfg(feature = "rusqlite")]
+pub mod rusqlite;
+
+#[cfg(feature = "datafusion")]
+pub mod datafusion;
+
+#[cfg(feature = "polars")]
+pub mod polars;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "heed")]
+pub mod heed;
+
+#[cfg(feature = "defmt")]
+pub mod defmt;
+
+#[cfg(feature = "mmap")]
+pub mod mmap;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "isin")]
+mod isin_crate;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// A regular expression matching the structure of a CUSIP: six uppercase alphanumeric characters
+/// for the _Issuer Number_, two for the _Issue Number_, and one decimal digit for the _Check
+/// Digit_. The pattern is anchored at both ends.
+///
+/// The pattern does *not* verify the _Check Digit_, so a string matching it may still be rejected
+/// by `validate()`. It is suitable for places that only accept a pattern, such as JSON Schema
+/// `pattern` keywords, API gateway rules or database `CHECK` constraints. The `regex` feature
+/// provides a compiled form and a helper that also verifies the _Check Digit_.
+pub const CUSIP_PATTERN: &str = "^[0-9A-Z]{6}[0-9A-Z]{2}[0-9]$";
+
 /// Compute the _Check Digit_ for an array of u8. No attempt is made to ensure the input string
 /// is in the CUSIP payload format or length. If an illegal character (not an ASCII digit and not
 /// an ASCII uppercase letter) is encountered, this function will panic.
@@ -263,51 +458,39 @@ pub fn parse_loose(value: &str) -> Result<CUSIP, CUSIPError> {
 /// Build a CUSIP from a _Payload_ (an already-concatenated _Issuer Number_ and _Issue Number_). The
 /// _Check Digit_ is automatically computed.
 pub fn build_from_payload(payload: &str) -> Result<CUSIP, CUSIPError> {
-    if payload.len() != 8 {
-        return Err(CUSIPError::InvalidPayloadLength { was: payload.len() });
-    }
-    let b = &payload.as_bytes()[0..8];
-
-    let issuer_num = &b[0..6];
-    validate_issuer_num_format(issuer_num)?;
-
-    let issue_num = &b[6..8];
-    validate_issue_num_format(issue_num)?;
-
-    let mut bb = [0u8; 9];
-
-    bb[0..8].copy_from_slice(b);
-    bb[8] = compute_check_digit(b);
-
-    Ok(CUSIP(bb))
+    payload::Payload::parse(payload).map(CUSIP::from)
 }
 
 /// Build a CUSIP from its parts: an _Issuer Number_ and an _Issue Number_. The _Check Digit_ is
 /// automatically computed.
 pub fn build_from_parts(issuer_num: &str, issue_num: &str) -> Result<CUSIP, CUSIPError> {
-    if issuer_num.len() != 6 {
-        return Err(CUSIPError::InvalidIssuerNumLength {
-            was: issuer_num.len(),
-        });
-    }
-    let issuer_num: &[u8] = &issuer_num.as_bytes()[0..6];
-    validate_issuer_num_format(issuer_num)?;
+    let issuer = payload::IssuerNum::parse(issuer_num)?;
+    let issue = payload::IssueNum::parse(issue_num)?;
+    Ok(payload::Payload::from_parts(issuer, issue).to_cusip())
+}
 
-    if issue_num.len() != 2 {
-        return Err(CUSIPError::InvalidIssueNumLength {
-            was: issue_num.len(),
-        });
+/// Normalize a string to the canonical form of the CUSIP it holds, accepting the same input as
+/// `CUSIP::parse_loose()`. When no letters need uppercasing the result borrows from the input
+/// (trimmed, if it had surrounding whitespace), so clean input is never copied.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// assert!(matches!(cusip::normalize_cow("037833100"), Ok(Cow::Borrowed("037833100"))));
+/// assert!(matches!(cusip::normalize_cow(" 037833100 "), Ok(Cow::Borrowed("037833100"))));
+/// assert!(matches!(cusip::normalize_cow("09739d100"), Ok(Cow::Owned(s)) if s == "09739D100"));
+/// ```
+#[cfg(feature = "std")]
+pub fn normalize_cow(value: &str) -> Result<std::borrow::Cow<'_, str>, CUSIPError> {
+    let cusip = CUSIP::parse_loose(value)?;
+    let trimmed = value.trim();
+    if trimmed.as_bytes() == cusip.as_bytes() {
+        Ok(std::borrow::Cow::Borrowed(trimmed))
+    } else {
+        Ok(std::borrow::Cow::Owned(cusip.to_string()))
     }
-    let issue_num: &[u8] = &issue_num.as_bytes()[0..2];
-    validate_issue_num_format(issue_num)?;
-
-    let mut bb = [0u8; 9];
-
-    bb[0..6].copy_from_slice(issuer_num);
-    bb[6..8].copy_from_slice(issue_num);
-    bb[8] = compute_check_digit(&bb[0..8]);
-
-    Ok(CUSIP(bb))
 }
 
 /// Test whether or not the passed string is in valid CUSIP format, without producing a CUSIP struct
@@ -420,7 +603,7 @@ pub struct ReadmeDoctests;
 pub struct CUSIP([u8; 9]);
 
 impl fmt::Display for CUSIP {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let temp = unsafe { from_utf8_unchecked(self.as_bytes()) }; // This is safe because we know it is ASCII
         write!(f, "{temp}")
     }
@@ -514,9 +697,115 @@ impl CUSIP {
     /// and format.
     #[inline]
     pub fn parse_loose(value: &str) -> Result<CUSIP, CUSIPError> {
-        let uc = value.to_ascii_uppercase();
-        let temp = uc.trim();
-        Self::parse(temp)
+        let temp = value.trim();
+        if temp.len() != 9 {
+            return Self::parse(temp);
+        }
+
+        // Uppercase into a buffer on the stack, so this works without allocating
+        let mut bb = [0u8; 9];
+        bb.copy_from_slice(temp.as_bytes());
+        bb.make_ascii_uppercase();
+        Self::from_bytes(&bb)
+    }
+
+    /// Parse a string that is either a full 9-character CUSIP (whose _Check Digit_ is verified) or
+    /// an 8-character _Payload_ (whose _Check Digit_ is computed). Returns the CUSIP along with
+    /// `true` if the input supplied the _Check Digit_, or `false` if it was computed.
+    ///
+    /// This suits data feeds that do not agree on whether to include the _Check Digit_. Any other
+    /// length is reported as an `InvalidCUSIPLength` error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let (cusip, supplied) = CUSIP::parse_8_or_9("03783310").unwrap();
+    /// assert_eq!(cusip.to_string(), "037833100");
+    /// assert!(!supplied);
+    ///
+    /// let (cusip, supplied) = CUSIP::parse_8_or_9("037833100").unwrap();
+    /// assert_eq!(cusip.to_string(), "037833100");
+    /// assert!(supplied);
+    /// ```
+    pub fn parse_8_or_9(value: &str) -> Result<(CUSIP, bool), CUSIPError> {
+        match value.len() {
+            8 => build_from_payload(value).map(|cusip| (cusip, false)),
+            9 => Self::parse(value).map(|cusip| (cusip, true)),
+            was => Err(CUSIPError::InvalidCUSIPLength { was }),
+        }
+    }
+
+    /// Parse the 9-byte field starting at `offset` in a fixed-width record, such as a line of a
+    /// mainframe-style data file. A record that ends before the field does is reported as an
+    /// `InvalidCUSIPLength` error with the number of bytes that were available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let record = b"0001APPLE INC       037833100USD";
+    /// let cusip = CUSIP::parse_at(record, 20).unwrap();
+    /// assert_eq!(cusip.to_string(), "037833100");
+    /// ```
+    pub fn parse_at(record: &[u8], offset: usize) -> Result<CUSIP, CUSIPError> {
+        let field = record.get(offset..).unwrap_or_default();
+        match field.get(..9) {
+            Some(field) => Self::from_bytes(field),
+            None => Err(CUSIPError::InvalidCUSIPLength { was: field.len() }),
+        }
+    }
+
+    /// Parse the `width`-byte field starting at `offset` in a fixed-width record, allowing the
+    /// field to be padded with whitespace and/or contain lowercase letters as `parse_loose()`
+    /// does. A record that ends before the field does is treated as if it had been padded, since
+    /// trailing spaces are often stripped from such records.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let record = b"0001  09739d100  BOISE";
+    /// let cusip = CUSIP::parse_at_loose(record, 4, 13).unwrap();
+    /// assert_eq!(cusip.to_string(), "09739D100");
+    /// ```
+    pub fn parse_at_loose(record: &[u8], offset: usize, width: usize) -> Result<CUSIP, CUSIPError> {
+        let field = record.get(offset..).unwrap_or_default();
+        let field = field[..width.min(field.len())].trim_ascii();
+        if field.len() != 9 {
+            return Err(CUSIPError::InvalidCUSIPLength { was: field.len() });
+        }
+
+        let mut bb = [0u8; 9];
+        bb.copy_from_slice(field);
+        bb.make_ascii_uppercase();
+        Self::from_bytes(&bb)
+    }
+
+    /// Compares two CUSIPs in constant time: every byte is examined whether or not an earlier one
+    /// differs, so the time taken does not reveal how much of a secret CUSIP (such as an entry on
+    /// a restricted list) a probe matched. Use `==` where timing does not matter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let restricted = CUSIP::parse("037833100").unwrap();
+    /// assert!(restricted.ct_eq(&CUSIP::parse("037833100").unwrap()));
+    /// assert!(!restricted.ct_eq(&CUSIP::parse("037833209").unwrap()));
+    /// ```
+    pub fn ct_eq(&self, other: &CUSIP) -> bool {
+        let diff = self
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        // Keep the optimizer from turning the accumulation back into an early exit
+        core::hint::black_box(diff) == 0
     }
 
     /// Internal convenience function for treating the ASCII characters as a byte-array slice.
@@ -543,7 +832,7 @@ impl CUSIP {
     /// let non_cins_cusip = CUSIP::parse("037833100").unwrap();
     /// assert!(non_cins_cusip.as_cins().is_none());
     /// ```
-    pub fn as_cins(&self) -> Option<CINS> {
+    pub fn as_cins(&self) -> Option<CINS<'_>> {
         CINS::new(self)
     }
 
@@ -577,6 +866,43 @@ impl CUSIP {
         cins_country_code(self.as_bytes()[0])
     }
 
+    /// Returns where the _Issuer_ is domiciled: North America for a conventional CUSIP, or the
+    /// CINS _Country Code_ for a CINS identifier.
+    ///
+    /// ```
+    /// use cusip::country::{CinsCountryCode, Domicile};
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::parse("S08000AA9").unwrap();
+    /// assert_eq!(cusip.domicile(), Domicile::Cins(CinsCountryCode::SouthAfrica));
+    /// ```
+    pub fn domicile(&self) -> country::Domicile {
+        match country::CinsCountryCode::from_char(char::from(self.as_bytes()[0])) {
+            Some(code) => country::Domicile::Cins(code),
+            None => country::Domicile::NorthAmerica,
+        }
+    }
+
+    /// Returns true if this is a CINS identifier with the _Country Code_.
+    ///
+    /// ```
+    /// use cusip::country::CinsCountryCode;
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::parse("S08000AA9").unwrap();
+    /// assert!(cusip.is_cins_for(CinsCountryCode::SouthAfrica));
+    /// assert!(!cusip.is_cins_for(CinsCountryCode::Spain));
+    /// ```
+    pub fn is_cins_for(&self, code: country::CinsCountryCode) -> bool {
+        self.as_bytes()[0] == code as u8
+    }
+
+    /// Returns the broad region of the _Issuer_'s domicile (see `domicile()`), or `None` for a
+    /// CINS identifier with an unused _Country Code_.
+    pub fn region(&self) -> Option<country::Region> {
+        self.domicile().region()
+    }
+
     /// Return just the _Issuer Number_ portion of the CUSIP.
     pub fn issuer_num(&self) -> &str {
         unsafe { from_utf8_unchecked(&self.as_bytes()[0..6]) } // This is safe because we know it is ASCII
@@ -604,6 +930,46 @@ impl CUSIP {
         unsafe { from_utf8_unchecked(&self.as_bytes()[6..8]) } // This is safe because we know it is ASCII
     }
 
+    /// Returns the _Issuer Number_ as a `payload::IssuerNum`, which keeps its validity with it.
+    pub fn issuer(&self) -> payload::IssuerNum {
+        payload::Payload::from(*self).issuer()
+    }
+
+    /// Returns the _Issue Number_ as a `payload::IssueNum`, which keeps its validity with it.
+    pub fn issue(&self) -> payload::IssueNum {
+        payload::Payload::from(*self).issue()
+    }
+
+    /// Returns the CUSIP of another issue of the same _Issuer_: this one's _Issuer Number_ with
+    /// the given _Issue Number_, and the _Check Digit_ recomputed.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let apple = CUSIP::parse("037833100").unwrap();
+    /// assert_eq!(apple.with_issue_num("AK").unwrap().to_string(), "037833AK6");
+    /// assert!(apple.with_issue_num("a").is_err());
+    /// ```
+    pub fn with_issue_num(&self, issue_num: &str) -> Result<CUSIP, CUSIPError> {
+        let issue = payload::IssueNum::parse(issue_num)?;
+        Ok(payload::Payload::from_parts(self.issuer(), issue).to_cusip())
+    }
+
+    /// Returns the CUSIP of the _Issuer_'s primary issue: _Issue Number_ "10", conventionally the
+    /// first equity issue, with the _Check Digit_ recomputed. Useful for collapsing the share
+    /// classes of an _Issuer_ to one line. Nothing checks that the issue exists.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let class_b = CUSIP::parse("084670702").unwrap();
+    /// assert_eq!(class_b.base_issue().to_string(), "084670108");
+    /// ```
+    pub fn base_issue(&self) -> CUSIP {
+        // "10" is a valid Issue Number, so this cannot fail
+        self.with_issue_num("10").unwrap()
+    }
+
     /// Returns true if the _Issue Number_ is reserved for private use.
     pub fn is_private_issue(&self) -> bool {
         let bs = self.as_bytes();
@@ -613,6 +979,20 @@ impl CUSIP {
         nine_tens && (digit_ones || letter_ones)
     }
 
+    /// Returns the kind of _Issue Number_: all digits, by convention an equity, or containing
+    /// letters, by convention fixed income. This is only the numbering convention, so it can be
+    /// wrong about a particular security.
+    ///
+    /// ```
+    /// use cusip::{IssueKind, CUSIP};
+    ///
+    /// assert_eq!(CUSIP::parse("037833100").unwrap().issue_kind(), IssueKind::Equity);
+    /// assert_eq!(CUSIP::parse("037833AK6").unwrap().issue_kind(), IssueKind::FixedIncome);
+    /// ```
+    pub fn issue_kind(&self) -> IssueKind {
+        self.issue().kind()
+    }
+
     /// Returns true if the CUSIP is reserved for private use (i.e., either it has a private issuer
     /// or it is a private issue).
     pub fn is_private_use(&self) -> bool {
@@ -628,6 +1008,170 @@ impl CUSIP {
     pub fn check_digit(&self) -> char {
         self.as_bytes()[8] as char
     }
+
+    /// Returns the ISIN with this CUSIP as its national identifier and the given country code
+    /// (such as `"US"` or `"CA"`), with the ISIN _Check Digit_ computed. Returns `None` if the
+    /// country code is not 2 uppercase ASCII letters. Which country codes take CUSIP-based ISINs
+    /// is up to the caller.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::parse("037833100").unwrap();
+    /// assert_eq!(cusip.to_isin("US").unwrap().to_string(), "US0378331005");
+    /// assert_eq!(cusip.to_isin("us"), None);
+    /// ```
+    pub fn to_isin(&self, country_code: &str) -> Option<isin::ISIN> {
+        isin::ISIN::from_parts(country_code, &self.0)
+    }
+
+    /// Parses a 12-character CUSIP-based ISIN (see `isin::ISIN::cusip()`), returning the CUSIP
+    /// that is its national identifier. Both the ISIN _Check Digit_ and the CUSIP _Check Digit_
+    /// must be correct.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::from_isin_str("US0378331005").unwrap();
+    /// assert_eq!(cusip.to_string(), "037833100");
+    /// assert!(CUSIP::from_isin_str("US0378331006").is_err());
+    /// assert!(CUSIP::from_isin_str("GB0002634946").is_err());
+    /// ```
+    pub fn from_isin_str(value: &str) -> Result<CUSIP, isin::FromIsinError> {
+        let isin = isin::ISIN::parse(value).ok_or(isin::FromIsinError::InvalidIsin)?;
+        // The same rule as ISIN::cusip()
+        match (CUSIP::parse(isin.nsin()), isin.country_code()) {
+            (Ok(cusip), "US" | "CA") => Ok(cusip),
+            (Ok(cusip), _) if cusip.is_cins() => Ok(cusip),
+            (Err(err), "US" | "CA") => Err(isin::FromIsinError::InvalidCusip(err)),
+            (_, country_code) => {
+                let mut bb = [0_u8; 2];
+                bb.copy_from_slice(country_code.as_bytes());
+                Err(isin::FromIsinError::NotCusipBased { country_code: bb })
+            }
+        }
+    }
+
+    /// Copies the CUSIP's 9 ASCII bytes into the buffer, without allocating or going through the
+    /// formatting machinery.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let mut record = *b"AAPL     ?????????";
+    /// let cusip = CUSIP::parse("037833100").unwrap();
+    /// cusip.fill((&mut record[9..]).try_into().unwrap());
+    /// assert_eq!(&record, b"AAPL     037833100");
+    /// ```
+    pub fn fill(&self, buf: &mut [u8; 9]) {
+        buf.copy_from_slice(&self.0);
+    }
+
+    /// Writes the CUSIP's 9 ASCII bytes to the writer, without allocating or going through the
+    /// formatting machinery.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let mut out = Vec::new();
+    /// CUSIP::parse("037833100").unwrap().write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"037833100");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&self.0)
+    }
+
+    /// Packs the CUSIP into a `u64` by reading the _Payload_ as a base-36 number (digits before
+    /// letters, as in ASCII). The _Check Digit_ is left out since it can be recomputed, so the
+    /// result is always less than 36<sup>8</sup>. Packing preserves order: `a < b` if and only if
+    /// `a.pack() < b.pack()`.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::parse("037833100").unwrap();
+    /// let packed = cusip.pack();
+    /// assert_eq!(CUSIP::unpack(packed), Some(cusip));
+    /// assert!(packed < CUSIP::parse("S08000AA9").unwrap().pack());
+    /// ```
+    pub fn pack(&self) -> u64 {
+        self.as_bytes()[0..8].iter().fold(0, |acc, &b| {
+            let value = match b {
+                b'0'..=b'9' => b - b'0',
+                _ => b - b'A' + 10,
+            };
+            acc * 36 + u64::from(value)
+        })
+    }
+
+    /// Unpacks a value produced by `pack()`, recomputing the _Check Digit_. Returns `None` if the
+    /// value is not less than 36<sup>8</sup>.
+    pub fn unpack(packed: u64) -> Option<CUSIP> {
+        if packed >= PACKED_LIMIT {
+            return None;
+        }
+        let mut bb = [0u8; 9];
+        let mut rest = packed;
+        for b in bb[0..8].iter_mut().rev() {
+            let value = (rest % 36) as u8;
+            *b = if value < 10 {
+                b'0' + value
+            } else {
+                b'A' + value - 10
+            };
+            rest /= 36;
+        }
+        bb[8] = compute_check_digit(&bb[0..8]);
+        Some(CUSIP(bb))
+    }
+
+    /// Assigns the CUSIP to one of `num_shards` partitions, returning a value less than
+    /// `num_shards`. The mapping is fixed, so every process (and every implementation that follows
+    /// it) partitions CUSIPs identically:
+    ///
+    /// 1. Take `x = self.pack()`.
+    /// 2. Mix it with the SplitMix64 finalizer: `x ^= x >> 30; x *= 0xBF58476D1CE4E5B9;
+    ///    x ^= x >> 27; x *= 0x94D049BB133111EB; x ^= x >> 31` (multiplications wrap).
+    /// 3. Return `((x >> 32) * num_shards) >> 32`.
+    ///
+    /// Mixing spreads CUSIPs of the same _Issuer_ across partitions, and the final step maps the
+    /// high 32 bits onto the partitions without the bias of `%`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero.
+    ///
+    /// ```
+    /// use cusip::CUSIP;
+    ///
+    /// let cusip = CUSIP::parse("037833100").unwrap();
+    /// assert!(cusip.partition_key(16) < 16);
+    /// assert_eq!(cusip.partition_key(1), 0);
+    /// ```
+    pub fn partition_key(&self, num_shards: u32) -> u32 {
+        assert!(num_shards > 0, "num_shards must be greater than zero");
+        let mut x = self.pack();
+        x ^= x >> 30;
+        x = x.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        x ^= x >> 27;
+        x = x.wrapping_mul(0x94D0_49BB_1331_11EB);
+        x ^= x >> 31;
+        (((x >> 32) * u64::from(num_shards)) >> 32) as u32
+    }
+}
+
+/// One more than the largest value `CUSIP::pack()` can produce (36 to the 8th power).
+const PACKED_LIMIT: u64 = 36u64.pow(8);
+
+/// The kind of an _Issue Number_, by the numbering conventions: equities are numbered with digits
+/// only, and fixed income issues with letters (and perhaps digits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IssueKind {
+    /// An _Issue Number_ of two digits.
+    Equity,
+    /// An _Issue Number_ containing a letter.
+    FixedIncome,
 }
 
 /// A CINS (CUSIP International Numbering System) identifier.
@@ -842,6 +1386,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_8_or_9_forms() {
+        assert_eq!(
+            CUSIP::parse_8_or_9("09739D10"),
+            Ok((CUSIP::parse("09739D100").unwrap(), false))
+        );
+        assert_eq!(
+            CUSIP::parse_8_or_9("09739D100"),
+            Ok((CUSIP::parse("09739D100").unwrap(), true))
+        );
+        assert!(matches!(
+            CUSIP::parse_8_or_9("09739D101"),
+            Err(CUSIPError::IncorrectCheckDigit { .. })
+        ));
+        assert_eq!(
+            CUSIP::parse_8_or_9("09739D1"),
+            Err(CUSIPError::InvalidCUSIPLength { was: 7 })
+        );
+    }
+
+    #[test]
+    fn parse_fixed_width_fields() {
+        let record = b"BCC 09739d100   X";
+        assert_eq!(
+            CUSIP::parse_at(record, 4),
+            Err(CUSIPError::InvalidIssuerNum { was: *b"09739d" })
+        );
+        assert_eq!(
+            CUSIP::parse_at(record, 12),
+            Err(CUSIPError::InvalidCUSIPLength { was: 5 })
+        );
+        assert_eq!(
+            CUSIP::parse_at(record, 40),
+            Err(CUSIPError::InvalidCUSIPLength { was: 0 })
+        );
+        let bcc = CUSIP::parse("09739D100").unwrap();
+        assert_eq!(CUSIP::parse_at_loose(record, 3, 12), Ok(bcc));
+        assert_eq!(CUSIP::parse_at_loose(b"BCC 09739D100", 3, 12), Ok(bcc));
+        assert_eq!(
+            CUSIP::parse_at_loose(record, 3, 14),
+            Err(CUSIPError::InvalidCUSIPLength { was: 13 })
+        );
+    }
+
+    #[test]
+    fn normalize_cow_borrows_when_possible() {
+        use std::borrow::Cow;
+
+        assert!(matches!(
+            normalize_cow("\tS08000AA9"),
+            Ok(Cow::Borrowed("S08000AA9"))
+        ));
+        assert_eq!(normalize_cow("s08000aa9").unwrap(), "S08000AA9");
+        assert_eq!(
+            normalize_cow("S08000AA8"),
+            Err(CUSIPError::IncorrectCheckDigit {
+                was: b'8',
+                expected: b'9'
+            })
+        );
+    }
+
+    #[test]
+    fn ct_eq_agrees_with_eq() {
+        let cusips =
+            ["037833100", "037833209", "S08000AA9", "09739D100"].map(|s| CUSIP::parse(s).unwrap());
+        for a in &cusips {
+            for b in &cusips {
+                assert_eq!(a.ct_eq(b), a == b);
+            }
+        }
+    }
+
     #[test]
     fn validate_cusip_for_bcc() {
         // Boise Cascade
@@ -1078,4 +1695,108 @@ mod tests {
             CUSIP::parse(&s);
         }
     }
+
+    proptest! {
+        // The proptest generator does not support anchors, so they are stripped off here.
+        #[test]
+        fn pattern_matches_only_fail_on_check_digit(
+            s in CUSIP_PATTERN.trim_start_matches('^').trim_end_matches('$')
+        ) {
+            match CUSIP::parse(&s) {
+                Ok(_) | Err(CUSIPError::IncorrectCheckDigit { .. }) => {}
+                Err(err) => panic!("Expected only check digit failures, but got: {err:?}"),
+            }
+        }
+    }
+    #[test]
+    fn to_isin_round_trips() {
+        for (value, country_code, expected) in [
+            ("037833100", "US", "US0378331005"),
+            ("594918104", "US", "US5949181045"),
+            ("09739D100", "US", "US09739D1000"),
+        ] {
+            let cusip = CUSIP::parse(value).unwrap();
+            let isin = cusip.to_isin(country_code).unwrap();
+            assert_eq!(isin.as_str(), expected);
+            assert_eq!(isin.cusip(), Some(cusip));
+        }
+        let cusip = CUSIP::parse("037833100").unwrap();
+        for country_code in ["", "U", "USA", "u1", "Us"] {
+            assert_eq!(cusip.to_isin(country_code), None);
+        }
+    }
+
+    #[test]
+    fn with_issue_num_keeps_issuer() {
+        let cusip = CUSIP::parse("S08000AA9").unwrap();
+        for issue_num in ["10", "AA", "9Z"] {
+            let sibling = cusip.with_issue_num(issue_num).unwrap();
+            assert_eq!(sibling.issuer_num(), "S08000");
+            assert_eq!(sibling.issue_num(), issue_num);
+            assert!(validate(&sibling.to_string()));
+        }
+        assert_eq!(cusip.with_issue_num("AA"), Ok(cusip));
+        assert_eq!(
+            cusip.with_issue_num("100"),
+            Err(CUSIPError::InvalidIssueNumLength { was: 3 })
+        );
+    }
+
+    #[test]
+    fn base_issue_is_issue_10() {
+        let apple = CUSIP::parse("037833100").unwrap();
+        assert_eq!(apple.base_issue(), apple);
+        let notes = apple.with_issue_num("AK").unwrap();
+        assert_eq!(notes.base_issue(), apple);
+    }
+
+    #[test]
+    fn issue_kinds() {
+        for (value, kind) in [
+            ("037833100", IssueKind::Equity),
+            ("084670702", IssueKind::Equity),
+            ("037833AK6", IssueKind::FixedIncome),
+            ("S08000AA9", IssueKind::FixedIncome),
+            ("9128285M8", IssueKind::FixedIncome),
+        ] {
+            assert_eq!(CUSIP::parse(value).unwrap().issue_kind(), kind, "{value}");
+        }
+    }
+
+    #[test]
+    fn unpack_out_of_range() {
+        assert_eq!(
+            CUSIP::unpack(PACKED_LIMIT - 1).unwrap().payload(),
+            "ZZZZZZZZ"
+        );
+        assert_eq!(CUSIP::unpack(PACKED_LIMIT), None);
+        assert_eq!(CUSIP::unpack(0).unwrap().to_string(), "000000000");
+    }
+
+    #[test]
+    fn partition_key_is_stable() {
+        // These values are part of the documented mapping and must never change
+        for (cusip, by_16, by_1000) in [
+            ("037833100", 15, 994),
+            ("09739D100", 0, 21),
+            ("S08000AA9", 2, 157),
+        ] {
+            let cusip = CUSIP::parse(cusip).unwrap();
+            assert_eq!(cusip.partition_key(16), by_16);
+            assert_eq!(cusip.partition_key(1000), by_1000);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn pack_round_trips_and_preserves_order(
+            a in "[0-9A-Z]{8}",
+            b in "[0-9A-Z]{8}"
+        ) {
+            let a = build_from_payload(&a).unwrap();
+            let b = build_from_payload(&b).unwrap();
+            prop_assert_eq!(CUSIP::unpack(a.pack()), Some(a));
+            prop_assert_eq!(a.cmp(&b), a.pack().cmp(&b.pack()));
+        }
+    }
 }
@@ -0,0 +1,196 @@
+#![warn(missing_docs)]
+//! # cusip::metrics
+//!
+//! Instrumentation of the bulk validation APIs through the [metrics](https://docs.rs/metrics)
+//! facade (requires the `metrics` feature).
+//!
+//! With this feature enabled, `report::ValidationReport` and `column::CusipColumn::try_from_raw()`
+//! emit the metrics below to whatever recorder the application installed (for example a Prometheus
+//! exporter), so data quality can be monitored without wrapping every call:
+//!
+//! * `cusip_parse_successes_total` (counter): values that were valid CUSIPs.
+//! * `cusip_parse_failures_total` (counter, labeled by `kind`, such as `"IncorrectCheckDigit"`):
+//!   values that were not.
+//! * `cusip_batch_size` (histogram, labeled by `api`): the number of values in each batch.
+//! * `cusip_batch_validation_seconds` (histogram, labeled by `api`): how long each batch took.
+//!
+//! The `api` label is `"report"` for `ValidationReport::from_values()` and `"column"` for
+//! `CusipColumn::try_from_raw()`. Call `describe()` once at startup to register units and
+//! descriptions with the recorder.
+//!
+//! ```
+//! cusip::metrics::describe();
+//!
+//! // Without an installed recorder the metrics are simply discarded
+//! let report = cusip::report::ValidationReport::from_values(["037833100", "037833101"]);
+//! assert_eq!(report.invalid(), 1);
+//! ```
+
+use std::time::Instant;
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+
+use crate::CUSIPError;
+
+/// The name of the counter of valid values.
+pub const PARSE_SUCCESSES: &str = "cusip_parse_successes_total";
+
+/// The name of the counter of invalid values, labeled by `kind`.
+pub const PARSE_FAILURES: &str = "cusip_parse_failures_total";
+
+/// The name of the histogram of batch sizes, labeled by `api`.
+pub const BATCH_SIZE: &str = "cusip_batch_size";
+
+/// The name of the histogram of batch validation times, labeled by `api`.
+pub const BATCH_VALIDATION_SECONDS: &str = "cusip_batch_validation_seconds";
+
+/// Registers the units and descriptions of this crate's metrics with the installed recorder.
+pub fn describe() {
+    describe_counter!(
+        PARSE_SUCCESSES,
+        Unit::Count,
+        "Values that were valid CUSIPs"
+    );
+    describe_counter!(
+        PARSE_FAILURES,
+        Unit::Count,
+        "Values that were not valid CUSIPs, by kind of error"
+    );
+    describe_histogram!(BATCH_SIZE, Unit::Count, "Number of values in each batch");
+    describe_histogram!(
+        BATCH_VALIDATION_SECONDS,
+        Unit::Seconds,
+        "Time taken to validate each batch"
+    );
+}
+
+/// Counts valid values.
+pub(crate) fn record_successes(n: usize) {
+    counter!(PARSE_SUCCESSES).increment(n as u64);
+}
+
+/// Counts an invalid value.
+pub(crate) fn record_failure(error: &CUSIPError) {
+    counter!(PARSE_FAILURES, "kind" => error.kind_name()).increment(1);
+}
+
+/// Records the size of a batch and the time taken since it `started`.
+pub(crate) fn record_batch(api: &'static str, size: usize, started: Instant) {
+    histogram!(BATCH_SIZE, "api" => api).record(size as f64);
+    histogram!(BATCH_VALIDATION_SECONDS, "api" => api).record(started.elapsed().as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use ::metrics::{
+        Counter, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder, SharedString,
+    };
+
+    use crate::column::CusipColumn;
+    use crate::report::ValidationReport;
+
+    use super::*;
+
+    /// Counts counter increments and histogram samples by metric name and labels.
+    #[derive(Default)]
+    struct TestRecorder {
+        counters: Mutex<BTreeMap<String, Arc<AtomicU64>>>,
+        histograms: Mutex<BTreeMap<String, Arc<Samples>>>,
+    }
+
+    #[derive(Default)]
+    struct Samples(AtomicU64);
+
+    impl HistogramFn for Samples {
+        fn record(&self, _value: f64) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn name(key: &Key) -> String {
+        let labels: Vec<String> = key
+            .labels()
+            .map(|l| format!("{}={}", l.key(), l.value()))
+            .collect();
+        format!("{}{{{}}}", key.name(), labels.join(","))
+    }
+
+    impl TestRecorder {
+        fn counter(&self, name: &str) -> u64 {
+            self.counters
+                .lock()
+                .unwrap()
+                .get(name)
+                .map_or(0, |c| c.load(Ordering::Relaxed))
+        }
+
+        fn samples(&self, name: &str) -> u64 {
+            self.histograms
+                .lock()
+                .unwrap()
+                .get(name)
+                .map_or(0, |h| h.0.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let mut counters = self.counters.lock().unwrap();
+            Counter::from_arc(counters.entry(name(key)).or_default().clone())
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            let mut histograms = self.histograms.lock().unwrap();
+            Histogram::from_arc(histograms.entry(name(key)).or_default().clone())
+        }
+    }
+
+    #[test]
+    fn report_emits_metrics() {
+        let recorder = TestRecorder::default();
+        ::metrics::with_local_recorder(&recorder, || {
+            ValidationReport::from_values(["037833100", "037833101", "03783310", "594918104"]);
+        });
+        assert_eq!(recorder.counter("cusip_parse_successes_total{}"), 2);
+        assert_eq!(
+            recorder.counter("cusip_parse_failures_total{kind=IncorrectCheckDigit}"),
+            1
+        );
+        assert_eq!(
+            recorder.counter("cusip_parse_failures_total{kind=InvalidCUSIPLength}"),
+            1
+        );
+        assert_eq!(recorder.samples("cusip_batch_size{api=report}"), 1);
+        assert_eq!(
+            recorder.samples("cusip_batch_validation_seconds{api=report}"),
+            1
+        );
+    }
+
+    #[test]
+    fn column_emits_metrics() {
+        let recorder = TestRecorder::default();
+        ::metrics::with_local_recorder(&recorder, || {
+            CusipColumn::try_from_raw(b"037833100594918104".to_vec()).unwrap();
+            CusipColumn::try_from_raw(b"037833101".to_vec()).unwrap_err();
+        });
+        assert_eq!(recorder.counter("cusip_parse_successes_total{}"), 2);
+        assert_eq!(
+            recorder.counter("cusip_parse_failures_total{kind=IncorrectCheckDigit}"),
+            1
+        );
+        assert_eq!(recorder.samples("cusip_batch_size{api=column}"), 2);
+    }
+}
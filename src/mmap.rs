@@ -0,0 +1,188 @@
+#![warn(missing_docs)]
+//! # cusip::mmap
+//!
+//! Large persisted sets of CUSIPs that are queried in place through a memory map (requires the
+//! `mmap` feature).
+//!
+//! The on-disk format is simply the sorted, deduplicated CUSIPs, 9 ASCII bytes each with no header
+//! or separators (the same layout as `column::CusipColumn::as_bytes()` for a sorted, deduplicated
+//! column). `write_set()` produces such a file. `MappedCusipSet::open()` maps one without reading
+//! or deserializing it, and membership tests are a binary search over the mapped records, so even
+//! a universe of hundreds of millions of CUSIPs is ready to query immediately, and processes
+//! mapping the same file share its pages.
+//!
+//! ```
+//! use cusip::mmap::{write_set, MappedCusipSet};
+//! use cusip::CUSIP;
+//!
+//! # let path = std::env::temp_dir().join(format!("cusip-mmap-doc-{}", std::process::id()));
+//! let cusips = ["594918104", "037833100", "594918104"].map(|s| CUSIP::parse(s).unwrap());
+//! let mut file = std::fs::File::create(&path).unwrap();
+//! assert_eq!(write_set(&mut file, cusips).unwrap(), 2);
+//! drop(file);
+//!
+//! // Safety: nothing modifies the file while it is mapped
+//! let set = unsafe { MappedCusipSet::open(&path) }.unwrap();
+//! assert_eq!(set.len(), 2);
+//! assert!(set.contains(&CUSIP::parse("037833100").unwrap()));
+//! assert!(!set.contains(&CUSIP::parse("09739D100").unwrap()));
+//! # drop(set);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+
+use ::memmap2::Mmap;
+
+use crate::{CUSIPError, CUSIP};
+
+/// Writes the CUSIPs in the on-disk set format: sorted, without duplicates. Returns the number of
+/// CUSIPs written.
+pub fn write_set<W: Write>(
+    writer: &mut W,
+    cusips: impl IntoIterator<Item = CUSIP>,
+) -> io::Result<usize> {
+    let mut cusips: Vec<CUSIP> = cusips.into_iter().collect();
+    cusips.sort_unstable();
+    cusips.dedup();
+    for cusip in &cusips {
+        cusip.write_to(writer)?;
+    }
+    Ok(cusips.len())
+}
+
+/// A read-only set of CUSIPs backed by a memory-mapped file in the on-disk set format.
+#[derive(Debug)]
+pub struct MappedCusipSet {
+    map: Mmap,
+}
+
+impl MappedCusipSet {
+    /// Maps the set file at `path`. Only the file length is checked, so opening is immediate
+    /// regardless of the size of the set; use `verify()` to check the contents.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified (by this or any other process) while it is mapped, as that
+    /// is undefined behavior. See `memmap2::Mmap::map()`.
+    #[allow(clippy::manual_is_multiple_of)] // usize::is_multiple_of() needs Rust 1.87
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let map = Mmap::map(&file)?;
+        if map.len() % 9 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("set file length {} bytes is not a multiple of 9", map.len()),
+            ));
+        }
+        Ok(MappedCusipSet { map })
+    }
+
+    fn record(&self, index: usize) -> &[u8] {
+        &self.map[index * 9..index * 9 + 9]
+    }
+
+    /// Returns the number of CUSIPs in the set.
+    pub fn len(&self) -> usize {
+        self.map.len() / 9
+    }
+
+    /// Returns true if the set holds no CUSIPs.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns true if the set contains the CUSIP.
+    pub fn contains(&self, cusip: &CUSIP) -> bool {
+        let target = cusip.as_bytes();
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.record(mid).cmp(target) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return true,
+            }
+        }
+        false
+    }
+
+    /// Returns the CUSIP at the (zero-based) index in ascending order, if there is one, or an
+    /// error if the record there is not a valid CUSIP.
+    pub fn get(&self, index: usize) -> Option<Result<CUSIP, CUSIPError>> {
+        (index < self.len()).then(|| CUSIP::from_bytes(self.record(index)))
+    }
+
+    /// Returns an iterator over the CUSIPs in the set, in ascending order. Records that are not
+    /// valid CUSIPs are returned as errors.
+    pub fn iter(&self) -> impl Iterator<Item = Result<CUSIP, CUSIPError>> + '_ {
+        self.map.chunks_exact(9).map(CUSIP::from_bytes)
+    }
+
+    /// Reads the whole file, checking that every record is a valid CUSIP and that the records are
+    /// in strictly ascending order. Returns the index of the first bad record, if any.
+    pub fn verify(&self) -> Result<(), usize> {
+        let mut previous: Option<&[u8]> = None;
+        for index in 0..self.len() {
+            let record = self.record(index);
+            if CUSIP::from_bytes(record).is_err() || previous.is_some_and(|p| p >= record) {
+                return Err(index);
+            }
+            previous = Some(record);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("cusip-mmap-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let cusips: Vec<CUSIP> = ["S08000AA9", "594918104", "037833100", "09739D100"]
+            .iter()
+            .map(|s| CUSIP::parse(s).unwrap())
+            .collect();
+        write_set(&mut File::create(&path).unwrap(), cusips.iter().copied()).unwrap();
+
+        let set = unsafe { MappedCusipSet::open(&path) }.unwrap();
+        assert_eq!(set.verify(), Ok(()));
+        for cusip in &cusips {
+            assert!(set.contains(cusip));
+        }
+        assert!(!set.contains(&CUSIP::parse("G0052B105").unwrap()));
+        assert_eq!(set.get(0), Some(Ok(CUSIP::parse("037833100").unwrap())));
+        assert_eq!(set.get(4), None);
+        assert_eq!(set.iter().count(), 4);
+        drop(set);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_files() {
+        let path = temp_path("bad");
+        std::fs::write(&path, b"0378331000").unwrap();
+        let err = unsafe { MappedCusipSet::open(&path) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        std::fs::write(&path, b"594918104037833100").unwrap();
+        let set = unsafe { MappedCusipSet::open(&path) }.unwrap();
+        assert_eq!(set.verify(), Err(1));
+        drop(set);
+
+        std::fs::write(&path, b"").unwrap();
+        let set = unsafe { MappedCusipSet::open(&path) }.unwrap();
+        assert!(set.is_empty());
+        assert!(!set.contains(&CUSIP::parse("037833100").unwrap()));
+        drop(set);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
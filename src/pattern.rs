@@ -0,0 +1,244 @@
+#![warn(missing_docs)]
+//! # cusip::pattern
+//!
+//! Wildcard patterns for matching CUSIPs, with a textual syntax suitable for configuration files
+//! and command line arguments.
+//!
+//! A pattern has one character for each position of the CUSIP, from the left:
+//!
+//! * `0`-`9` or `A`-`Z` matches exactly that character.
+//! * `?` matches any character.
+//! * `#` matches any decimal digit.
+//!
+//! A pattern must cover all 9 positions, unless it ends with `*`, which matches whatever remains.
+//! So `037833*` matches every CUSIP of one _Issuer_, and `??????##?` matches every CUSIP with a
+//! numeric _Issue Number_.
+//!
+//! ```
+//! use cusip::pattern::CusipPattern;
+//! use cusip::CUSIP;
+//!
+//! let pattern: CusipPattern = "037833*".parse().unwrap();
+//! assert!(pattern.matches(&CUSIP::parse("037833100").unwrap()));
+//! assert!(!pattern.matches(&CUSIP::parse("594918104").unwrap()));
+//! assert_eq!(pattern.to_string(), "037833*");
+//!
+//! let err = "0378-3*".parse::<CusipPattern>().unwrap_err();
+//! assert_eq!(err.to_string(), "invalid character '-' at position 4 of the pattern");
+//! ```
+
+use core::fmt;
+use core::str::FromStr;
+
+use crate::CUSIP;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Element {
+    Any,
+    Digit,
+    Literal(u8),
+}
+
+impl Element {
+    fn matches(self, b: u8) -> bool {
+        match self {
+            Element::Any => true,
+            Element::Digit => b.is_ascii_digit(),
+            Element::Literal(lit) => b == lit,
+        }
+    }
+
+    fn as_char(self) -> char {
+        match self {
+            Element::Any => '?',
+            Element::Digit => '#',
+            Element::Literal(lit) => lit as char,
+        }
+    }
+}
+
+/// A wildcard pattern over CUSIPs. See the module documentation for the syntax.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CusipPattern {
+    elements: [Element; 9],
+    len: usize,
+    open_ended: bool,
+}
+
+/// All the ways parsing a pattern could fail. Positions are zero-based character positions in the
+/// pattern text.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern has a character that is not allowed.
+    InvalidChar {
+        /// The position of the character
+        position: usize,
+        /// The character we found
+        was: char,
+    },
+    /// The pattern has a `*` before its end.
+    MisplacedStar {
+        /// The position of the `*`
+        position: usize,
+    },
+    /// The pattern has more than 9 positions.
+    TooLong {
+        /// The position of the first character past the ninth
+        position: usize,
+    },
+    /// The pattern has fewer than 9 positions and does not end with `*`.
+    TooShort {
+        /// The number of positions we found
+        was: usize,
+    },
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::InvalidChar { position, was } => {
+                write!(
+                    f,
+                    "invalid character {was:?} at position {position} of the pattern"
+                )
+            }
+            PatternError::MisplacedStar { position } => {
+                write!(
+                    f,
+                    "'*' at position {position} is not at the end of the pattern"
+                )
+            }
+            PatternError::TooLong { position } => {
+                write!(
+                    f,
+                    "pattern is longer than 9 positions at position {position}"
+                )
+            }
+            PatternError::TooShort { was } => {
+                write!(
+                    f,
+                    "pattern has {was} positions when expecting 9 or a trailing '*'"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatternError {}
+
+impl CusipPattern {
+    /// Returns true if the CUSIP matches the pattern.
+    pub fn matches(&self, cusip: &CUSIP) -> bool {
+        self.elements[..self.len]
+            .iter()
+            .zip(cusip.as_bytes())
+            .all(|(element, &b)| element.matches(b))
+    }
+}
+
+impl FromStr for CusipPattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pattern = CusipPattern {
+            elements: [Element::Any; 9],
+            len: 0,
+            open_ended: false,
+        };
+
+        for (position, c) in s.chars().enumerate() {
+            if pattern.open_ended {
+                return Err(PatternError::MisplacedStar {
+                    position: position - 1,
+                });
+            }
+            let element = match c {
+                '*' => {
+                    pattern.open_ended = true;
+                    continue;
+                }
+                '?' => Element::Any,
+                '#' => Element::Digit,
+                '0'..='9' | 'A'..='Z' => Element::Literal(c as u8),
+                _ => return Err(PatternError::InvalidChar { position, was: c }),
+            };
+            if pattern.len == 9 {
+                return Err(PatternError::TooLong { position });
+            }
+            pattern.elements[pattern.len] = element;
+            pattern.len += 1;
+        }
+
+        if pattern.len < 9 && !pattern.open_ended {
+            return Err(PatternError::TooShort { was: pattern.len });
+        }
+        Ok(pattern)
+    }
+}
+
+impl fmt::Display for CusipPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for element in &self.elements[..self.len] {
+            write!(f, "{}", element.as_char())?;
+        }
+        if self.open_ended {
+            write!(f, "*")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cusip(s: &str) -> CUSIP {
+        CUSIP::parse(s).unwrap()
+    }
+
+    #[test]
+    fn matching() {
+        let numeric_issue: CusipPattern = "??????##?".parse().unwrap();
+        assert!(numeric_issue.matches(&cusip("037833100")));
+        assert!(!numeric_issue.matches(&cusip("S08000AA9")));
+
+        let everything: CusipPattern = "*".parse().unwrap();
+        assert!(everything.matches(&cusip("S08000AA9")));
+
+        let exact: CusipPattern = "S08000AA9".parse().unwrap();
+        assert!(exact.matches(&cusip("S08000AA9")));
+        assert!(!exact.matches(&cusip("037833100")));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in ["*", "037833*", "??????##?", "S08000AA9", "S?8000AA9*"] {
+            assert_eq!(text.parse::<CusipPattern>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_character() {
+        assert_eq!(
+            "03783a*".parse::<CusipPattern>(),
+            Err(PatternError::InvalidChar {
+                position: 5,
+                was: 'a'
+            })
+        );
+        assert_eq!(
+            "03*833".parse::<CusipPattern>(),
+            Err(PatternError::MisplacedStar { position: 2 })
+        );
+        assert_eq!(
+            "0378331000".parse::<CusipPattern>(),
+            Err(PatternError::TooLong { position: 9 })
+        );
+        assert_eq!(
+            "037833".parse::<CusipPattern>(),
+            Err(PatternError::TooShort { was: 6 })
+        );
+    }
+}
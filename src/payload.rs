@@ -0,0 +1,332 @@
+#![warn(missing_docs)]
+//! # cusip::payload
+//!
+//! The _Payload_ of a CUSIP on its own: the 6-character _Issuer Number_ and 2-character _Issue
+//! Number_, without the _Check Digit_ (sometimes called a "CUSIP-8").
+//!
+//! Some files carry CUSIPs without their _Check Digit_. A `Payload` holds such a value once its
+//! format has been checked, so it can be carried around, compared and sorted, and then completed
+//! into a `CUSIP` with the _Check Digit_ computed:
+//!
+//! ```
+//! use cusip::payload::Payload;
+//!
+//! let payload = Payload::parse("03783310").unwrap();
+//! assert_eq!(payload.issuer_num(), "037833");
+//! assert_eq!(payload.check_digit(), '0');
+//! assert_eq!(payload.to_cusip().to_string(), "037833100");
+//!
+//! assert!(Payload::parse("037833100").is_err()); // Too long
+//! assert!(Payload::parse("0378331!").is_err());
+//! ```
+//!
+//! Its two fields have types of their own, `IssuerNum` and `IssueNum`, so code that carries them
+//! around separately need not validate them again, and cannot mix them up:
+//!
+//! ```
+//! use cusip::payload::{IssueNum, IssuerNum, Payload};
+//! use cusip::CUSIP;
+//!
+//! let apple = CUSIP::parse("037833100").unwrap();
+//! let issuer: IssuerNum = apple.issuer();
+//! let notes: IssueNum = "AK".parse().unwrap();
+//! let cusip = Payload::from_parts(issuer, notes).to_cusip();
+//! assert_eq!(cusip.to_string(), "037833AK6");
+//! assert_eq!(cusip.issuer(), apple.issuer());
+//! ```
+
+use core::fmt;
+use core::str::{from_utf8_unchecked, FromStr};
+
+use crate::{compute_check_digit, validate_issue_num_format, validate_issuer_num_format};
+use crate::{CUSIPError, IssueKind, CUSIP};
+
+/// An _Issuer Number_ in valid format: 6 uppercase ASCII alphanumeric characters.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct IssuerNum([u8; 6]);
+
+impl fmt::Display for IssuerNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for IssuerNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IssuerNum({})", self.as_str())
+    }
+}
+
+impl FromStr for IssuerNum {
+    type Err = CUSIPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl IssuerNum {
+    /// Constructs an `IssuerNum` from 6 bytes, checking their format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<IssuerNum, CUSIPError> {
+        if bytes.len() != 6 {
+            return Err(CUSIPError::InvalidIssuerNumLength { was: bytes.len() });
+        }
+        validate_issuer_num_format(bytes)?;
+
+        let mut bb = [0_u8; 6];
+        bb.copy_from_slice(bytes);
+        Ok(IssuerNum(bb))
+    }
+
+    /// Parses a string to a valid `IssuerNum`, requiring the exact canonical form.
+    pub fn parse(value: &str) -> Result<IssuerNum, CUSIPError> {
+        Self::from_bytes(value.as_bytes())
+    }
+
+    /// Returns the _Issuer Number_ as a string.
+    pub fn as_str(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.0) } // This is safe because we know it is ASCII
+    }
+}
+
+/// An _Issue Number_ in valid format: 2 uppercase ASCII alphanumeric characters.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct IssueNum([u8; 2]);
+
+impl fmt::Display for IssueNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for IssueNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IssueNum({})", self.as_str())
+    }
+}
+
+impl FromStr for IssueNum {
+    type Err = CUSIPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl IssueNum {
+    /// Constructs an `IssueNum` from 2 bytes, checking their format.
+    pub fn from_bytes(bytes: &[u8]) -> Result<IssueNum, CUSIPError> {
+        if bytes.len() != 2 {
+            return Err(CUSIPError::InvalidIssueNumLength { was: bytes.len() });
+        }
+        validate_issue_num_format(bytes)?;
+
+        Ok(IssueNum([bytes[0], bytes[1]]))
+    }
+
+    /// Parses a string to a valid `IssueNum`, requiring the exact canonical form.
+    pub fn parse(value: &str) -> Result<IssueNum, CUSIPError> {
+        Self::from_bytes(value.as_bytes())
+    }
+
+    /// Returns the _Issue Number_ as a string.
+    pub fn as_str(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.0) } // This is safe because we know it is ASCII
+    }
+
+    /// Returns the kind of _Issue Number_ (see `CUSIP::issue_kind()`).
+    pub fn kind(&self) -> IssueKind {
+        if self.0.iter().all(u8::is_ascii_digit) {
+            IssueKind::Equity
+        } else {
+            IssueKind::FixedIncome
+        }
+    }
+}
+
+/// A _Payload_ in valid format: an _Issuer Number_ and _Issue Number_ of uppercase ASCII
+/// alphanumeric characters.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub struct Payload([u8; 8]);
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Payload({})", self.as_str())
+    }
+}
+
+impl FromStr for Payload {
+    type Err = CUSIPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<CUSIP> for Payload {
+    fn from(cusip: CUSIP) -> Self {
+        let mut bb = [0_u8; 8];
+        bb.copy_from_slice(&cusip.0[0..8]);
+        Payload(bb)
+    }
+}
+
+impl From<Payload> for CUSIP {
+    fn from(payload: Payload) -> Self {
+        payload.to_cusip()
+    }
+}
+
+impl Payload {
+    /// Constructs a `Payload` from its _Issuer Number_ and _Issue Number_.
+    pub fn from_parts(issuer: IssuerNum, issue: IssueNum) -> Payload {
+        let mut bb = [0_u8; 8];
+        bb[0..6].copy_from_slice(&issuer.0);
+        bb[6..8].copy_from_slice(&issue.0);
+        Payload(bb)
+    }
+
+    /// Constructs a `Payload` from 8 bytes, checking the format of the _Issuer Number_ and _Issue
+    /// Number_.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Payload, CUSIPError> {
+        if bytes.len() != 8 {
+            return Err(CUSIPError::InvalidPayloadLength { was: bytes.len() });
+        }
+        validate_issuer_num_format(&bytes[0..6])?;
+        validate_issue_num_format(&bytes[6..8])?;
+
+        let mut bb = [0_u8; 8];
+        bb.copy_from_slice(bytes);
+        Ok(Payload(bb))
+    }
+
+    /// Parses a string to a valid `Payload`, requiring the string to already be only uppercase
+    /// alphanumerics with no leading or trailing whitespace.
+    pub fn parse(value: &str) -> Result<Payload, CUSIPError> {
+        Self::from_bytes(value.as_bytes())
+    }
+
+    /// Parses a string to a valid `Payload`, allowing leading or trailing whitespace and lowercase
+    /// letters, as `CUSIP::parse_loose()` does.
+    pub fn parse_loose(value: &str) -> Result<Payload, CUSIPError> {
+        let temp = value.trim();
+        if temp.len() != 8 {
+            return Self::parse(temp);
+        }
+        let mut bb = [0_u8; 8];
+        bb.copy_from_slice(temp.as_bytes());
+        bb.make_ascii_uppercase();
+        Self::from_bytes(&bb)
+    }
+
+    /// Returns the _Payload_ as a string.
+    pub fn as_str(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.0) } // This is safe because we know it is ASCII
+    }
+
+    /// Returns the _Issuer Number_.
+    pub fn issuer_num(&self) -> &str {
+        &self.as_str()[0..6]
+    }
+
+    /// Returns the _Issue Number_.
+    pub fn issue_num(&self) -> &str {
+        &self.as_str()[6..8]
+    }
+
+    /// Returns the _Issuer Number_ as an `IssuerNum`.
+    pub fn issuer(&self) -> IssuerNum {
+        IssuerNum([
+            self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5],
+        ])
+    }
+
+    /// Returns the _Issue Number_ as an `IssueNum`.
+    pub fn issue(&self) -> IssueNum {
+        IssueNum([self.0[6], self.0[7]])
+    }
+
+    /// Computes the _Check Digit_ that completes the _Payload_.
+    pub fn check_digit(&self) -> char {
+        char::from(compute_check_digit(&self.0))
+    }
+
+    /// Returns the `CUSIP` made of the _Payload_ and its computed _Check Digit_.
+    pub fn to_cusip(&self) -> CUSIP {
+        let mut bb = [0_u8; 9];
+        bb[0..8].copy_from_slice(&self.0);
+        bb[8] = compute_check_digit(&self.0);
+        CUSIP(bb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for value in ["037833100", "09739D100", "S08000AA9"] {
+            let cusip = CUSIP::parse(value).unwrap();
+            let payload = Payload::from(cusip);
+            assert_eq!(payload.as_str(), cusip.payload());
+            assert_eq!(payload.check_digit(), cusip.check_digit());
+            assert_eq!(CUSIP::from(payload), cusip);
+        }
+    }
+
+    #[test]
+    fn parts() {
+        let cusip = CUSIP::parse("09739D100").unwrap();
+        assert_eq!(cusip.issuer().as_str(), "09739D");
+        assert_eq!(cusip.issue().as_str(), "10");
+        assert_eq!(
+            Payload::from_parts(cusip.issuer(), cusip.issue()),
+            Payload::from(cusip)
+        );
+        assert!(IssuerNum::parse("037833").unwrap() < cusip.issuer());
+
+        assert_eq!(
+            IssuerNum::parse("03783"),
+            Err(CUSIPError::InvalidIssuerNumLength { was: 5 })
+        );
+        assert_eq!(
+            IssuerNum::parse("03783a"),
+            Err(CUSIPError::InvalidIssuerNum { was: *b"03783a" })
+        );
+        assert_eq!(
+            IssueNum::parse("100"),
+            Err(CUSIPError::InvalidIssueNumLength { was: 3 })
+        );
+        assert_eq!(
+            IssueNum::parse("1 "),
+            Err(CUSIPError::InvalidIssueNum { was: *b"1 " })
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Payload::parse("0378331"),
+            Err(CUSIPError::InvalidPayloadLength { was: 7 })
+        );
+        assert_eq!(
+            Payload::parse("03783a10"),
+            Err(CUSIPError::InvalidIssuerNum { was: *b"03783a" })
+        );
+        assert_eq!(
+            Payload::parse("037833-0"),
+            Err(CUSIPError::InvalidIssueNum { was: *b"-0" })
+        );
+        assert_eq!(
+            Payload::parse_loose(" 09739d10\n"),
+            Payload::parse("09739D10")
+        );
+    }
+}
@@ -0,0 +1,200 @@
+#![warn(missing_docs)]
+//! # cusip::polars
+//!
+//! A `cusip` namespace for [Polars](https://pola.rs/) string `Series` and `Expr` values (requires
+//! the `polars` feature).
+//!
+//! Bring `CusipSeriesExt` and/or `CusipExprExt` into scope and call `.cusip()` to reach the
+//! operations, each of which works on a whole column at a time:
+//!
+//! * `validate()`: A boolean column, true where the value is a valid CUSIP (as for `validate()`).
+//! * `issuer()`: A string column with the _Issuer Number_ of each valid CUSIP.
+//! * `country()`: A string column with the CINS _Country Code_ of each valid CINS identifier.
+//!
+//! Nulls are propagated, and `issuer()` and `country()` also produce null for values that are not
+//! valid CUSIPs (or, for `country()`, not CINS identifiers).
+//!
+//! ```
+//! use cusip::polars::CusipExprExt;
+//! use polars::prelude::*;
+//!
+//! let df = df!("cusip" => ["037833100", "S08000AA9", "037833101"]).unwrap();
+//! let out = df
+//!     .lazy()
+//!     .select([
+//!         col("cusip").cusip().validate().alias("valid"),
+//!         col("cusip").cusip().country().alias("country"),
+//!     ])
+//!     .collect()
+//!     .unwrap();
+//!
+//! let valid: Vec<Option<bool>> = out.column("valid").unwrap().bool().unwrap().iter().collect();
+//! assert_eq!(valid, [Some(true), Some(true), Some(false)]);
+//! ```
+
+use ::polars::prelude::*;
+
+use crate::{validate, CUSIP};
+
+/// Computes `validate()` for each value.
+fn validate_kernel(ca: &StringChunked) -> BooleanChunked {
+    ca.iter()
+        .map(|s| s.map(validate))
+        .collect::<BooleanChunked>()
+        .with_name(ca.name().clone())
+}
+
+/// Computes the _Issuer Number_ of each valid CUSIP.
+fn issuer_kernel(ca: &StringChunked) -> StringChunked {
+    ca.iter()
+        .map(|s| s.and_then(|s| CUSIP::parse(s).ok()))
+        .map(|c| c.map(|c| c.issuer_num().to_string()))
+        .collect::<StringChunked>()
+        .with_name(ca.name().clone())
+}
+
+/// Computes the CINS _Country Code_ of each valid CINS identifier.
+fn country_kernel(ca: &StringChunked) -> StringChunked {
+    ca.iter()
+        .map(|s| s.and_then(|s| CUSIP::parse(s).ok()))
+        .map(|c| c.and_then(|c| c.as_cins().map(|cins| cins.country_code().to_string())))
+        .collect::<StringChunked>()
+        .with_name(ca.name().clone())
+}
+
+/// Adds the `cusip` namespace to `Series`.
+pub trait CusipSeriesExt {
+    /// Returns the `cusip` namespace for this `Series`.
+    fn cusip(&self) -> CusipSeriesNameSpace<'_>;
+}
+
+impl CusipSeriesExt for Series {
+    fn cusip(&self) -> CusipSeriesNameSpace<'_> {
+        CusipSeriesNameSpace(self)
+    }
+}
+
+/// The `cusip` namespace of a `Series`. All operations fail if the `Series` is not a string
+/// `Series`.
+pub struct CusipSeriesNameSpace<'a>(&'a Series);
+
+impl CusipSeriesNameSpace<'_> {
+    /// Returns a boolean `Series`, true where the value is a valid CUSIP.
+    pub fn validate(&self) -> PolarsResult<Series> {
+        Ok(validate_kernel(self.0.str()?).into_series())
+    }
+
+    /// Returns a string `Series` with the _Issuer Number_ of each valid CUSIP.
+    pub fn issuer(&self) -> PolarsResult<Series> {
+        Ok(issuer_kernel(self.0.str()?).into_series())
+    }
+
+    /// Returns a string `Series` with the CINS _Country Code_ of each valid CINS identifier.
+    pub fn country(&self) -> PolarsResult<Series> {
+        Ok(country_kernel(self.0.str()?).into_series())
+    }
+}
+
+/// Adds the `cusip` namespace to `Expr`.
+pub trait CusipExprExt {
+    /// Returns the `cusip` namespace for this `Expr`.
+    fn cusip(self) -> CusipExprNameSpace;
+}
+
+impl CusipExprExt for Expr {
+    fn cusip(self) -> CusipExprNameSpace {
+        CusipExprNameSpace(self)
+    }
+}
+
+/// The `cusip` namespace of an `Expr`. All operations fail when evaluated if the input is not a
+/// string column.
+pub struct CusipExprNameSpace(Expr);
+
+impl CusipExprNameSpace {
+    /// Returns a boolean expression, true where the value is a valid CUSIP.
+    pub fn validate(self) -> Expr {
+        self.0.map_with_fmt_str(
+            |c| Ok(validate_kernel(c.str()?).into_column()),
+            |_, field| Ok(Field::new(field.name().clone(), DataType::Boolean)),
+            "cusip.validate",
+        )
+    }
+
+    /// Returns a string expression with the _Issuer Number_ of each valid CUSIP.
+    pub fn issuer(self) -> Expr {
+        self.0.map_with_fmt_str(
+            |c| Ok(issuer_kernel(c.str()?).into_column()),
+            |_, field| Ok(Field::new(field.name().clone(), DataType::String)),
+            "cusip.issuer",
+        )
+    }
+
+    /// Returns a string expression with the CINS _Country Code_ of each valid CINS identifier.
+    pub fn country(self) -> Expr {
+        self.0.map_with_fmt_str(
+            |c| Ok(country_kernel(c.str()?).into_column()),
+            |_, field| Ok(Field::new(field.name().clone(), DataType::String)),
+            "cusip.country",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series() -> Series {
+        Series::new(
+            "cusip".into(),
+            [
+                Some("037833100"),
+                Some("S08000AA9"),
+                Some("037833101"),
+                None,
+            ],
+        )
+    }
+
+    #[test]
+    fn series_namespace() {
+        let s = series();
+
+        let valid: Vec<Option<bool>> = s
+            .cusip()
+            .validate()
+            .unwrap()
+            .bool()
+            .unwrap()
+            .iter()
+            .collect();
+        assert_eq!(valid, [Some(true), Some(true), Some(false), None]);
+
+        let issuer = s.cusip().issuer().unwrap();
+        let issuer: Vec<Option<&str>> = issuer.str().unwrap().iter().collect();
+        assert_eq!(issuer, [Some("037833"), Some("S08000"), None, None]);
+
+        let country = s.cusip().country().unwrap();
+        let country: Vec<Option<&str>> = country.str().unwrap().iter().collect();
+        assert_eq!(country, [None, Some("S"), None, None]);
+    }
+
+    #[test]
+    fn series_namespace_rejects_non_strings() {
+        let s = Series::new("cusip".into(), [1i32, 2, 3]);
+        assert!(s.cusip().validate().is_err());
+    }
+
+    #[test]
+    fn expr_namespace() {
+        let df = DataFrame::new_infer_height(vec![series().into_column()]).unwrap();
+        let out = df
+            .lazy()
+            .select([col("cusip").cusip().issuer()])
+            .collect()
+            .unwrap();
+        let issuer: Vec<Option<&str>> =
+            out.column("cusip").unwrap().str().unwrap().iter().collect();
+        assert_eq!(issuer, [Some("037833"), Some("S08000"), None, None]);
+    }
+}
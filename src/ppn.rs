@@ -0,0 +1,224 @@
+#![warn(missing_docs)]
+//! # cusip::ppn
+//!
+//! Private Placement Numbers (PPNs), which may use the three extended characters The Standard
+//! defines for the "PPN System": '`*`' (value 36), '`@`' (value 37) and '`#`' (value 38).
+//!
+//! A `CUSIP` never holds these characters, so that every `CUSIP` can be packed, embedded in an
+//! ISIN and so on. A `PPN` is the opt-in alternative for systems that process private placements:
+//! it accepts everything `CUSIP::parse()` accepts, plus the extended characters anywhere in the
+//! _Payload_, with their values counted in the _Check Digit_. A PPN without extended characters
+//! converts to a `CUSIP`.
+//!
+//! ```
+//! use cusip::ppn::PPN;
+//! use cusip::CUSIP;
+//!
+//! let ppn = PPN::parse("12345*@#7").unwrap();
+//! assert!(ppn.has_extended_chars());
+//! assert_eq!(ppn.to_cusip(), None);
+//! assert!(PPN::parse("12345*@#8").is_err());
+//!
+//! let apple = PPN::parse("037833100").unwrap();
+//! assert_eq!(apple.to_cusip(), Some(CUSIP::parse("037833100").unwrap()));
+//! ```
+//!
+//! Errors are reported as `CUSIPError`s. Their messages describe the characters of a `CUSIP`, so
+//! an _Issuer Number_ or _Issue Number_ rejected here holds a character that is neither
+//! alphanumeric nor one of the extended characters.
+
+use core::fmt;
+use core::str::{from_utf8_unchecked, FromStr};
+
+use crate::{CUSIPError, CUSIP};
+
+/// The value of a PPN character: 0 to 35 for uppercase ASCII alphanumeric characters as in a
+/// CUSIP, and 36 to 38 for the extended characters, or `None` for any other character.
+fn char_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'Z' => Some(b - b'A' + 10),
+        b'*' => Some(36),
+        b'@' => Some(37),
+        b'#' => Some(38),
+        _ => None,
+    }
+}
+
+/// Computes the _Check Digit_ for a PPN _Payload_, or returns `None` if any character is not
+/// valid in a PPN. No attempt is made to ensure the input is 8 characters long. For a _Payload_
+/// without extended characters, the result is the same as `cusip::compute_check_digit()`.
+pub fn compute_check_digit(payload: &[u8]) -> Option<u8> {
+    let mut sum: u32 = 0;
+    for (i, &b) in payload.iter().enumerate() {
+        let v = char_value(b)?;
+        // Counting from one, the even positions are doubled
+        let v = if i % 2 == 1 { v * 2 } else { v };
+        sum += u32::from(v / 10 + v % 10);
+    }
+    Some(b'0' + ((10 - sum % 10) % 10) as u8)
+}
+
+/// Returns true if the string is a valid PPN.
+pub fn validate(value: &str) -> bool {
+    PPN::parse(value).is_ok()
+}
+
+/// A valid Private Placement Number, which may use the extended characters `*`, `@` and `#`.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct PPN([u8; 9]);
+
+impl fmt::Display for PPN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for PPN {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PPN({})", self.as_str())
+    }
+}
+
+impl FromStr for PPN {
+    type Err = CUSIPError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl From<CUSIP> for PPN {
+    fn from(cusip: CUSIP) -> Self {
+        PPN(cusip.0)
+    }
+}
+
+impl PPN {
+    /// Constructs a `PPN` from 9 bytes, checking the format of each field and the _Check Digit_.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PPN, CUSIPError> {
+        if bytes.len() != 9 {
+            return Err(CUSIPError::InvalidCUSIPLength { was: bytes.len() });
+        }
+        if bytes[0..6].iter().any(|&b| char_value(b).is_none()) {
+            let mut was = [0_u8; 6];
+            was.copy_from_slice(&bytes[0..6]);
+            return Err(CUSIPError::InvalidIssuerNum { was });
+        }
+        if bytes[6..8].iter().any(|&b| char_value(b).is_none()) {
+            let mut was = [0_u8; 2];
+            was.copy_from_slice(&bytes[6..8]);
+            return Err(CUSIPError::InvalidIssueNum { was });
+        }
+        let was = bytes[8];
+        if !was.is_ascii_digit() {
+            return Err(CUSIPError::InvalidCheckDigit { was });
+        }
+        // Every character of the Payload has a value, so it has a Check Digit
+        let expected = compute_check_digit(&bytes[0..8]).unwrap();
+        if was != expected {
+            return Err(CUSIPError::IncorrectCheckDigit { was, expected });
+        }
+
+        let mut bb = [0_u8; 9];
+        bb.copy_from_slice(bytes);
+        Ok(PPN(bb))
+    }
+
+    /// Parses a string to a valid PPN, requiring the exact canonical form as `CUSIP::parse()`
+    /// does.
+    pub fn parse(value: &str) -> Result<PPN, CUSIPError> {
+        Self::from_bytes(value.as_bytes())
+    }
+
+    /// Parses a string to a valid PPN, allowing leading or trailing whitespace and lowercase
+    /// letters, as `CUSIP::parse_loose()` does.
+    pub fn parse_loose(value: &str) -> Result<PPN, CUSIPError> {
+        let temp = value.trim();
+        if temp.len() != 9 {
+            return Self::parse(temp);
+        }
+        let mut bb = [0_u8; 9];
+        bb.copy_from_slice(temp.as_bytes());
+        bb.make_ascii_uppercase();
+        Self::from_bytes(&bb)
+    }
+
+    /// Returns the PPN as a string.
+    pub fn as_str(&self) -> &str {
+        unsafe { from_utf8_unchecked(&self.0) } // This is safe because we know it is ASCII
+    }
+
+    /// Returns the _Issuer Number_.
+    pub fn issuer_num(&self) -> &str {
+        &self.as_str()[0..6]
+    }
+
+    /// Returns the _Issue Number_.
+    pub fn issue_num(&self) -> &str {
+        &self.as_str()[6..8]
+    }
+
+    /// Returns the _Payload_ &mdash; everything except the _Check Digit_.
+    pub fn payload(&self) -> &str {
+        &self.as_str()[0..8]
+    }
+
+    /// Returns the _Check Digit_.
+    pub fn check_digit(&self) -> char {
+        char::from(self.0[8])
+    }
+
+    /// Returns true if the PPN uses any of the extended characters `*`, `@` and `#`.
+    pub fn has_extended_chars(&self) -> bool {
+        self.0.iter().any(|b| matches!(b, b'*' | b'@' | b'#'))
+    }
+
+    /// Returns the PPN as a `CUSIP`, or `None` if it uses any of the extended characters.
+    pub fn to_cusip(&self) -> Option<CUSIP> {
+        (!self.has_extended_chars()).then_some(CUSIP(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn extended_characters() {
+        let ppn = PPN::parse("12345*@#7").unwrap();
+        assert_eq!(ppn.issuer_num(), "12345*");
+        assert_eq!(ppn.issue_num(), "@#");
+        assert_eq!(ppn.check_digit(), '7');
+        assert!(!validate("12345*@#"));
+        assert_eq!(PPN::parse_loose(" 12345*@#7\n"), Ok(ppn));
+        assert_eq!(
+            PPN::parse("12345*@#0"),
+            Err(CUSIPError::IncorrectCheckDigit {
+                was: b'0',
+                expected: b'7'
+            })
+        );
+        assert_eq!(
+            PPN::parse("12345$@#7"),
+            Err(CUSIPError::InvalidIssuerNum { was: *b"12345$" })
+        );
+        assert_eq!(
+            PPN::parse("123456*a7"),
+            Err(CUSIPError::InvalidIssueNum { was: *b"*a" })
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn agrees_with_cusip(payload in "[0-9A-Z]{8}") {
+            let cusip = crate::build_from_payload(&payload).unwrap();
+            let ppn = PPN::parse(&cusip.to_string()).unwrap();
+            prop_assert!(!ppn.has_extended_chars());
+            prop_assert_eq!(ppn.to_cusip(), Some(cusip));
+            prop_assert_eq!(PPN::from(cusip), ppn);
+        }
+    }
+}
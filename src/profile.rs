@@ -0,0 +1,352 @@
+#![warn(missing_docs)]
+//! # cusip::profile
+//!
+//! Validation policies that go beyond the format rules every CUSIP must follow.
+//!
+//! A `ValidationProfile` says whether input may be given loosely (with surrounding whitespace or
+//! lowercase letters), which characters the _Payload_ may use, and how to treat kinds of CUSIP
+//! that are valid but that some systems don't want to accept: CINS identifiers, CINS identifiers
+//! using the extended country codes 'I', 'O' and 'Z', and identifiers reserved for private use.
+//! Each of those policies has a `Severity`, so it can be ignored, reported as a warning, or cause
+//! the value to be rejected.
+//!
+//! Private Placement Numbers using the extended characters `*`, `@` and `#` are not CUSIPs, so
+//! `parse()` always rejects them. Systems that process private placements can call `parse_ppn()`
+//! instead, which accepts them when the profile's `ppn` setting is on (see the `ppn` module).
+//!
+//! With the `serde` feature, profiles can be deserialized, so data-governance teams can keep
+//! their validation policy in a versioned TOML or JSON file. Missing fields take their default
+//! values, which accept everything `CUSIP::parse()` does.
+//!
+//! ```
+//! use cusip::profile::{Lint, ProfileError, Severity, ValidationProfile};
+//!
+//! let profile = ValidationProfile {
+//!     loose: true,
+//!     cins: Severity::Warn,
+//!     private_use: Severity::Deny,
+//!     ..Default::default()
+//! };
+//!
+//! let validated = profile.parse(" s08000aa9").unwrap();
+//! assert_eq!(validated.cusip.to_string(), "S08000AA9");
+//! assert_eq!(validated.warnings, vec![Lint::Cins]);
+//!
+//! assert_eq!(
+//!     profile.parse("03783T996").unwrap_err(),
+//!     ProfileError::Denied(Lint::PrivateUse)
+//! );
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use ::serde::{Deserialize, Serialize};
+
+use crate::ppn::PPN;
+use crate::{CUSIPError, CUSIP};
+
+/// How to treat a CUSIP that a policy flags.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// Accept the CUSIP without comment.
+    #[default]
+    Allow,
+    /// Accept the CUSIP, but report the policy in `Validated::warnings`.
+    Warn,
+    /// Reject the CUSIP.
+    Deny,
+}
+
+/// The policies a `ValidationProfile` can apply to a valid CUSIP.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Lint {
+    /// The CUSIP is a CINS identifier.
+    Cins,
+    /// The CUSIP is a CINS identifier using one of the extended country codes 'I', 'O' or 'Z'.
+    CinsExtended,
+    /// The CUSIP is reserved for private use.
+    PrivateUse,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::Cins => write!(f, "CINS identifier"),
+            Lint::CinsExtended => write!(f, "CINS identifier with an extended country code"),
+            Lint::PrivateUse => write!(f, "identifier reserved for private use"),
+        }
+    }
+}
+
+/// A validation policy. The default profile accepts exactly what `CUSIP::parse()` accepts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct ValidationProfile {
+    /// Accept leading or trailing whitespace and lowercase letters, as `CUSIP::parse_loose()`
+    /// does.
+    pub loose: bool,
+    /// If set, the only characters the _Payload_ may use. Characters are compared after any
+    /// loose normalization.
+    pub allowed_chars: Option<String>,
+    /// How to treat CINS identifiers.
+    pub cins: Severity,
+    /// How to treat CINS identifiers using one of the extended country codes 'I', 'O' or 'Z'.
+    pub cins_extended: Severity,
+    /// How to treat identifiers reserved for private use.
+    pub private_use: Severity,
+    /// Accept Private Placement Numbers using the extended characters `*`, `@` and `#` in
+    /// `parse_ppn()`.
+    pub ppn: bool,
+}
+
+/// A CUSIP accepted by a `ValidationProfile`, along with any warnings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Validated {
+    /// The CUSIP.
+    pub cusip: CUSIP,
+    /// The policies with `Severity::Warn` that the CUSIP triggered.
+    pub warnings: Vec<Lint>,
+}
+
+/// A Private Placement Number accepted by `ValidationProfile::parse_ppn()`, along with any
+/// warnings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatedPpn {
+    /// The PPN.
+    pub ppn: PPN,
+    /// The policies with `Severity::Warn` that the PPN triggered.
+    pub warnings: Vec<Lint>,
+}
+
+/// All the ways a value could be rejected by a `ValidationProfile`.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    /// The value is not a valid CUSIP.
+    Invalid(CUSIPError),
+    /// The _Payload_ uses a character the profile does not allow.
+    DisallowedChar {
+        /// The character we found
+        was: char,
+    },
+    /// The CUSIP triggered a policy with `Severity::Deny`.
+    Denied(Lint),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Invalid(err) => write!(f, "{err}"),
+            ProfileError::DisallowedChar { was } => {
+                write!(
+                    f,
+                    "character {was:?} is not allowed by the validation profile"
+                )
+            }
+            ProfileError::Denied(lint) => {
+                write!(f, "{lint} is not allowed by the validation profile")
+            }
+        }
+    }
+}
+
+impl Error for ProfileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProfileError::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<CUSIPError> for ProfileError {
+    fn from(err: CUSIPError) -> Self {
+        ProfileError::Invalid(err)
+    }
+}
+
+impl ValidationProfile {
+    /// Parse a string to a valid CUSIP and apply the profile's policies to it.
+    pub fn parse(&self, value: &str) -> Result<Validated, ProfileError> {
+        let cusip = if self.loose {
+            CUSIP::parse_loose(value)?
+        } else {
+            CUSIP::parse(value)?
+        };
+        self.check(&cusip)
+            .map(|warnings| Validated { cusip, warnings })
+    }
+
+    /// Parse a string to a valid PPN and apply the profile's policies to it. Unless `ppn` is set,
+    /// the value must be a CUSIP, as for `parse()`. A PPN using the extended characters is only
+    /// checked against `allowed_chars`, as the other policies concern CUSIPs.
+    pub fn parse_ppn(&self, value: &str) -> Result<ValidatedPpn, ProfileError> {
+        if !self.ppn {
+            return self.parse(value).map(|validated| ValidatedPpn {
+                ppn: PPN::from(validated.cusip),
+                warnings: validated.warnings,
+            });
+        }
+
+        let ppn = if self.loose {
+            PPN::parse_loose(value)?
+        } else {
+            PPN::parse(value)?
+        };
+        let warnings = match ppn.to_cusip() {
+            Some(cusip) => self.check(&cusip)?,
+            None => {
+                self.check_chars(ppn.payload())?;
+                Vec::new()
+            }
+        };
+        Ok(ValidatedPpn { ppn, warnings })
+    }
+
+    /// Apply the profile's policies to an already valid CUSIP, returning any warnings.
+    pub fn check(&self, cusip: &CUSIP) -> Result<Vec<Lint>, ProfileError> {
+        self.check_chars(cusip.payload())?;
+
+        let mut warnings = Vec::new();
+        let policies = [
+            (Lint::Cins, self.cins, cusip.is_cins()),
+            (
+                Lint::CinsExtended,
+                self.cins_extended,
+                cusip.as_cins().is_some_and(|cins| cins.is_extended()),
+            ),
+            (Lint::PrivateUse, self.private_use, cusip.is_private_use()),
+        ];
+        for (lint, severity, triggered) in policies {
+            match severity {
+                _ if !triggered => {}
+                Severity::Allow => {}
+                Severity::Warn => warnings.push(lint),
+                Severity::Deny => return Err(ProfileError::Denied(lint)),
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    fn check_chars(&self, payload: &str) -> Result<(), ProfileError> {
+        match &self.allowed_chars {
+            Some(allowed) => match payload.chars().find(|c| !allowed.contains(*c)) {
+                Some(was) => Err(ProfileError::DisallowedChar { was }),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_parse() {
+        let profile = ValidationProfile::default();
+        for value in ["037833100", "S08000AA9", "I08000AA0", "03783T996"] {
+            let validated = profile.parse(value).unwrap();
+            assert_eq!(validated.cusip, CUSIP::parse(value).unwrap());
+            assert!(validated.warnings.is_empty());
+        }
+        assert_eq!(
+            profile.parse(" 037833100"),
+            Err(ProfileError::Invalid(CUSIPError::InvalidCUSIPLength {
+                was: 10
+            }))
+        );
+    }
+
+    #[test]
+    fn allowed_chars() {
+        let profile = ValidationProfile {
+            allowed_chars: Some("0123456789".to_string()),
+            ..Default::default()
+        };
+        assert!(profile.parse("037833100").is_ok());
+        assert_eq!(
+            profile.parse("S08000AA9"),
+            Err(ProfileError::DisallowedChar { was: 'S' })
+        );
+    }
+
+    #[test]
+    fn cins_extended_severity() {
+        let profile = ValidationProfile {
+            cins: Severity::Warn,
+            cins_extended: Severity::Deny,
+            ..Default::default()
+        };
+        assert_eq!(profile.parse("S08000AA9").unwrap().warnings, [Lint::Cins]);
+        assert_eq!(
+            profile.parse("I08000AA0"),
+            Err(ProfileError::Denied(Lint::CinsExtended))
+        );
+    }
+
+    #[test]
+    fn ppn_setting() {
+        let value = "12345*@#7";
+        let profile = ValidationProfile {
+            private_use: Severity::Warn,
+            ..Default::default()
+        };
+        assert!(profile.parse_ppn(value).is_err());
+        assert_eq!(
+            profile.parse_ppn("03783T996").unwrap().warnings,
+            [Lint::PrivateUse]
+        );
+
+        let profile = ValidationProfile {
+            ppn: true,
+            loose: true,
+            ..profile
+        };
+        assert!(profile.parse(value).is_err());
+        let validated = profile.parse_ppn(" 12345*@#7").unwrap();
+        assert_eq!(validated.ppn, PPN::parse(value).unwrap());
+        assert!(validated.warnings.is_empty());
+        assert_eq!(
+            profile.parse_ppn("03783T996").unwrap().warnings,
+            [Lint::PrivateUse]
+        );
+
+        let digits_only = ValidationProfile {
+            allowed_chars: Some("0123456789".to_string()),
+            ..profile
+        };
+        assert_eq!(
+            digits_only.parse_ppn(value),
+            Err(ProfileError::DisallowedChar { was: '*' })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn load_from_toml_and_json() {
+        let from_toml: ValidationProfile =
+            toml::from_str("loose = true\nprivate_use = \"deny\"\n").unwrap();
+        let from_json: ValidationProfile =
+            serde_json::from_str(r#"{"loose": true, "private_use": "deny"}"#).unwrap();
+        let expected = ValidationProfile {
+            loose: true,
+            private_use: Severity::Deny,
+            ..Default::default()
+        };
+        assert_eq!(from_toml, expected);
+        assert_eq!(from_json, expected);
+
+        assert!(serde_json::from_str::<ValidationProfile>(r#"{"strict": true}"#).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+#![warn(missing_docs)]
+//! # cusip::quality
+//!
+//! A batch-level data quality report, for attaching to each load of identifiers.
+//!
+//! `DataQualityReport` validates a batch of values under a `profile::ValidationProfile` and
+//! summarizes it in one structure: the share of values that are valid, the failures by kind of
+//! error, how often each profile policy warned or rejected, how many values repeat another value
+//! in the batch, and the mix of CINS and domestic identifiers. It renders as a line of JSON.
+//!
+//! ```
+//! use cusip::profile::{Lint, Severity, ValidationProfile};
+//! use cusip::quality::DataQualityReport;
+//!
+//! let profile = ValidationProfile {
+//!     cins: Severity::Warn,
+//!     ..Default::default()
+//! };
+//! let report = DataQualityReport::from_values(
+//!     ["037833100", "037833100", "S08000AA9", "037833101"],
+//!     &profile,
+//! );
+//!
+//! assert_eq!(report.percent_valid(), 75.0);
+//! assert_eq!(report.validation().counts_by_kind()["IncorrectCheckDigit"], 1);
+//! assert_eq!(report.warnings()[&Lint::Cins], 1);
+//! assert_eq!(report.duplicates(), 1);
+//! assert_eq!((report.domestic(), report.cins()), (2, 1));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::analytics::CusipCounter;
+use crate::profile::{Lint, ProfileError, ValidationProfile};
+use crate::report::ValidationReport;
+use crate::CUSIP;
+
+/// The quality of a batch of values validated under a profile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataQualityReport {
+    validation: ValidationReport,
+    counter: CusipCounter,
+    warnings: BTreeMap<Lint, usize>,
+    rejected: BTreeMap<Lint, usize>,
+    disallowed_chars: usize,
+    cins: usize,
+}
+
+impl DataQualityReport {
+    /// Validates each value under the profile, summarizing the outcomes.
+    pub fn from_values<I, S>(values: I, profile: &ValidationProfile) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut report = Self::default();
+        for value in values {
+            report.record(value.as_ref(), profile);
+        }
+        report
+    }
+
+    /// Validates the next value under the profile and records the outcome.
+    pub fn record(&mut self, value: &str, profile: &ValidationProfile) {
+        let parsed = if profile.loose {
+            CUSIP::parse_loose(value)
+        } else {
+            CUSIP::parse(value)
+        };
+        self.validation
+            .record(value, parsed.as_ref().map(|_| ()).map_err(Clone::clone));
+        let Ok(cusip) = parsed else {
+            return;
+        };
+
+        self.counter.add(&cusip);
+        if cusip.is_cins() {
+            self.cins += 1;
+        }
+        match profile.check(&cusip) {
+            Ok(warnings) => {
+                for lint in warnings {
+                    *self.warnings.entry(lint).or_insert(0) += 1;
+                }
+            }
+            Err(ProfileError::Denied(lint)) => *self.rejected.entry(lint).or_insert(0) += 1,
+            Err(_) => self.disallowed_chars += 1,
+        }
+    }
+
+    /// Returns the outcome of validating each value against the format rules, including the
+    /// failures by kind of error.
+    pub fn validation(&self) -> &ValidationReport {
+        &self.validation
+    }
+
+    /// Returns the percentage of values that were valid CUSIPs, or 0 if there were no values.
+    /// Values rejected only by the profile's policies count as valid here.
+    pub fn percent_valid(&self) -> f64 {
+        match self.validation.total() {
+            0 => 0.0,
+            total => 100.0 * self.validation.valid() as f64 / total as f64,
+        }
+    }
+
+    /// Returns the number of valid CUSIPs that triggered each policy with `Severity::Warn`.
+    pub fn warnings(&self) -> &BTreeMap<Lint, usize> {
+        &self.warnings
+    }
+
+    /// Returns the number of valid CUSIPs rejected by each policy with `Severity::Deny`.
+    pub fn rejected(&self) -> &BTreeMap<Lint, usize> {
+        &self.rejected
+    }
+
+    /// Returns the number of valid CUSIPs rejected for using characters the profile does not
+    /// allow.
+    pub fn disallowed_chars(&self) -> usize {
+        self.disallowed_chars
+    }
+
+    /// Returns the number of distinct valid CUSIPs.
+    pub fn distinct(&self) -> usize {
+        self.counter.distinct()
+    }
+
+    /// Returns the number of valid values that repeat an earlier value in the batch.
+    pub fn duplicates(&self) -> usize {
+        self.validation.valid() - self.counter.distinct()
+    }
+
+    /// Returns the number of valid values that are CINS identifiers.
+    pub fn cins(&self) -> usize {
+        self.cins
+    }
+
+    /// Returns the number of valid values that are domestic (not CINS) identifiers.
+    pub fn domestic(&self) -> usize {
+        self.validation.valid() - self.cins
+    }
+
+    /// Renders the report as a single line of JSON for other programs. The `validation` member
+    /// is the output of `ValidationReport::render_json()`.
+    pub fn render_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            r#"{{"percent_valid":{},"distinct":{},"duplicates":{},"domestic":{},"cins":{}"#,
+            self.percent_valid(),
+            self.distinct(),
+            self.duplicates(),
+            self.domestic(),
+            self.cins()
+        );
+        for (name, counts) in [("warnings", &self.warnings), ("rejected", &self.rejected)] {
+            let counts: Vec<String> = counts
+                .iter()
+                .map(|(lint, n)| format!(r#""{}":{n}"#, lint_key(*lint)))
+                .collect();
+            let _ = write!(out, r#","{name}":{{{}}}"#, counts.join(","));
+        }
+        let _ = write!(
+            out,
+            r#","disallowed_chars":{},"validation":{}}}"#,
+            self.disallowed_chars,
+            self.validation.render_json()
+        );
+        out
+    }
+}
+
+/// The JSON key for the lint, matching its serde name.
+fn lint_key(lint: Lint) -> &'static str {
+    match lint {
+        Lint::Cins => "cins",
+        Lint::CinsExtended => "cins_extended",
+        Lint::PrivateUse => "private_use",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Severity;
+
+    #[test]
+    fn counts_policies() {
+        let profile = ValidationProfile {
+            loose: true,
+            allowed_chars: Some("0123456789ABCDEFGHJKLMNPQRSTUVWXY".to_string()),
+            cins: Severity::Warn,
+            private_use: Severity::Deny,
+            ..Default::default()
+        };
+        let report = DataQualityReport::from_values(
+            ["s08000aa9", "03783T996", "I08000AA0", "", "S08000AA9"],
+            &profile,
+        );
+        assert_eq!(report.percent_valid(), 80.0);
+        assert_eq!(report.warnings()[&Lint::Cins], 2);
+        assert_eq!(report.rejected()[&Lint::PrivateUse], 1);
+        assert_eq!(report.disallowed_chars(), 1);
+        assert_eq!(report.distinct(), 3);
+        assert_eq!(report.duplicates(), 1);
+        assert_eq!((report.domestic(), report.cins()), (1, 3));
+    }
+
+    #[test]
+    fn render_json() {
+        let report = DataQualityReport::from_values(["037833100"], &ValidationProfile::default());
+        assert_eq!(
+            report.render_json(),
+            r#"{"percent_valid":100,"distinct":1,"duplicates":0,"domestic":1,"cins":0,"warnings":{},"rejected":{},"disallowed_chars":0,"validation":{"total":1,"valid":1,"invalid":0,"by_kind":{},"by_field":{},"errors":[]}}"#
+        );
+        assert_eq!(DataQualityReport::default().percent_valid(), 0.0);
+    }
+}
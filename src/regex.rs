@@ -0,0 +1,48 @@
+#![warn(missing_docs)]
+//! # cusip::regex
+//!
+//! A compiled form of `CUSIP_PATTERN` (requires the `regex` feature).
+//!
+//! The pattern only checks the structure of a CUSIP, so `is_match_and_valid()` combines it with
+//! the _Check Digit_ verification performed by `validate()`.
+
+use std::sync::OnceLock;
+
+use ::regex::Regex;
+
+use crate::CUSIP_PATTERN;
+
+/// Returns the lazily compiled `Regex` for `CUSIP_PATTERN`. The `Regex` is compiled on first use
+/// and shared thereafter.
+pub fn regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(CUSIP_PATTERN).expect("CUSIP_PATTERN should always compile"))
+}
+
+/// Returns true if the value matches `CUSIP_PATTERN` *and* has the correct _Check Digit_. This
+/// is equivalent to `validate()`, and exists for callers that want to confirm the pattern they
+/// publish agrees with this crate.
+pub fn is_match_and_valid(value: &str) -> bool {
+    regex().is_match(value) && crate::validate(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_structure_only() {
+        assert!(regex().is_match("09739D100"));
+        assert!(regex().is_match("09739D101")); // Wrong Check Digit, but right structure
+        assert!(!regex().is_match("09739d100"));
+        assert!(!regex().is_match("09739D10"));
+        assert!(!regex().is_match(" 09739D100"));
+        assert!(!regex().is_match("09739D10A"));
+    }
+
+    #[test]
+    fn match_and_valid() {
+        assert!(is_match_and_valid("09739D100"));
+        assert!(!is_match_and_valid("09739D101"));
+    }
+}
@@ -0,0 +1,272 @@
+#![warn(missing_docs)]
+//! # cusip::remap
+//!
+//! Replacing CUSIPs that corporate actions have retired with their successors.
+//!
+//! Reorganizations, name changes and other corporate actions give securities new CUSIPs. A
+//! `CusipRemapper` holds old→new mappings, loaded from CSV or added one at a time, and applies
+//! them to single CUSIPs, iterators, and whole collections such as sets. Chains of mappings (a
+//! CUSIP re-assigned more than once) are followed to the latest CUSIP, and mappings that would
+//! form a cycle are rejected. Applying the remapper to a collection reports which CUSIPs were
+//! replaced and how many were left alone.
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//!
+//! use cusip::remap::CusipRemapper;
+//! use cusip::CUSIP;
+//!
+//! let csv = "old,new\n037833100,594918104\n594918104,09739D100\n";
+//! let remapper = CusipRemapper::from_csv(csv.as_bytes()).unwrap();
+//!
+//! let held: BTreeSet<CUSIP> = ["037833100", "S08000AA9"]
+//!     .iter()
+//!     .map(|s| CUSIP::parse(s).unwrap())
+//!     .collect();
+//! let (held, report) = remapper.apply(held);
+//!
+//! assert!(held.contains(&CUSIP::parse("09739D100").unwrap()));
+//! assert_eq!(report.remapped.len(), 1);
+//! assert_eq!(report.unmapped, 1);
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::csv::split_record;
+use crate::{CUSIPError, CUSIP};
+
+/// All the ways adding or loading mappings could fail.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum RemapError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The header row does not have a column with this name.
+    MissingColumn(&'static str),
+    /// A row has a value that is not a valid CUSIP.
+    InvalidCusip {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// Why the value is invalid
+        error: CUSIPError,
+    },
+    /// The mapping from this CUSIP would lead back to it, through the mappings already held.
+    Cycle {
+        /// The old CUSIP of the mapping
+        old: CUSIP,
+    },
+}
+
+impl fmt::Display for RemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemapError::Io(err) => write!(f, "error reading mappings: {err}"),
+            RemapError::MissingColumn(name) => write!(f, "no column named {name:?}"),
+            RemapError::InvalidCusip { line, error } => write!(f, "line {line}: {error}"),
+            RemapError::Cycle { old } => write!(f, "mapping {old} would form a cycle"),
+        }
+    }
+}
+
+impl Error for RemapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RemapError::Io(err) => Some(err),
+            RemapError::InvalidCusip { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RemapError {
+    fn from(err: io::Error) -> Self {
+        RemapError::Io(err)
+    }
+}
+
+/// What applying a `CusipRemapper` to a collection did.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemapReport {
+    /// Each CUSIP that was replaced, with its replacement, in the order encountered.
+    pub remapped: Vec<(CUSIP, CUSIP)>,
+    /// The number of CUSIPs that had no mapping and were kept as they were.
+    pub unmapped: usize,
+}
+
+/// A table of old→new CUSIP mappings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CusipRemapper {
+    mappings: HashMap<CUSIP, CUSIP>,
+}
+
+impl CusipRemapper {
+    /// Constructs a remapper with no mappings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads mappings from comma-separated text with a header row, which must have columns named
+    /// `old` and `new` (any other columns are ignored). Values may have surrounding whitespace or
+    /// lowercase letters, as `CUSIP::parse_loose()` allows. Blank lines are skipped, and a later
+    /// row for the same old CUSIP replaces an earlier one.
+    pub fn from_csv<R: BufRead>(reader: R) -> Result<Self, RemapError> {
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(header) => split_record(&header?, ','),
+            None => return Ok(Self::new()),
+        };
+        let column = |name: &'static str| {
+            header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or(RemapError::MissingColumn(name))
+        };
+        let old_col = column("old")?;
+        let new_col = column("new")?;
+
+        let mut remapper = Self::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split_record(&line, ',');
+            let cusip = |col: usize| {
+                CUSIP::parse_loose(fields.get(col).map_or("", String::as_str))
+                    .map_err(|error| RemapError::InvalidCusip { line: i + 2, error })
+            };
+            remapper.insert(cusip(old_col)?, cusip(new_col)?)?;
+        }
+
+        Ok(remapper)
+    }
+
+    /// Adds (or replaces) the mapping from `old` to `new`. Fails, leaving the remapper unchanged,
+    /// if following the mappings from `new` would lead back to `old`.
+    pub fn insert(&mut self, old: CUSIP, new: CUSIP) -> Result<(), RemapError> {
+        let mut current = new;
+        loop {
+            if current == old {
+                return Err(RemapError::Cycle { old });
+            }
+            match self.mappings.get(&current) {
+                Some(next) => current = *next,
+                None => break,
+            }
+        }
+        self.mappings.insert(old, new);
+        Ok(())
+    }
+
+    /// Returns the number of mappings.
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    /// Returns true if there are no mappings.
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// Returns the latest CUSIP the CUSIP has been remapped to, following chains of mappings, or
+    /// `None` if it has no mapping.
+    pub fn get(&self, cusip: &CUSIP) -> Option<CUSIP> {
+        let mut current = *self.mappings.get(cusip)?;
+        // Cycles are rejected on insert, so every chain ends
+        while let Some(next) = self.mappings.get(&current) {
+            current = *next;
+        }
+        Some(current)
+    }
+
+    /// Returns the latest CUSIP the CUSIP has been remapped to, or the CUSIP itself if it has no
+    /// mapping.
+    pub fn remap(&self, cusip: &CUSIP) -> CUSIP {
+        self.get(cusip).unwrap_or(*cusip)
+    }
+
+    /// Returns an iterator that remaps each CUSIP.
+    pub fn remap_iter<'a, I>(&'a self, cusips: I) -> impl Iterator<Item = CUSIP> + 'a
+    where
+        I: IntoIterator<Item = CUSIP>,
+        I::IntoIter: 'a,
+    {
+        cusips.into_iter().map(move |cusip| self.remap(&cusip))
+    }
+
+    /// Remaps every CUSIP in the collection (such as a `BTreeSet`, `HashSet` or `Vec`), returning
+    /// the new collection and a report of what changed. For sets, CUSIPs remapped onto one
+    /// already present merge into one entry.
+    pub fn apply<C>(&self, cusips: C) -> (C, RemapReport)
+    where
+        C: IntoIterator<Item = CUSIP> + FromIterator<CUSIP>,
+    {
+        let mut report = RemapReport::default();
+        let remapped = cusips
+            .into_iter()
+            .map(|cusip| match self.get(&cusip) {
+                Some(new) => {
+                    report.remapped.push((cusip, new));
+                    new
+                }
+                None => {
+                    report.unmapped += 1;
+                    cusip
+                }
+            })
+            .collect();
+        (remapped, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cusip(s: &str) -> CUSIP {
+        CUSIP::parse(s).unwrap()
+    }
+
+    #[test]
+    fn follows_chains_and_rejects_cycles() {
+        let (a, b, c) = (cusip("037833100"), cusip("594918104"), cusip("09739D100"));
+        let mut remapper = CusipRemapper::new();
+        remapper.insert(a, b).unwrap();
+        remapper.insert(b, c).unwrap();
+        assert_eq!(remapper.get(&a), Some(c));
+        assert_eq!(remapper.remap(&c), c);
+        assert!(matches!(
+            remapper.insert(c, a),
+            Err(RemapError::Cycle { old }) if old == c
+        ));
+        assert!(matches!(
+            remapper.insert(a, a),
+            Err(RemapError::Cycle { .. })
+        ));
+        assert_eq!(remapper.len(), 2);
+        assert_eq!(
+            remapper.remap_iter([a, b, c]).collect::<Vec<_>>(),
+            [c, c, c]
+        );
+    }
+
+    #[test]
+    fn load_errors() {
+        let csv = "new,old\n037833100, 594918104\n037833101,594918104\n";
+        match CusipRemapper::from_csv(csv.as_bytes()) {
+            Err(RemapError::InvalidCusip {
+                line: 3,
+                error: CUSIPError::IncorrectCheckDigit { .. },
+            }) => {}
+            other => panic!("Expected InvalidCusip on line 3, but got: {other:?}"),
+        }
+        assert!(matches!(
+            CusipRemapper::from_csv("old\n".as_bytes()),
+            Err(RemapError::MissingColumn("new"))
+        ));
+    }
+}
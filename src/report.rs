@@ -0,0 +1,353 @@
+#![warn(missing_docs)]
+//! # cusip::report
+//!
+//! A summary of validating many values at once, with text and JSON renderings.
+//!
+//! A `ValidationReport` records the outcome for each value validated, keeping the details of each
+//! failure. It can then be rendered as text for people or as JSON for other programs, with counts
+//! by kind of error and by the field of the CUSIP that was at fault, so command line tools and
+//! batch jobs emit consistent summaries.
+//!
+//! ```
+//! use cusip::report::ValidationReport;
+//!
+//! let report = ValidationReport::from_values(["037833100", "037833101", "03783310"]);
+//! assert_eq!(report.total(), 3);
+//! assert_eq!(report.invalid(), 2);
+//!
+//! print!("{}", report.render_text());
+//! assert!(report.render_json().starts_with(r#"{"total":3,"valid":1,"invalid":2,"#));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::{CUSIPError, CUSIP};
+
+/// One value that failed validation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportedError {
+    /// The (zero-based) position of the value among those validated.
+    pub index: usize,
+    /// The value as given.
+    pub value: String,
+    /// Why the value is invalid.
+    pub error: CUSIPError,
+}
+
+impl ReportedError {
+    /// The name of the kind of error, such as `"IncorrectCheckDigit"`.
+    pub fn kind(&self) -> &'static str {
+        self.error.kind_name()
+    }
+
+    /// The field of the CUSIP at fault: `"length"`, `"issuer_num"`, `"issue_num"` or
+    /// `"check_digit"`.
+    pub fn field(&self) -> &'static str {
+        field_name(&self.error)
+    }
+}
+
+fn field_name(error: &CUSIPError) -> &'static str {
+    match error {
+        CUSIPError::InvalidCUSIPLength { .. } | CUSIPError::InvalidPayloadLength { .. } => "length",
+        CUSIPError::InvalidIssuerNumLength { .. } | CUSIPError::InvalidIssuerNum { .. } => {
+            "issuer_num"
+        }
+        CUSIPError::InvalidIssueNumLength { .. } | CUSIPError::InvalidIssueNum { .. } => {
+            "issue_num"
+        }
+        CUSIPError::InvalidCheckDigit { .. } | CUSIPError::IncorrectCheckDigit { .. } => {
+            "check_digit"
+        }
+    }
+}
+
+/// The outcome of validating many values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    total: usize,
+    errors: Vec<ReportedError>,
+}
+
+impl ValidationReport {
+    /// Constructs an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validates each value with `CUSIP::parse()`, recording the outcomes.
+    pub fn from_values<I, S>(values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let mut report = Self::new();
+        for value in values {
+            let value = value.as_ref();
+            report.record(value, CUSIP::parse(value).map(|_| ()));
+        }
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_batch("report", report.total, started);
+        report
+    }
+
+    /// Records the outcome of validating the next value.
+    pub fn record<T>(&mut self, value: &str, result: Result<T, CUSIPError>) {
+        #[cfg(feature = "metrics")]
+        match &result {
+            Ok(_) => crate::metrics::record_successes(1),
+            Err(error) => crate::metrics::record_failure(error),
+        }
+        if let Err(error) = result {
+            self.errors.push(ReportedError {
+                index: self.total,
+                value: value.to_string(),
+                error,
+            });
+        }
+        self.total += 1;
+    }
+
+    /// Returns the number of values validated.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of values that were valid.
+    pub fn valid(&self) -> usize {
+        self.total - self.errors.len()
+    }
+
+    /// Returns the number of values that were invalid.
+    pub fn invalid(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns the details of each invalid value, in the order validated.
+    pub fn errors(&self) -> &[ReportedError] {
+        &self.errors
+    }
+
+    /// Returns the number of invalid values for each kind of error.
+    pub fn counts_by_kind(&self) -> BTreeMap<&'static str, usize> {
+        count(self.errors.iter().map(ReportedError::kind))
+    }
+
+    /// Returns the number of invalid values for each field at fault.
+    pub fn counts_by_field(&self) -> BTreeMap<&'static str, usize> {
+        count(self.errors.iter().map(ReportedError::field))
+    }
+
+    /// Renders the report as text for people, ending with a newline.
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Validated {} values: {} valid, {} invalid",
+            self.total(),
+            self.valid(),
+            self.invalid()
+        );
+        if self.errors.is_empty() {
+            return out;
+        }
+
+        for (title, counts) in [
+            ("Errors by kind", self.counts_by_kind()),
+            ("Errors by field", self.counts_by_field()),
+        ] {
+            let width = counts.keys().map(|k| k.len()).max().unwrap_or(0);
+            let _ = writeln!(out, "\n{title}:");
+            for (name, n) in counts {
+                let _ = writeln!(out, "  {name:width$}  {n}");
+            }
+        }
+
+        let _ = writeln!(out, "\nDetails:");
+        for e in &self.errors {
+            let _ = writeln!(
+                out,
+                "  [{}] {:?}: {}: {}",
+                e.index,
+                e.value,
+                e.field(),
+                e.error
+            );
+        }
+        out
+    }
+
+    /// Renders the report as a single line of JSON for other programs.
+    pub fn render_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            r#"{{"total":{},"valid":{},"invalid":{}"#,
+            self.total(),
+            self.valid(),
+            self.invalid()
+        );
+        for (name, counts) in [
+            ("by_kind", self.counts_by_kind()),
+            ("by_field", self.counts_by_field()),
+        ] {
+            let counts: Vec<String> = counts
+                .into_iter()
+                .map(|(k, n)| format!(r#""{k}":{n}"#))
+                .collect();
+            let _ = write!(out, r#","{name}":{{{}}}"#, counts.join(","));
+        }
+        let errors: Vec<String> = self
+            .errors
+            .iter()
+            .map(|e| {
+                format!(
+                    r#"{{"index":{},"value":{},"kind":"{}","field":"{}","message":{}}}"#,
+                    e.index,
+                    json_string(&e.value),
+                    e.kind(),
+                    e.field(),
+                    json_string(&e.error.to_string())
+                )
+            })
+            .collect();
+        let _ = write!(out, r#","errors":[{}]}}"#, errors.join(","));
+        out
+    }
+}
+
+/// Renders the outcome of validating one value as a single line of JSON (with no newline), for
+/// JSON Lines output. Every line has the same keys: the `input` as given, whether it is `valid`,
+/// the `normalized` CUSIP and its `issuer_num`, `issue_num` and `check_digit` (`null` if invalid),
+/// and the error `kind`, `field` and `message` (`null` if valid).
+///
+/// ```
+/// use cusip::report::render_json_line;
+/// use cusip::CUSIP;
+///
+/// let input = "09739d100";
+/// let line = render_json_line(input, &CUSIP::parse_loose(input));
+/// assert_eq!(
+///     line,
+///     r#"{"input":"09739d100","valid":true,"normalized":"09739D100","issuer_num":"09739D","issue_num":"10","check_digit":"0","kind":null,"field":null,"message":null}"#
+/// );
+/// ```
+pub fn render_json_line(value: &str, result: &Result<CUSIP, CUSIPError>) -> String {
+    let mut out = format!(r#"{{"input":{}"#, json_string(value));
+    let _ = match result {
+        Ok(cusip) => write!(
+            out,
+            r#","valid":true,"normalized":"{cusip}","issuer_num":"{}","issue_num":"{}","check_digit":"{}","kind":null,"field":null,"message":null}}"#,
+            cusip.issuer_num(),
+            cusip.issue_num(),
+            cusip.check_digit()
+        ),
+        Err(error) => write!(
+            out,
+            r#","valid":false,"normalized":null,"issuer_num":null,"issue_num":null,"check_digit":null,"kind":"{}","field":"{}","message":{}}}"#,
+            error.kind_name(),
+            field_name(error),
+            json_string(&error.to_string())
+        ),
+    };
+    out
+}
+
+fn count(names: impl Iterator<Item = &'static str>) -> BTreeMap<&'static str, usize> {
+    let mut counts = BTreeMap::new();
+    for name in names {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Quotes and escapes the string as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ValidationReport {
+        ValidationReport::from_values(["037833100", "037833101", "0378331", "09739d100"])
+    }
+
+    #[test]
+    fn render_text() {
+        assert_eq!(
+            report().render_text(),
+            r#"Validated 4 values: 1 valid, 3 invalid
+
+Errors by kind:
+  IncorrectCheckDigit  1
+  InvalidCUSIPLength   1
+  InvalidIssuerNum     1
+
+Errors by field:
+  check_digit  1
+  issuer_num   1
+  length       1
+
+Details:
+  [1] "037833101": check_digit: incorrect Check Digit '1' when expecting '0'
+  [2] "0378331": length: invalid CUSIP length 7 bytes when expecting 9
+  [3] "09739d100": issuer_num: Issuer Number "09739d" is not six uppercase ASCII alphanumeric characters
+"#
+        );
+        assert_eq!(
+            ValidationReport::new().render_text(),
+            "Validated 0 values: 0 valid, 0 invalid\n"
+        );
+    }
+
+    #[test]
+    fn render_json() {
+        let json = report().render_json();
+        assert!(json.starts_with(
+            r#"{"total":4,"valid":1,"invalid":3,"by_kind":{"IncorrectCheckDigit":1,"InvalidCUSIPLength":1,"InvalidIssuerNum":1},"by_field":{"check_digit":1,"issuer_num":1,"length":1},"errors":[{"index":1,"#
+        ));
+        assert!(json.contains(
+            r#""message":"Issuer Number \"09739d\" is not six uppercase ASCII alphanumeric characters"}]}"#
+        ));
+
+        #[cfg(feature = "serde")]
+        serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    }
+
+    #[test]
+    fn render_invalid_json_line() {
+        let line = render_json_line("037833101", &CUSIP::parse("037833101"));
+        assert!(line.ends_with(
+            r#""kind":"IncorrectCheckDigit","field":"check_digit","message":"incorrect Check Digit '1' when expecting '0'"}"#
+        ));
+
+        #[cfg(feature = "serde")]
+        serde_json::from_str::<serde_json::Value>(&line).unwrap();
+    }
+
+    #[test]
+    fn escape_json() {
+        assert_eq!(json_string("a\"b\\c\u{1}"), r#""a\"b\\c\u0001""#);
+    }
+}
@@ -0,0 +1,247 @@
+#![warn(missing_docs)]
+//! # cusip::resolver
+//!
+//! An extension point for looking up descriptive metadata about the _Issuer_ of a CUSIP.
+//!
+//! This crate does not ship any issuer data. Implement `IssuerResolver` over whatever securities
+//! master you have, or load a CSV file into an `InMemoryIssuerResolver`, then use `enrich()` to
+//! pair a `CUSIP` with what is known about its _Issuer_.
+//!
+//! ```
+//! use cusip::resolver::{enrich, InMemoryIssuerResolver};
+//! use cusip::CUSIP;
+//!
+//! let csv = "issuer_num,name,country\n037833,Apple Inc.,US\n594918,Microsoft Corp.,US\n";
+//! let resolver = InMemoryIssuerResolver::from_csv(csv.as_bytes()).unwrap();
+//!
+//! let cusip = CUSIP::parse("037833100").unwrap();
+//! let enriched = enrich(&cusip, &resolver);
+//! let issuer = enriched.issuer.unwrap();
+//! assert_eq!(issuer.name, "Apple Inc.");
+//! assert_eq!(issuer.attributes["country"], "US");
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::csv::split_record;
+use crate::payload::IssuerNum;
+use crate::{CUSIPError, CUSIP};
+
+/// What is known about an _Issuer_.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IssuerInfo {
+    /// The _Issuer Number_.
+    pub issuer_num: IssuerNum,
+    /// The name of the _Issuer_.
+    pub name: String,
+    /// Any other metadata, by name.
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Looks up metadata about an _Issuer_ by its _Issuer Number_.
+pub trait IssuerResolver {
+    /// Returns what is known about the _Issuer_ with the given _Issuer Number_, if anything.
+    fn resolve(&self, issuer_num: &IssuerNum) -> Option<IssuerInfo>;
+}
+
+impl<R: IssuerResolver + ?Sized> IssuerResolver for &R {
+    fn resolve(&self, issuer_num: &IssuerNum) -> Option<IssuerInfo> {
+        (**self).resolve(issuer_num)
+    }
+}
+
+impl<R: IssuerResolver + ?Sized> IssuerResolver for Box<R> {
+    fn resolve(&self, issuer_num: &IssuerNum) -> Option<IssuerInfo> {
+        (**self).resolve(issuer_num)
+    }
+}
+
+/// A `CUSIP` paired with what its resolver knew about its _Issuer_.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enriched {
+    /// The CUSIP.
+    pub cusip: CUSIP,
+    /// What is known about the _Issuer_, if anything.
+    pub issuer: Option<IssuerInfo>,
+}
+
+/// Pairs the CUSIP with what the resolver knows about its _Issuer_.
+pub fn enrich<R: IssuerResolver + ?Sized>(cusip: &CUSIP, resolver: &R) -> Enriched {
+    Enriched {
+        cusip: *cusip,
+        issuer: resolver.resolve(&cusip.issuer()),
+    }
+}
+
+/// All the ways loading issuer data could fail.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum LoadError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The header row does not have a column with this name.
+    MissingColumn(&'static str),
+    /// A row has an _Issuer Number_ that is not in a valid format.
+    InvalidIssuerNum {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// Why the _Issuer Number_ is invalid
+        error: CUSIPError,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "error reading issuer data: {err}"),
+            LoadError::MissingColumn(name) => write!(f, "no column named {name:?}"),
+            LoadError::InvalidIssuerNum { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::MissingColumn(_) => None,
+            LoadError::InvalidIssuerNum { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+/// An `IssuerResolver` holding all its data in memory.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryIssuerResolver {
+    issuers: HashMap<IssuerNum, IssuerInfo>,
+}
+
+impl InMemoryIssuerResolver {
+    /// Constructs an empty resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a resolver from comma-separated text with a header row. There must be columns named
+    /// `issuer_num` and `name`; any other columns become `attributes`. Every _Issuer Number_ is
+    /// validated, and blank lines are skipped. A later row for the same _Issuer Number_ replaces an
+    /// earlier one.
+    pub fn from_csv<R: BufRead>(reader: R) -> Result<Self, LoadError> {
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(header) => split_record(&header?, ','),
+            None => return Ok(Self::new()),
+        };
+        let column = |name: &'static str| {
+            header
+                .iter()
+                .position(|h| h.trim() == name)
+                .ok_or(LoadError::MissingColumn(name))
+        };
+        let issuer_num_col = column("issuer_num")?;
+        let name_col = column("name")?;
+
+        let mut resolver = Self::new();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = split_record(&line, ',');
+            let field = |col: usize| fields.get(col).map(|f| f.trim()).unwrap_or("");
+
+            let issuer_num = IssuerNum::parse(field(issuer_num_col))
+                .map_err(|error| LoadError::InvalidIssuerNum { line: i + 2, error })?;
+
+            let attributes = header
+                .iter()
+                .enumerate()
+                .filter(|(col, _)| *col != issuer_num_col && *col != name_col)
+                .map(|(col, h)| (h.trim().to_string(), field(col).to_string()))
+                .collect();
+            resolver.insert(IssuerInfo {
+                issuer_num,
+                name: field(name_col).to_string(),
+                attributes,
+            });
+        }
+
+        Ok(resolver)
+    }
+
+    /// Adds (or replaces) the information for an _Issuer_, returning any previous information.
+    pub fn insert(&mut self, info: IssuerInfo) -> Option<IssuerInfo> {
+        self.issuers.insert(info.issuer_num, info)
+    }
+
+    /// Returns the number of _Issuers_ known.
+    pub fn len(&self) -> usize {
+        self.issuers.len()
+    }
+
+    /// Returns true if no _Issuers_ are known.
+    pub fn is_empty(&self) -> bool {
+        self.issuers.is_empty()
+    }
+}
+
+impl IssuerResolver for InMemoryIssuerResolver {
+    fn resolve(&self, issuer_num: &IssuerNum) -> Option<IssuerInfo> {
+        self.issuers.get(issuer_num).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_and_resolve() {
+        let csv = "name,issuer_num\n\"Boise Cascade Co, L.L.C.\",09739D\n\n";
+        let resolver = InMemoryIssuerResolver::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(resolver.len(), 1);
+
+        let enriched = enrich(&CUSIP::parse("09739D100").unwrap(), &resolver);
+        assert_eq!(enriched.issuer.unwrap().name, "Boise Cascade Co, L.L.C.");
+
+        let enriched = enrich(&CUSIP::parse("037833100").unwrap(), &resolver);
+        assert_eq!(enriched.issuer, None);
+    }
+
+    #[test]
+    fn dyn_resolver() {
+        let resolver: Box<dyn IssuerResolver> = Box::new(InMemoryIssuerResolver::new());
+        let apple = IssuerNum::parse("037833").unwrap();
+        assert_eq!(resolver.resolve(&apple), None);
+    }
+
+    #[test]
+    fn reject_missing_column() {
+        match InMemoryIssuerResolver::from_csv("issuer_num,title\n".as_bytes()) {
+            Err(LoadError::MissingColumn("name")) => {}
+            other => panic!("Expected MissingColumn(\"name\"), but got: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reject_invalid_issuer_num() {
+        let csv = "issuer_num,name\n037833,Apple Inc.\n03783,Truncated\n";
+        match InMemoryIssuerResolver::from_csv(csv.as_bytes()) {
+            Err(LoadError::InvalidIssuerNum {
+                line: 3,
+                error: CUSIPError::InvalidIssuerNumLength { was: 5 },
+            }) => {}
+            other => panic!("Expected InvalidIssuerNum on line 3, but got: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+#![warn(missing_docs)]
+//! # cusip::rocket
+//!
+//! Integration with the [Rocket](https://rocket.rs/) web framework (requires the `rocket`
+//! feature).
+//!
+//! `CUSIP` implements `FromParam` and `FromFormField`, so routes can take a validated `CUSIP`
+//! directly, either as a dynamic path segment or as a form or query field. Values are parsed with
+//! `CUSIP::parse_loose`, the same as the `FromStr` implementation.
+//!
+//! ```no_run
+//! # #[macro_use] extern crate rocket;
+//! use cusip::CUSIP;
+//!
+//! #[get("/securities/<cusip>")]
+//! fn security(cusip: CUSIP) -> String {
+//!     format!("Issuer Number {}", cusip.issuer_num())
+//! }
+//!
+//! #[get("/search?<cusip>")]
+//! fn search(cusip: CUSIP) -> String {
+//!     format!("Issue Number {}", cusip.issue_num())
+//! }
+//! ```
+
+use ::rocket::form::{self, FromFormField, ValueField};
+use ::rocket::request::FromParam;
+
+use crate::{CUSIPError, CUSIP};
+
+impl<'a> FromParam<'a> for CUSIP {
+    type Error = CUSIPError;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        CUSIP::parse_loose(param)
+    }
+}
+
+impl<'v> FromFormField<'v> for CUSIP {
+    fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+        CUSIP::parse_loose(field.value)
+            .map_err(|err| form::Error::validation(err.to_string()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rocket::form::Form;
+
+    #[test]
+    fn from_param() {
+        assert_eq!(
+            CUSIP::from_param("09739d100"),
+            Ok(CUSIP::parse("09739D100").unwrap())
+        );
+        assert!(matches!(
+            CUSIP::from_param("09739D101"),
+            Err(CUSIPError::IncorrectCheckDigit { .. })
+        ));
+    }
+
+    #[test]
+    fn from_form_field() {
+        #[derive(Debug, ::rocket::FromForm)]
+        struct Query {
+            cusip: CUSIP,
+        }
+
+        let query: Query = Form::parse("cusip=09739D100").unwrap();
+        assert_eq!(query.cusip, CUSIP::parse("09739D100").unwrap());
+
+        let errors = Form::<Query>::parse("cusip=09739D101").unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|e| e.to_string().contains("incorrect Check Digit")));
+    }
+}
@@ -0,0 +1,103 @@
+#![warn(missing_docs)]
+//! # cusip::rusqlite
+//!
+//! SQL functions for validating CUSIPs inside SQLite via
+//! [rusqlite](https://crates.io/crates/rusqlite) (requires the `rusqlite` feature).
+//!
+//! Call `register_functions()` on a `Connection` to make these available to queries:
+//!
+//! * `cusip_valid(text)`: 1 if the value is a valid CUSIP (as for `validate()`), 0 if it is not,
+//!   and NULL if the value is NULL.
+//! * `cusip_check_digit(text)`: The _Check Digit_ (as text) for an 8-character _Payload_, or NULL
+//!   if the value is NULL or not a valid _Payload_.
+//!
+//! ```
+//! use rusqlite::Connection;
+//!
+//! let conn = Connection::open_in_memory().unwrap();
+//! cusip::rusqlite::register_functions(&conn).unwrap();
+//!
+//! let valid: bool = conn
+//!     .query_row("SELECT cusip_valid('037833100')", [], |row| row.get(0))
+//!     .unwrap();
+//! assert!(valid);
+//!
+//! let check_digit: String = conn
+//!     .query_row("SELECT cusip_check_digit('03783310')", [], |row| row.get(0))
+//!     .unwrap();
+//! assert_eq!(check_digit, "0");
+//! ```
+
+use ::rusqlite::functions::{Context, FunctionFlags};
+use ::rusqlite::types::ValueRef;
+use ::rusqlite::{Connection, Result};
+
+use crate::{build_from_payload, validate};
+
+/// Registers the `cusip_valid` and `cusip_check_digit` SQL functions on the connection.
+pub fn register_functions(conn: &Connection) -> Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8
+        | FunctionFlags::SQLITE_DETERMINISTIC
+        | FunctionFlags::SQLITE_INNOCUOUS;
+
+    conn.create_scalar_function("cusip_valid", 1, flags, |ctx| {
+        Ok(match ctx.get_raw(0) {
+            ValueRef::Null => None,
+            ValueRef::Text(bytes) => Some(std::str::from_utf8(bytes).is_ok_and(validate)),
+            _ => Some(false),
+        })
+    })?;
+
+    conn.create_scalar_function("cusip_check_digit", 1, flags, |ctx| {
+        Ok(text_arg(ctx)
+            .and_then(|payload| build_from_payload(payload).ok())
+            .map(|cusip| cusip.check_digit().to_string()))
+    })?;
+
+    Ok(())
+}
+
+/// Returns the first argument if it is valid UTF-8 text.
+fn text_arg<'a>(ctx: &'a Context<'_>) -> Option<&'a str> {
+    match ctx.get_raw(0) {
+        ValueRef::Text(bytes) => std::str::from_utf8(bytes).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        register_functions(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn cusip_valid() {
+        let conn = connection();
+        let query =
+            |sql: &str| -> Option<bool> { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(query("SELECT cusip_valid('09739D100')"), Some(true));
+        assert_eq!(query("SELECT cusip_valid('09739D101')"), Some(false));
+        assert_eq!(query("SELECT cusip_valid('09739d100')"), Some(false));
+        assert_eq!(query("SELECT cusip_valid(9739100)"), Some(false));
+        assert_eq!(query("SELECT cusip_valid(NULL)"), None);
+    }
+
+    #[test]
+    fn cusip_check_digit() {
+        let conn = connection();
+        let query =
+            |sql: &str| -> Option<String> { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+        assert_eq!(
+            query("SELECT cusip_check_digit('09739D10')"),
+            Some("0".to_string())
+        );
+        assert_eq!(query("SELECT cusip_check_digit('09739D1')"), None);
+        assert_eq!(query("SELECT cusip_check_digit('09739d10')"), None);
+        assert_eq!(query("SELECT cusip_check_digit(NULL)"), None);
+    }
+}
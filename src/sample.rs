@@ -0,0 +1,173 @@
+#![warn(missing_docs)]
+//! # cusip::sample
+//!
+//! Stratified random sampling of CUSIP collections, for building representative QA samples from
+//! large universes.
+//!
+//! The input is read once, keeping a reservoir of at most `per_stratum` CUSIPs for each stratum,
+//! so memory use does not grow with the size of the input. The sample is determined entirely by
+//! the seed and the order of the input, so the same seed reproduces the same sample (the random
+//! number generator is built in, and will not change between releases of this crate).
+//!
+//! ```
+//! use cusip::sample::{stratified_sample, Strata};
+//! use cusip::CUSIP;
+//!
+//! let universe: Vec<CUSIP> = ["037833100", "594918104", "S08000AA9", "G0052B105"]
+//!     .iter()
+//!     .map(|s| CUSIP::parse(s).unwrap())
+//!     .collect();
+//!
+//! // At most one CUSIP for each CINS country, plus one that is not a CINS
+//! let sample = stratified_sample(universe.iter().copied(), Strata::CinsCountry, 1, 42);
+//! assert_eq!(sample.len(), 3);
+//! assert_eq!(sample, stratified_sample(universe, Strata::CinsCountry, 1, 42));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{IssueKind, CUSIP};
+
+/// The built-in ways of dividing CUSIPs into strata.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strata {
+    /// By CINS country code, with all CUSIPs that are not CINS identifiers in one stratum.
+    CinsCountry,
+    /// By _Issuer Number_.
+    Issuer,
+    /// By kind of _Issue Number_: all digits (the convention for equities) or containing letters
+    /// (the convention for fixed income).
+    IssueKind,
+}
+
+impl Strata {
+    fn key(self, cusip: &CUSIP) -> String {
+        match self {
+            Strata::CinsCountry => cusip
+                .as_cins()
+                .map(|cins| cins.country_code().to_string())
+                .unwrap_or_default(),
+            Strata::Issuer => cusip.issuer_num().to_string(),
+            Strata::IssueKind => match cusip.issue_kind() {
+                IssueKind::Equity => "numeric".to_string(),
+                IssueKind::FixedIncome => "alphanumeric".to_string(),
+            },
+        }
+    }
+}
+
+/// Draws at most `per_stratum` CUSIPs at random from each stratum of the input. The result is
+/// ordered by stratum, and within each stratum by position in the input.
+pub fn stratified_sample<I>(cusips: I, strata: Strata, per_stratum: usize, seed: u64) -> Vec<CUSIP>
+where
+    I: IntoIterator<Item = CUSIP>,
+{
+    stratified_sample_by(cusips, |cusip| strata.key(cusip), per_stratum, seed)
+}
+
+/// Draws at most `per_stratum` CUSIPs at random from each stratum of the input, where the stratum
+/// of each CUSIP is given by the `key` function. The result is ordered by key, and within each
+/// stratum by position in the input.
+pub fn stratified_sample_by<I, K, F>(cusips: I, key: F, per_stratum: usize, seed: u64) -> Vec<CUSIP>
+where
+    I: IntoIterator<Item = CUSIP>,
+    K: Ord,
+    F: Fn(&CUSIP) -> K,
+{
+    let mut rng = SplitMix64(seed);
+    // Each reservoir holds (position in input, CUSIP) pairs, plus the count seen in its stratum
+    let mut reservoirs: BTreeMap<K, (u64, Vec<(usize, CUSIP)>)> = BTreeMap::new();
+
+    for (index, cusip) in cusips.into_iter().enumerate() {
+        let (seen, reservoir) = reservoirs.entry(key(&cusip)).or_default();
+        *seen += 1;
+        if reservoir.len() < per_stratum {
+            reservoir.push((index, cusip));
+        } else {
+            let j = rng.below(*seen);
+            if j < per_stratum as u64 {
+                reservoir[j as usize] = (index, cusip);
+            }
+        }
+    }
+
+    reservoirs
+        .into_values()
+        .flat_map(|(_, mut reservoir)| {
+            reservoir.sort_unstable_by_key(|(index, _)| *index);
+            reservoir.into_iter().map(|(_, cusip)| cusip)
+        })
+        .collect()
+}
+
+/// The SplitMix64 generator: small, fast and fully specified, so samples are reproducible.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, for `n` greater than zero.
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(n)) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_from_parts;
+
+    fn universe() -> Vec<CUSIP> {
+        let mut cusips = Vec::new();
+        for issuer in ["037833", "594918", "S08000", "G0052B"] {
+            for issue in ["10", "20", "AA", "AB", "AC"] {
+                cusips.push(build_from_parts(issuer, issue).unwrap());
+            }
+        }
+        cusips
+    }
+
+    #[test]
+    fn one_per_issuer() {
+        let sample = stratified_sample(universe(), Strata::Issuer, 1, 7);
+        let issuers: Vec<_> = sample.iter().map(|c| c.issuer_num()).collect();
+        assert_eq!(issuers, ["037833", "594918", "G0052B", "S08000"]);
+    }
+
+    #[test]
+    fn small_strata_are_kept_whole() {
+        let sample = stratified_sample(universe(), Strata::IssueKind, 10, 7);
+        assert_eq!(sample.len(), 18);
+        assert_eq!(
+            sample.iter().filter(|c| c.issue_num() == "10").count(),
+            4,
+            "all numeric issues fit in their reservoir"
+        );
+    }
+
+    #[test]
+    fn seed_determines_sample() {
+        let a = stratified_sample(universe(), Strata::CinsCountry, 3, 1);
+        let b = stratified_sample(universe(), Strata::CinsCountry, 3, 1);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 9);
+
+        let samples: Vec<_> = (0..20)
+            .map(|seed| stratified_sample(universe(), Strata::CinsCountry, 3, seed))
+            .collect();
+        assert!(samples.iter().any(|s| *s != a));
+    }
+
+    #[test]
+    fn custom_key() {
+        let sample = stratified_sample_by(universe(), |c| c.is_private_use(), 2, 0);
+        assert_eq!(sample.len(), 2);
+    }
+}
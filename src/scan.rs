@@ -0,0 +1,109 @@
+#![warn(missing_docs)]
+//! # cusip::scan
+//!
+//! Finding CUSIPs in free text, such as documents, emails and text extracted from PDFs.
+//!
+//! `find_cusips()` looks for runs of exactly 9 uppercase ASCII letters and digits that are not part
+//! of a longer run of ASCII letters and digits, and yields those that are valid CUSIPs (_Check
+//! Digit_ included) with their byte offsets. The text need not be UTF-8. The _Check Digit_ rules
+//! out most runs that merely happen to be 9 characters long, but not all of them: about one in ten
+//! 9-digit numbers is a valid CUSIP, so expect some false positives in text full of numbers.
+//!
+//! CUSIPs inside ISINs are not found, because an ISIN is one run of 12 characters. See
+//! `cusip::isin` for those.
+//!
+//! ```
+//! use cusip::scan::find_cusips;
+//!
+//! let text = "Bought 100 AAPL (CUSIP 037833100) and 50 of S08000AA9; ref 123456780.";
+//! let found: Vec<(usize, String)> = find_cusips(text)
+//!     .map(|(offset, cusip)| (offset, cusip.to_string()))
+//!     .collect();
+//! assert_eq!(
+//!     found,
+//!     [(23, "037833100".to_string()), (44, "S08000AA9".to_string())]
+//! );
+//! ```
+
+use core::iter::FusedIterator;
+
+use crate::CUSIP;
+
+/// Returns an iterator over the valid CUSIPs in the text, with the byte offset of each. See the
+/// module documentation for what counts as a CUSIP in the text.
+pub fn find_cusips<T: AsRef<[u8]> + ?Sized>(text: &T) -> FindCusips<'_> {
+    FindCusips {
+        text: text.as_ref(),
+        pos: 0,
+    }
+}
+
+/// An iterator over the valid CUSIPs in some text, returned by `find_cusips()`.
+#[derive(Clone, Debug)]
+pub struct FindCusips<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Iterator for FindCusips<'_> {
+    type Item = (usize, CUSIP);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.text.len() {
+            let rest = &self.text[self.pos..];
+            let Some(skip) = rest.iter().position(u8::is_ascii_alphanumeric) else {
+                self.pos = self.text.len();
+                break;
+            };
+            let start = self.pos + skip;
+            let len = self.text[start..]
+                .iter()
+                .position(|b| !b.is_ascii_alphanumeric())
+                .unwrap_or(self.text.len() - start);
+            self.pos = start + len;
+
+            if len == 9 {
+                if let Ok(cusip) = CUSIP::from_bytes(&self.text[start..start + len]) {
+                    return Some((start, cusip));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl FusedIterator for FindCusips<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &[u8]) -> Vec<(usize, CUSIP)> {
+        find_cusips(text).collect()
+    }
+
+    #[test]
+    fn only_whole_valid_runs() {
+        let apple = CUSIP::parse("037833100").unwrap();
+        assert_eq!(found(b"037833100"), [(0, apple)]);
+        assert_eq!(found(b"(037833100)"), [(1, apple)]);
+        assert_eq!(found(b"037833100\n037833100"), [(0, apple), (10, apple)]);
+
+        assert_eq!(found(b""), []);
+        assert_eq!(found(b"037833101"), []); // Incorrect Check Digit
+        assert_eq!(found(b"037833100X"), []); // Part of a longer run
+        assert_eq!(found(b"x037833100"), []);
+        assert_eq!(found(b"US0378331005"), []);
+        assert_eq!(found(b"09739d100"), []); // Lowercase letters are not canonical
+    }
+
+    #[test]
+    fn any_bytes() {
+        let apple = CUSIP::parse("037833100").unwrap();
+        assert_eq!(found(b"\xff\xfe037833100\xc3"), [(2, apple)]);
+        assert_eq!(
+            find_cusips("Straße 037833100").collect::<Vec<_>>(),
+            [(8, apple)]
+        );
+    }
+}
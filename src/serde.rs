@@ -0,0 +1,336 @@
+//! # cusip::serde
+//!
+//! `Serialize` and `Deserialize` for `CUSIP` and the types built from it (requires the `serde`
+//! feature). A CUSIP is serialized as its 9-character string form, and deserializing requires that
+//! exact form, as `CUSIP::parse()` does. A `CINS` is serialized the same way, but can only be
+//! deserialized as the `CUSIP` it borrows from. A `CusipOrIsin` is serialized as the string it
+//! was parsed from, and deserialized as `CusipOrIsin::parse()` does. The parts `IssuerNum`,
+//! `IssueNum` and `Payload` are likewise serialized as their string forms, and deserialized as
+//! their `parse()` functions do.
+//!
+//! The collections `CusipArraySet`, `CusipColumn` and `IssuerIndex` are serialized as sequences of
+//! CUSIPs (in the collection's own order), and deserialized from any sequence of CUSIPs.
+//! Deserializing a `CusipArraySet` fails if the sequence has more distinct CUSIPs than its
+//! capacity.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use ::serde::de::{self, SeqAccess, Visitor};
+use ::serde::ser::SerializeSeq;
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::array_set::CusipArraySet;
+use crate::column::CusipColumn;
+use crate::identifier::CusipOrIsin;
+use crate::issuer_index::IssuerIndex;
+use crate::payload::{IssueNum, IssuerNum, Payload};
+use crate::{CINS, CUSIP};
+
+impl Serialize for CUSIP {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct CUSIPVisitor;
+
+impl Visitor<'_> for CUSIPVisitor {
+    type Value = CUSIP;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 9-character CUSIP string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<CUSIP, E> {
+        CUSIP::parse(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for CUSIP {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(CUSIPVisitor)
+    }
+}
+
+impl Serialize for CINS<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_cusip().serialize(serializer)
+    }
+}
+
+impl Serialize for IssuerNum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+struct IssuerNumVisitor;
+
+impl Visitor<'_> for IssuerNumVisitor {
+    type Value = IssuerNum;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 6-character Issuer Number string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<IssuerNum, E> {
+        IssuerNum::parse(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for IssuerNum {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(IssuerNumVisitor)
+    }
+}
+
+impl Serialize for IssueNum {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+struct IssueNumVisitor;
+
+impl Visitor<'_> for IssueNumVisitor {
+    type Value = IssueNum;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 2-character Issue Number string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<IssueNum, E> {
+        IssueNum::parse(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for IssueNum {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(IssueNumVisitor)
+    }
+}
+
+impl Serialize for Payload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+struct PayloadVisitor;
+
+impl Visitor<'_> for PayloadVisitor {
+    type Value = Payload;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an 8-character CUSIP Payload string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Payload, E> {
+        Payload::parse(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Payload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(PayloadVisitor)
+    }
+}
+
+impl Serialize for CusipOrIsin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+struct CusipOrIsinVisitor;
+
+impl Visitor<'_> for CusipOrIsinVisitor {
+    type Value = CusipOrIsin;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 9-character CUSIP or 12-character ISIN string")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<CusipOrIsin, E> {
+        CusipOrIsin::parse(value).map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for CusipOrIsin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(CusipOrIsinVisitor)
+    }
+}
+
+fn serialize_seq<'a, S, I>(serializer: S, len: usize, cusips: I) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    I: IntoIterator<Item = &'a CUSIP>,
+{
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    for cusip in cusips {
+        seq.serialize_element(cusip)?;
+    }
+    seq.end()
+}
+
+impl<const N: usize> Serialize for CusipArraySet<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self)
+    }
+}
+
+impl Serialize for CusipColumn {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self)
+    }
+}
+
+impl Serialize for IssuerIndex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self.iter())
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for CusipArraySet<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ArraySetVisitor<const N: usize>;
+
+        impl<'de, const N: usize> Visitor<'de> for ArraySetVisitor<N> {
+            type Value = CusipArraySet<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "a sequence of at most {N} distinct CUSIPs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut set = CusipArraySet::new();
+                while let Some(cusip) = seq.next_element()? {
+                    set.insert(cusip).map_err(de::Error::custom)?;
+                }
+                Ok(set)
+            }
+        }
+
+        deserializer.deserialize_seq(ArraySetVisitor::<N>)
+    }
+}
+
+/// Deserializes any collection that can be built from a sequence of CUSIPs.
+struct CollectVisitor<C>(PhantomData<C>);
+
+impl<'de, C: Default + Extend<CUSIP>> Visitor<'de> for CollectVisitor<C> {
+    type Value = C;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of CUSIPs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<C, A::Error> {
+        let mut collection = C::default();
+        while let Some(cusip) = seq.next_element()? {
+            collection.extend([cusip]);
+        }
+        Ok(collection)
+    }
+}
+
+impl<'de> Deserialize<'de> for CusipColumn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(CollectVisitor(PhantomData))
+    }
+}
+
+impl<'de> Deserialize<'de> for IssuerIndex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(CollectVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cusip = CUSIP::parse("09739D100").unwrap();
+        let json = serde_json::to_string(&cusip).unwrap();
+        assert_eq!(json, r#""09739D100""#);
+        assert_eq!(serde_json::from_str::<CUSIP>(&json).unwrap(), cusip);
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let err = serde_json::from_str::<CUSIP>(r#""09739D101""#).unwrap_err();
+        assert!(err.to_string().contains("incorrect Check Digit"));
+        assert!(serde_json::from_str::<CUSIP>("12").is_err());
+    }
+
+    #[test]
+    fn cins() {
+        let cusip = CUSIP::parse("S08000AA9").unwrap();
+        let json = serde_json::to_string(&cusip.as_cins().unwrap()).unwrap();
+        assert_eq!(json, r#""S08000AA9""#);
+    }
+
+    #[test]
+    fn parts() {
+        let cusip = CUSIP::parse("09739D100").unwrap();
+
+        let json = serde_json::to_string(&cusip.issuer()).unwrap();
+        assert_eq!(json, r#""09739D""#);
+        assert_eq!(
+            serde_json::from_str::<IssuerNum>(&json).unwrap(),
+            cusip.issuer()
+        );
+
+        let json = serde_json::to_string(&cusip.issue()).unwrap();
+        assert_eq!(json, r#""10""#);
+        assert_eq!(
+            serde_json::from_str::<IssueNum>(&json).unwrap(),
+            cusip.issue()
+        );
+
+        let payload = Payload::from(cusip);
+        let json = serde_json::to_string(&payload).unwrap();
+        assert_eq!(json, r#""09739D10""#);
+        assert_eq!(serde_json::from_str::<Payload>(&json).unwrap(), payload);
+
+        assert!(serde_json::from_str::<IssuerNum>(r#""09739""#).is_err());
+        assert!(serde_json::from_str::<IssueNum>(r#""1$""#).is_err());
+        assert!(serde_json::from_str::<Payload>(r#""09739D100""#).is_err());
+    }
+
+    #[test]
+    fn cusip_or_isin() {
+        let json = r#"["US0378331005","037833100"]"#;
+        let values: Vec<CusipOrIsin> = serde_json::from_str(json).unwrap();
+        assert_eq!(values[0].normalize(), values[1]);
+        assert_eq!(serde_json::to_string(&values).unwrap(), json);
+        assert!(serde_json::from_str::<CusipOrIsin>(r#""US0378331006""#).is_err());
+    }
+
+    #[test]
+    fn collections() {
+        let json = r#"["594918104","037833100","594918104"]"#;
+
+        let column: CusipColumn = serde_json::from_str(json).unwrap();
+        assert_eq!(column.len(), 3);
+        assert_eq!(serde_json::to_string(&column).unwrap(), json);
+
+        let index: IssuerIndex = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            serde_json::to_string(&index).unwrap(),
+            r#"["037833100","594918104"]"#
+        );
+
+        let set: CusipArraySet<2> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            serde_json::to_string(&set).unwrap(),
+            r#"["037833100","594918104"]"#
+        );
+        let err = serde_json::from_str::<CusipArraySet<1>>(json).unwrap_err();
+        assert!(err.to_string().contains("full set"), "{err}");
+    }
+}
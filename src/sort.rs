@@ -0,0 +1,352 @@
+#![warn(missing_docs)]
+//! # cusip::sort
+//!
+//! Sorting and de-duplicating more CUSIPs than fit in memory.
+//!
+//! An `ExternalSorter` collects CUSIPs (packed into 8 bytes each, see `CUSIP::pack()`) in memory
+//! until it holds `chunk_size` of them, then sorts and de-duplicates that chunk and spills it to a
+//! temporary file as a "run". `finish()` merges the runs, yielding every distinct CUSIP once in
+//! ascending order. Memory use is bounded by the chunk size plus one buffered reader per run, and
+//! when everything fits in one chunk no files are written at all. The temporary files are removed
+//! when the sorter or the iterator returned by `finish()` is dropped.
+//!
+//! ```
+//! use cusip::sort::ExternalSorter;
+//! use cusip::CUSIP;
+//!
+//! let mut sorter = ExternalSorter::new().with_chunk_size(2);
+//! for s in ["S08000AA9", "037833100", "09739D100", "037833100"] {
+//!     sorter.push(CUSIP::parse(s).unwrap()).unwrap();
+//! }
+//! let sorted: Vec<String> = sorter
+//!     .finish()
+//!     .unwrap()
+//!     .map(|cusip| cusip.unwrap().to_string())
+//!     .collect();
+//! assert_eq!(sorted, ["037833100", "09739D100", "S08000AA9"]);
+//! ```
+//!
+//! `merge_sorted()` merges inputs that are already in ascending order, such as sorted files,
+//! streaming: it holds only the next CUSIP of each input, however long the inputs are.
+//!
+//! ```
+//! use cusip::sort::merge_sorted;
+//! use cusip::CUSIP;
+//!
+//! let parse = |values: &[&str]| -> Vec<CUSIP> {
+//!     values.iter().map(|s| CUSIP::parse(s).unwrap()).collect()
+//! };
+//! let americas = parse(&["037833100", "594918104"]);
+//! let europe = parse(&["037833100", "G1151C101"]);
+//! let merged: Vec<String> = merge_sorted([americas, europe])
+//!     .map(|cusip| cusip.to_string())
+//!     .collect();
+//! assert_eq!(merged, ["037833100", "594918104", "G1151C101"]);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::vec;
+
+use crate::CUSIP;
+
+/// The number of CUSIPs held in memory before spilling a run, by default (64 MiB of packed
+/// values).
+pub const DEFAULT_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Distinguishes the run files of sorters in the same process.
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file holding one sorted run, removed when dropped.
+struct Run {
+    path: PathBuf,
+    file: File,
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Sorts and de-duplicates CUSIPs, spilling to temporary files as needed.
+pub struct ExternalSorter {
+    chunk_size: usize,
+    dir: PathBuf,
+    buffer: Vec<u64>,
+    runs: Vec<Run>,
+}
+
+impl Default for ExternalSorter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExternalSorter {
+    /// Constructs a sorter with the default chunk size, spilling to the system temporary
+    /// directory.
+    pub fn new() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            dir: std::env::temp_dir(),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Returns the sorter with a different chunk size (at least 1).
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Returns the sorter spilling runs to a different directory.
+    pub fn with_temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Adds a CUSIP, spilling a run if the chunk is full.
+    pub fn push(&mut self, cusip: CUSIP) -> io::Result<()> {
+        self.buffer.push(cusip.pack());
+        if self.buffer.len() >= self.chunk_size {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of runs spilled to temporary files so far.
+    pub fn runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    fn sort_buffer(&mut self) {
+        self.buffer.sort_unstable();
+        self.buffer.dedup();
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort_buffer();
+        let path = self.dir.join(format!(
+            "cusip-sort-{}-{}.run",
+            process::id(),
+            NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // Own the file from here on, so it is removed even if writing fails
+        let mut run = Run { path, file };
+        let mut writer = BufWriter::new(&run.file);
+        for packed in self.buffer.drain(..) {
+            writer.write_all(&packed.to_le_bytes())?;
+        }
+        writer.flush()?;
+        drop(writer);
+        run.file.seek(SeekFrom::Start(0))?;
+        self.runs.push(run);
+        Ok(())
+    }
+
+    /// Finishes adding CUSIPs, returning an iterator over the distinct CUSIPs in ascending order.
+    /// Reading a run back can fail, so each item is a `Result`.
+    pub fn finish(mut self) -> io::Result<SortedCusips> {
+        if self.runs.is_empty() {
+            self.sort_buffer();
+            return Ok(SortedCusips {
+                source: Source::Memory(std::mem::take(&mut self.buffer).into_iter()),
+                last: None,
+            });
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+
+        let runs = std::mem::take(&mut self.runs);
+        let mut readers: Vec<BufReader<File>> = Vec::with_capacity(runs.len());
+        for run in &runs {
+            readers.push(BufReader::new(run.file.try_clone()?));
+        }
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(packed) = read_packed(reader)? {
+                heap.push(Reverse((packed, i)));
+            }
+        }
+        Ok(SortedCusips {
+            source: Source::Merge {
+                readers,
+                heap,
+                _runs: runs,
+            },
+            last: None,
+        })
+    }
+}
+
+/// Reads the next packed value from a run, or `None` at the end.
+fn read_packed(reader: &mut BufReader<File>) -> io::Result<Option<u64>> {
+    let mut bytes = [0_u8; 8];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(Some(u64::from_le_bytes(bytes))),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+enum Source {
+    Memory(vec::IntoIter<u64>),
+    Merge {
+        readers: Vec<BufReader<File>>,
+        heap: BinaryHeap<Reverse<(u64, usize)>>,
+        _runs: Vec<Run>,
+    },
+}
+
+/// The distinct CUSIPs from an `ExternalSorter`, in ascending order.
+pub struct SortedCusips {
+    source: Source,
+    last: Option<u64>,
+}
+
+impl SortedCusips {
+    fn next_packed(&mut self) -> io::Result<Option<u64>> {
+        match &mut self.source {
+            Source::Memory(values) => Ok(values.next()),
+            Source::Merge { readers, heap, .. } => {
+                let Some(Reverse((packed, i))) = heap.pop() else {
+                    return Ok(None);
+                };
+                if let Some(next) = read_packed(&mut readers[i])? {
+                    heap.push(Reverse((next, i)));
+                }
+                Ok(Some(packed))
+            }
+        }
+    }
+}
+
+impl Iterator for SortedCusips {
+    type Item = io::Result<CUSIP>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_packed() {
+                Ok(Some(packed)) if self.last == Some(packed) => continue,
+                Ok(Some(packed)) => {
+                    self.last = Some(packed);
+                    return CUSIP::unpack(packed).map(Ok).or_else(|| {
+                        Some(Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "sort run holds an out-of-range value",
+                        )))
+                    });
+                }
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Merges inputs that are each in ascending order, returning an iterator over every distinct CUSIP
+/// in them once, in ascending order. If an input is not in ascending order, neither is the output.
+pub fn merge_sorted<I: IntoIterator<Item = CUSIP>>(
+    inputs: impl IntoIterator<Item = I>,
+) -> MergeSorted<I::IntoIter> {
+    let mut inputs: Vec<I::IntoIter> = inputs.into_iter().map(IntoIterator::into_iter).collect();
+    let mut heap = BinaryHeap::with_capacity(inputs.len());
+    for (i, input) in inputs.iter_mut().enumerate() {
+        if let Some(cusip) = input.next() {
+            heap.push(Reverse((cusip, i)));
+        }
+    }
+    MergeSorted {
+        inputs,
+        heap,
+        last: None,
+    }
+}
+
+/// The distinct CUSIPs from inputs in ascending order, merged, returned by `merge_sorted()`.
+pub struct MergeSorted<I> {
+    inputs: Vec<I>,
+    heap: BinaryHeap<Reverse<(CUSIP, usize)>>,
+    last: Option<CUSIP>,
+}
+
+impl<I: Iterator<Item = CUSIP>> Iterator for MergeSorted<I> {
+    type Item = CUSIP;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Reverse((cusip, i)) = self.heap.pop()?;
+            if let Some(next) = self.inputs[i].next() {
+                self.heap.push(Reverse((next, i)));
+            }
+            if self.last != Some(cusip) {
+                self.last = Some(cusip);
+                return Some(cusip);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_runs_and_removes_them() {
+        let cusips: Vec<CUSIP> = (0..100u64)
+            .map(|i| CUSIP::unpack((i * 37) % 50).unwrap())
+            .collect();
+        let mut sorter = ExternalSorter::new().with_chunk_size(7);
+        for cusip in &cusips {
+            sorter.push(*cusip).unwrap();
+        }
+        assert_eq!(sorter.runs(), 14);
+        let paths: Vec<PathBuf> = sorter.runs.iter().map(|run| run.path.clone()).collect();
+        assert!(paths.iter().all(|path| path.exists()));
+
+        let sorted: Vec<CUSIP> = sorter.finish().unwrap().map(Result::unwrap).collect();
+        let mut expected = cusips;
+        expected.sort();
+        expected.dedup();
+        assert_eq!(sorted, expected);
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+
+    #[test]
+    fn merges_sorted_inputs() {
+        let input = |values: &[u64]| -> Vec<CUSIP> {
+            values.iter().map(|&i| CUSIP::unpack(i).unwrap()).collect()
+        };
+        let merged: Vec<CUSIP> = merge_sorted([
+            input(&[1, 4, 4, 9]),
+            input(&[]),
+            input(&[2, 4, 10]),
+            input(&[0]),
+        ])
+        .collect();
+        assert_eq!(merged, input(&[0, 1, 2, 4, 9, 10]));
+        assert_eq!(merge_sorted(Vec::<Vec<CUSIP>>::new()).count(), 0);
+    }
+
+    #[test]
+    fn in_memory_when_small() {
+        let mut sorter = ExternalSorter::new();
+        sorter.push(CUSIP::parse("037833100").unwrap()).unwrap();
+        assert_eq!(sorter.runs(), 0);
+        assert_eq!(sorter.finish().unwrap().count(), 1);
+        assert_eq!(ExternalSorter::new().finish().unwrap().count(), 0);
+    }
+}
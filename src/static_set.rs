@@ -0,0 +1,221 @@
+#![warn(missing_docs)]
+//! # cusip::static_set
+//!
+//! Hard-coded sets of CUSIPs that are validated and hashed at compile time.
+//!
+//! The `cusip_set!` macro takes CUSIP string literals and produces a `StaticCusipSet`. Every
+//! literal is validated (including its _Check Digit_) during compilation, so a typo is a build
+//! error rather than a set that silently never matches. The set is an open-addressing hash table
+//! built at compile time, so membership tests take constant time and nothing is allocated, which
+//! suits small allow and deny lists in latency-sensitive code.
+//!
+//! ```
+//! use cusip::static_set::StaticCusipSet;
+//! use cusip::{cusip_set, CUSIP};
+//!
+//! static DENY: StaticCusipSet = cusip_set!["037833100", "594918104"];
+//!
+//! assert!(DENY.contains(&CUSIP::parse("037833100").unwrap()));
+//! assert!(!DENY.contains(&CUSIP::parse("09739D100").unwrap()));
+//! assert_eq!(DENY.len(), 2);
+//! ```
+//!
+//! An invalid literal fails to compile:
+//!
+//! ```compile_fail
+//! use cusip::static_set::StaticCusipSet;
+//!
+//! static DENY: StaticCusipSet = cusip::cusip_set!["037833101"]; // Wrong Check Digit
+//! ```
+
+use crate::CUSIP;
+
+/// Parses a CUSIP in a `const` context, such as the initializer of a `const` or `static`.
+///
+/// # Panics
+///
+/// If the string is not a valid CUSIP. When evaluated at compile time this is a build error.
+///
+/// ```
+/// use cusip::static_set::parse_const;
+/// use cusip::CUSIP;
+///
+/// const APPLE: CUSIP = parse_const("037833100");
+/// assert_eq!(APPLE.to_string(), "037833100");
+/// ```
+pub const fn parse_const(value: &str) -> CUSIP {
+    let b = value.as_bytes();
+    if b.len() != 9 {
+        panic!("CUSIP literal must be 9 characters long");
+    }
+
+    let mut sum = 0;
+    let mut i = 0;
+    while i < 8 {
+        let mut v = match b[i] {
+            c @ b'0'..=b'9' => c - b'0',
+            c @ b'A'..=b'Z' => c - b'A' + 10,
+            _ => panic!("CUSIP literal must have uppercase ASCII alphanumeric Payload"),
+        };
+        if i % 2 == 1 {
+            v *= 2;
+        }
+        sum += (v / 10 + v % 10) as u32;
+        i += 1;
+    }
+    if b[8] != b'0' + ((10 - sum % 10) % 10) as u8 {
+        panic!("CUSIP literal has an incorrect Check Digit");
+    }
+
+    CUSIP([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8]])
+}
+
+/// FNV-1a over the bytes of the CUSIP. It only needs to spread the keys of one small table.
+const fn hash(cusip: &CUSIP) -> usize {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut i = 0;
+    while i < 9 {
+        h ^= cusip.0[i] as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    h as usize
+}
+
+const fn same(a: &CUSIP, b: &CUSIP) -> bool {
+    let mut i = 0;
+    while i < 9 {
+        if a.0[i] != b.0[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// An immutable set of CUSIPs built at compile time by `cusip_set!`.
+#[derive(Clone, Copy, Debug)]
+pub struct StaticCusipSet {
+    slots: &'static [Option<CUSIP>],
+    len: usize,
+}
+
+impl StaticCusipSet {
+    /// Not public API. Wraps a table built by `build_table()`.
+    #[doc(hidden)]
+    pub const fn from_table(slots: &'static [Option<CUSIP>], len: usize) -> Self {
+        StaticCusipSet { slots, len }
+    }
+
+    /// Returns true if the set contains the CUSIP.
+    pub fn contains(&self, cusip: &CUSIP) -> bool {
+        let mask = self.slots.len() - 1;
+        let mut i = hash(cusip) & mask;
+        while let Some(candidate) = &self.slots[i] {
+            if candidate == cusip {
+                return true;
+            }
+            i = (i + 1) & mask;
+        }
+        false
+    }
+
+    /// Returns the number of CUSIPs in the set.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the set holds no CUSIPs.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the CUSIPs in the set, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &'static CUSIP> {
+        self.slots.iter().flatten()
+    }
+}
+
+/// Not public API. The table size `cusip_set!` uses for `n` CUSIPs: a power of two, at least twice
+/// `n`, so probe sequences stay short and always reach an empty slot.
+#[doc(hidden)]
+pub const fn table_size(n: usize) -> usize {
+    (2 * n).next_power_of_two()
+}
+
+/// Not public API. Builds the hash table for `cusip_set!`, returning it with the number of
+/// distinct CUSIPs it holds.
+#[doc(hidden)]
+pub const fn build_table<const M: usize>(cusips: &[CUSIP]) -> ([Option<CUSIP>; M], usize) {
+    let mut slots = [None; M];
+    let mut len = 0;
+    let mut k = 0;
+    while k < cusips.len() {
+        let mut i = hash(&cusips[k]) & (M - 1);
+        loop {
+            match &slots[i] {
+                Some(existing) if same(existing, &cusips[k]) => break,
+                Some(_) => i = (i + 1) & (M - 1),
+                None => {
+                    slots[i] = Some(cusips[k]);
+                    len += 1;
+                    break;
+                }
+            }
+        }
+        k += 1;
+    }
+    (slots, len)
+}
+
+/// Builds a `StaticCusipSet` from CUSIP string literals, validating each one at compile time.
+/// Repeated literals are only stored once. See the `static_set` module for an example.
+#[macro_export]
+macro_rules! cusip_set {
+    ($($cusip:literal),* $(,)?) => {{
+        const CUSIPS: &[$crate::CUSIP] = &[$($crate::static_set::parse_const($cusip)),*];
+        const SIZE: usize = $crate::static_set::table_size(CUSIPS.len());
+        const TABLE: ([::core::option::Option<$crate::CUSIP>; SIZE], usize) =
+            $crate::static_set::build_table::<SIZE>(CUSIPS);
+        $crate::static_set::StaticCusipSet::from_table(&TABLE.0, TABLE.1)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_const_agrees_with_parse() {
+        for s in ["037833100", "09739D100", "S08000AA9", "G0052B105"] {
+            assert_eq!(parse_const(s), CUSIP::parse(s).unwrap());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "incorrect Check Digit")]
+    fn parse_const_rejects_check_digit() {
+        parse_const("037833101");
+    }
+
+    #[test]
+    fn membership() {
+        let set = cusip_set![
+            "037833100",
+            "594918104",
+            "09739D100",
+            "S08000AA9",
+            "037833100",
+        ];
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter().count(), 4);
+        for s in ["037833100", "594918104", "09739D100", "S08000AA9"] {
+            assert!(set.contains(&CUSIP::parse(s).unwrap()));
+        }
+        assert!(!set.contains(&CUSIP::parse("G0052B105").unwrap()));
+
+        let empty = cusip_set![];
+        assert!(empty.is_empty());
+        assert!(!empty.contains(&CUSIP::parse("037833100").unwrap()));
+    }
+}
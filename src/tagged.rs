@@ -0,0 +1,137 @@
+#![warn(missing_docs)]
+//! # cusip::tagged
+//!
+//! A `CUSIP` carrying metadata about where it came from.
+//!
+//! `TaggedCusip<M>` pairs a CUSIP with any metadata type `M`. The `Provenance` type covers the
+//! common case of tracking the source feed, whether the identifier was licensed or generated
+//! internally, and when it was received, so audits can trace where each identifier entered the
+//! system. With the `serde` feature, both can be serialized and deserialized (the CUSIP as its
+//! string form).
+//!
+//! ```
+//! use std::time::SystemTime;
+//!
+//! use cusip::tagged::{Licensing, Provenance, TaggedCusip};
+//! use cusip::CUSIP;
+//!
+//! let tagged = TaggedCusip::new(
+//!     CUSIP::parse("037833100").unwrap(),
+//!     Provenance {
+//!         source: "vendor-feed".to_string(),
+//!         licensing: Licensing::Licensed,
+//!         received_at: Some(SystemTime::now()),
+//!     },
+//! );
+//!
+//! assert_eq!(tagged.issuer_num(), "037833");
+//! assert_eq!(tagged.meta.source, "vendor-feed");
+//! ```
+
+use std::ops::Deref;
+use std::time::SystemTime;
+
+#[cfg(feature = "serde")]
+use ::serde::{Deserialize, Serialize};
+
+use crate::CUSIP;
+
+/// A `CUSIP` along with metadata of type `M`. It dereferences to the `CUSIP`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TaggedCusip<M> {
+    /// The CUSIP.
+    pub cusip: CUSIP,
+    /// The metadata.
+    pub meta: M,
+}
+
+impl<M> TaggedCusip<M> {
+    /// Tags the CUSIP with the metadata.
+    pub fn new(cusip: CUSIP, meta: M) -> Self {
+        TaggedCusip { cusip, meta }
+    }
+
+    /// Separates the CUSIP from its metadata.
+    pub fn into_parts(self) -> (CUSIP, M) {
+        (self.cusip, self.meta)
+    }
+
+    /// Replaces the metadata with the result of applying `f` to it.
+    pub fn map_meta<N, F: FnOnce(M) -> N>(self, f: F) -> TaggedCusip<N> {
+        TaggedCusip {
+            cusip: self.cusip,
+            meta: f(self.meta),
+        }
+    }
+}
+
+impl<M> Deref for TaggedCusip<M> {
+    type Target = CUSIP;
+
+    fn deref(&self) -> &CUSIP {
+        &self.cusip
+    }
+}
+
+impl<M> AsRef<CUSIP> for TaggedCusip<M> {
+    fn as_ref(&self) -> &CUSIP {
+        &self.cusip
+    }
+}
+
+/// Whether an identifier came from a licensed source or was generated internally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Licensing {
+    /// Received from a source under a CUSIP license.
+    Licensed,
+    /// Generated internally, for example a private use identifier.
+    Internal,
+}
+
+/// Where and when an identifier entered the system.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Provenance {
+    /// The name of the feed or system the identifier came from.
+    pub source: String,
+    /// Whether the identifier is licensed or internally generated.
+    pub licensing: Licensing,
+    /// When the identifier was received, if known.
+    pub received_at: Option<SystemTime>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deref_and_map() {
+        let tagged = TaggedCusip::new(CUSIP::parse("S08000AA9").unwrap(), 7u32);
+        assert!(tagged.is_cins());
+        let tagged = tagged.map_meta(|n| n.to_string());
+        assert_eq!(tagged.into_parts().1, "7");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let tagged = TaggedCusip::new(
+            CUSIP::parse("037833100").unwrap(),
+            Provenance {
+                source: "internal".to_string(),
+                licensing: Licensing::Internal,
+                received_at: None,
+            },
+        );
+        let json = serde_json::to_string(&tagged).unwrap();
+        assert_eq!(
+            json,
+            r#"{"cusip":"037833100","meta":{"source":"internal","licensing":"internal","received_at":null}}"#
+        );
+        let back: TaggedCusip<Provenance> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, tagged);
+    }
+}
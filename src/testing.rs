@@ -0,0 +1,113 @@
+#![warn(missing_docs)]
+//! # cusip::testing
+//!
+//! Known-valid CUSIPs for use in tests (requires the `testing` feature, typically enabled only in
+//! `[dev-dependencies]`).
+//!
+//! The fixtures are grouped by the kind of identifier, so downstream tests can use realistic
+//! values without copying lists between crates. Every fixture is validated at compile time. The
+//! large-cap equities are real CUSIPs. The others are well-formed values in the style of each kind
+//! of identifier, not necessarily assigned to any security.
+//!
+//! ```
+//! use cusip::testing::{CINS_BY_COUNTRY, LARGE_CAP_EQUITIES, PRIVATE_USE};
+//!
+//! assert!(LARGE_CAP_EQUITIES.iter().all(|c| !c.is_cins()));
+//! assert_eq!(CINS_BY_COUNTRY.len(), 26);
+//! assert!(PRIVATE_USE.iter().all(|c| c.is_private_use()));
+//! ```
+
+use crate::static_set::parse_const;
+use crate::CUSIP;
+
+/// Common stock of ten large-cap US companies.
+pub const LARGE_CAP_EQUITIES: &[CUSIP] = &[
+    parse_const("037833100"), // Apple Inc.
+    parse_const("594918104"), // Microsoft Corp.
+    parse_const("023135106"), // Amazon.com Inc.
+    parse_const("02079K305"), // Alphabet Inc. Class A
+    parse_const("30303M102"), // Meta Platforms Inc.
+    parse_const("67066G104"), // NVIDIA Corp.
+    parse_const("084670702"), // Berkshire Hathaway Inc. Class B
+    parse_const("46625H100"), // JPMorgan Chase & Co.
+    parse_const("30231G102"), // Exxon Mobil Corp.
+    parse_const("478160104"), // Johnson & Johnson
+];
+
+/// One CINS identifier for each country code, 'A' through 'Z' in order.
+pub const CINS_BY_COUNTRY: &[CUSIP] = &[
+    parse_const("A12345AB9"),
+    parse_const("B12345AB8"),
+    parse_const("C12345AB7"),
+    parse_const("D12345AB6"),
+    parse_const("E12345AB5"),
+    parse_const("F12345AB4"),
+    parse_const("G12345AB3"),
+    parse_const("H12345AB2"),
+    parse_const("I12345AB1"),
+    parse_const("J12345AB0"),
+    parse_const("K12345AB8"),
+    parse_const("L12345AB7"),
+    parse_const("M12345AB6"),
+    parse_const("N12345AB5"),
+    parse_const("O12345AB4"),
+    parse_const("P12345AB3"),
+    parse_const("Q12345AB2"),
+    parse_const("R12345AB1"),
+    parse_const("S12345AB0"),
+    parse_const("T12345AB9"),
+    parse_const("U12345AB7"),
+    parse_const("V12345AB6"),
+    parse_const("W12345AB5"),
+    parse_const("X12345AB4"),
+    parse_const("Y12345AB3"),
+    parse_const("Z12345AB2"),
+];
+
+/// CUSIPs reserved for private use, covering each private-use range of _Issuer Number_ and
+/// _Issue Number_.
+pub const PRIVATE_USE: &[CUSIP] = &[
+    parse_const("03783T996"), // Issue Number 90 to 99
+    parse_const("03783T9A3"), // Issue Number 9A to 9Y
+    parse_const("12399A101"), // Issuer Number ???99?
+    parse_const("99000A109"), // Issuer Number 99000? to 99999?
+    parse_const("99999Z107"),
+];
+
+/// CUSIPs in the style of fixed-income issues, whose _Issue Numbers_ use letters.
+pub const FIXED_INCOME: &[CUSIP] = &[
+    parse_const("912828ZT0"), // US Treasury note
+    parse_const("912810SE9"), // US Treasury bond
+    parse_const("037833AK6"), // Corporate bond
+    parse_const("594918BC7"), // Corporate bond
+    parse_const("13063DGA0"), // Municipal bond
+    parse_const("64971XQM3"), // Municipal bond
+];
+
+/// Returns all the fixtures, group by group.
+pub fn all() -> impl Iterator<Item = &'static CUSIP> {
+    LARGE_CAP_EQUITIES
+        .iter()
+        .chain(CINS_BY_COUNTRY)
+        .chain(PRIVATE_USE)
+        .chain(FIXED_INCOME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_are_what_they_claim() {
+        assert!(LARGE_CAP_EQUITIES
+            .iter()
+            .all(|c| !c.is_cins() && !c.is_private_use()));
+        for (cusip, country) in CINS_BY_COUNTRY.iter().zip('A'..='Z') {
+            assert_eq!(cusip.as_cins().unwrap().country_code(), country);
+        }
+        assert!(FIXED_INCOME
+            .iter()
+            .all(|c| c.issue_num().bytes().any(|b| b.is_ascii_uppercase())));
+        assert_eq!(all().count(), 47);
+    }
+}
@@ -0,0 +1,117 @@
+#![warn(missing_docs)]
+//! # cusip::tokio
+//!
+//! Async adapters for parsing CUSIPs in [tokio](https://tokio.rs/) pipelines (requires the
+//! `tokio` feature).
+//!
+//! * `CusipStreamExt` adds `parse_cusips()` and `parse_cusips_loose()` combinators to any
+//!   `Stream` of strings.
+//! * `parse_lines()` and `parse_lines_loose()` read an `AsyncBufRead` line by line, yielding a
+//!   parse result for each line.
+//!
+//! ```
+//! use cusip::tokio::{parse_lines, CusipStreamExt};
+//! use tokio_stream::StreamExt;
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let values = tokio_stream::iter(vec!["037833100".to_string(), "037833101".to_string()]);
+//! let results: Vec<_> = values.parse_cusips().collect().await;
+//! assert!(results[0].is_ok());
+//! assert!(results[1].is_err());
+//!
+//! let input: &[u8] = b"037833100\n09739D100\n";
+//! let results: Vec<_> = parse_lines(input).collect().await;
+//! assert!(results.iter().all(|r| matches!(r, Ok(Ok(_)))));
+//! # });
+//! ```
+
+use std::io;
+
+use ::tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio_stream::wrappers::LinesStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{CUSIPError, CUSIP};
+
+/// Combinators for parsing a `Stream` of strings into CUSIPs.
+pub trait CusipStreamExt: Stream + Sized
+where
+    Self::Item: AsRef<str>,
+{
+    /// Parses each item with `CUSIP::parse`.
+    fn parse_cusips(self) -> impl Stream<Item = Result<CUSIP, CUSIPError>> {
+        self.map(|s| CUSIP::parse(s.as_ref()))
+    }
+
+    /// Parses each item with `CUSIP::parse_loose`.
+    fn parse_cusips_loose(self) -> impl Stream<Item = Result<CUSIP, CUSIPError>> {
+        self.map(|s| CUSIP::parse_loose(s.as_ref()))
+    }
+}
+
+impl<S> CusipStreamExt for S
+where
+    S: Stream,
+    S::Item: AsRef<str>,
+{
+}
+
+/// Reads the input line by line, parsing each line with `CUSIP::parse`. The outer `Result`
+/// reports errors reading the input, and the inner one reports errors parsing a line.
+pub fn parse_lines<R: AsyncBufRead>(
+    reader: R,
+) -> impl Stream<Item = io::Result<Result<CUSIP, CUSIPError>>> {
+    LinesStream::new(reader.lines()).map(|line| line.map(|line| CUSIP::parse(&line)))
+}
+
+/// Reads the input line by line, parsing each line with `CUSIP::parse_loose`. The outer `Result`
+/// reports errors reading the input, and the inner one reports errors parsing a line.
+pub fn parse_lines_loose<R: AsyncBufRead>(
+    reader: R,
+) -> impl Stream<Item = io::Result<Result<CUSIP, CUSIPError>>> {
+    LinesStream::new(reader.lines()).map(|line| line.map(|line| CUSIP::parse_loose(&line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn parse_stream() {
+        let values = tokio_stream::iter(["09739D100", "09739d100", "09739D101"]);
+        let results: Vec<_> = values.parse_cusips().collect().await;
+        assert_eq!(results[0], Ok(CUSIP::parse("09739D100").unwrap()));
+        assert!(matches!(
+            results[1],
+            Err(CUSIPError::InvalidIssuerNum { .. })
+        ));
+        assert!(matches!(
+            results[2],
+            Err(CUSIPError::IncorrectCheckDigit { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn parse_stream_loose() {
+        let values = tokio_stream::iter(vec![" 09739d100".to_string()]);
+        let results: Vec<_> = values.parse_cusips_loose().collect().await;
+        assert_eq!(results, [Ok(CUSIP::parse("09739D100").unwrap())]);
+    }
+
+    #[tokio::test]
+    async fn parse_lines_strict_and_loose() {
+        let input: &[u8] = b"09739D100\r\n09739d100\n";
+
+        let strict: Vec<_> = parse_lines(input)
+            .map(|r| r.unwrap().is_ok())
+            .collect()
+            .await;
+        assert_eq!(strict, [true, false]);
+
+        let loose: Vec<_> = parse_lines_loose(input)
+            .map(|r| r.unwrap().is_ok())
+            .collect()
+            .await;
+        assert_eq!(loose, [true, true]);
+    }
+}
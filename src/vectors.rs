@@ -0,0 +1,146 @@
+#![warn(missing_docs)]
+//! # cusip::vectors
+//!
+//! Conformance test vectors, for checking other implementations of CUSIP validation against this
+//! crate.
+//!
+//! `test_vectors()` returns a fixed list of inputs covering valid CUSIPs (including boundary cases
+//! for the _Check Digit_ computation) and every kind of error `CUSIP::parse()` can report, each
+//! with the outcome this crate produces. `render_json()` renders them for teams maintaining
+//! implementations in other languages. The `test_vectors` example writes that JSON to standard
+//! output:
+//!
+//! ```text
+//! cargo run --example test_vectors > cusip-test-vectors.json
+//! ```
+//!
+//! The JSON is an object with a `vectors` array. Each vector has the `input` string, a
+//! `description`, and `valid`. Valid vectors also have the `payload` and its `check_digit`.
+//! Invalid vectors have the `error` kind (such as `"IncorrectCheckDigit"`) and the `message`.
+//!
+//! ```
+//! use cusip::vectors::{render_json, test_vectors};
+//!
+//! let vectors = test_vectors();
+//! assert!(vectors.iter().any(|v| v.expected.is_ok()));
+//! assert!(render_json(&vectors).starts_with(r#"{"vectors":[{"input":"#));
+//! ```
+
+use std::fmt::Write;
+
+use crate::report::json_string;
+use crate::{CUSIPError, CUSIP};
+
+/// One input and the outcome of parsing it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    /// The input string.
+    pub input: &'static str,
+    /// What the vector exercises.
+    pub description: &'static str,
+    /// The outcome of `CUSIP::parse()` on the input.
+    pub expected: Result<CUSIP, CUSIPError>,
+}
+
+const INPUTS: &[(&str, &str)] = &[
+    ("037833100", "valid: Apple Inc. common stock"),
+    ("09739D100", "valid: letter in the Issuer Number"),
+    ("912828ZT0", "valid: letters in the Issue Number"),
+    ("S08000AA9", "valid: CINS identifier"),
+    (
+        "I08000AA0",
+        "valid: CINS identifier with an extended country code",
+    ),
+    ("03783T996", "valid: private use Issue Number"),
+    ("000000000", "valid: smallest Payload"),
+    ("ZZZZZZZZ0", "valid: largest Payload"),
+    ("999999998", "valid: largest all-digit Payload"),
+    (
+        "Y00000003",
+        "valid: largest character value in an undoubled position",
+    ),
+    (
+        "0000000Y6",
+        "valid: largest character value in a doubled position",
+    ),
+    ("", "InvalidCUSIPLength: empty"),
+    ("03783310", "InvalidCUSIPLength: Check Digit missing"),
+    ("0378331000", "InvalidCUSIPLength: one character too many"),
+    ("03783310é", "InvalidCUSIPLength: 9 characters but 10 bytes"),
+    (" 03783310", "InvalidIssuerNum: leading whitespace"),
+    ("09739d100", "InvalidIssuerNum: lowercase letter"),
+    ("0378-3100", "InvalidIssuerNum: punctuation"),
+    ("037833a00", "InvalidIssueNum: lowercase letter"),
+    ("037833*00", "InvalidIssueNum: punctuation"),
+    ("03783310A", "InvalidCheckDigit: letter"),
+    ("03783310 ", "InvalidCheckDigit: trailing whitespace"),
+    ("037833101", "IncorrectCheckDigit: off by one"),
+    ("S08000AA0", "IncorrectCheckDigit: CINS identifier"),
+];
+
+/// Returns the conformance test vectors, with the outcomes this crate produces.
+pub fn test_vectors() -> Vec<TestVector> {
+    INPUTS
+        .iter()
+        .map(|&(input, description)| TestVector {
+            input,
+            description,
+            expected: CUSIP::parse(input),
+        })
+        .collect()
+}
+
+/// Renders the test vectors as JSON, one vector per line.
+pub fn render_json(vectors: &[TestVector]) -> String {
+    let lines: Vec<String> = vectors
+        .iter()
+        .map(|v| {
+            let mut line = format!(
+                r#"{{"input":{},"description":{}"#,
+                json_string(v.input),
+                json_string(v.description)
+            );
+            let _ = match &v.expected {
+                Ok(cusip) => write!(
+                    line,
+                    r#","valid":true,"payload":"{}","check_digit":"{}"}}"#,
+                    cusip.payload(),
+                    cusip.check_digit()
+                ),
+                Err(error) => write!(
+                    line,
+                    r#","valid":false,"error":"{}","message":{}}}"#,
+                    error.kind_name(),
+                    json_string(&error.to_string())
+                ),
+            };
+            line
+        })
+        .collect();
+    format!("{{\"vectors\":[{}\n]}}\n", lines.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptions_match_outcomes() {
+        for v in test_vectors() {
+            let kind = v
+                .expected
+                .as_ref()
+                .map_or_else(|e| e.kind_name(), |_| "valid");
+            assert!(v.description.starts_with(kind), "{:?} gave {kind}", v.input);
+        }
+    }
+
+    #[test]
+    fn render_vectors() {
+        let vectors = &test_vectors()[..1];
+        assert_eq!(
+            render_json(vectors),
+            "{\"vectors\":[{\"input\":\"037833100\",\"description\":\"valid: Apple Inc. common stock\",\"valid\":true,\"payload\":\"03783310\",\"check_digit\":\"0\"}\n]}\n"
+        );
+    }
+}
@@ -0,0 +1,356 @@
+#![warn(missing_docs)]
+//! # cusip::xref
+//!
+//! A symbology cross-reference table mapping between CUSIPs, tickers, ISINs and descriptions.
+//!
+//! `CrossReference` loads a CSV file of securities, validating every identifier as it goes, and
+//! then looks securities up by any of their identifiers. It also implements
+//! `resolver::IssuerResolver`, so it can be used wherever a resolver is expected.
+//!
+//! ```
+//! use cusip::xref::CrossReference;
+//! use cusip::CUSIP;
+//!
+//! let csv = "\
+//! cusip,ticker,isin,description
+//! 037833100,AAPL,US0378331005,Apple Inc.
+//! 594918104,MSFT,US5949181045,Microsoft Corp.
+//! ";
+//! let xref = CrossReference::from_csv(csv.as_bytes()).unwrap();
+//!
+//! let apple = CUSIP::parse("037833100").unwrap();
+//! assert_eq!(xref.by_cusip(&apple).unwrap().ticker.as_deref(), Some("AAPL"));
+//! assert_eq!(xref.by_ticker("MSFT").unwrap().cusip.to_string(), "594918104");
+//! assert_eq!(xref.by_isin("US0378331005").unwrap().description, "Apple Inc.");
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use crate::csv::split_record;
+use crate::isin::FromIsinError;
+use crate::payload::IssuerNum;
+use crate::resolver::{IssuerInfo, IssuerResolver};
+use crate::{CUSIPError, CUSIP};
+
+/// One security in a `CrossReference`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Security {
+    /// The CUSIP.
+    pub cusip: CUSIP,
+    /// The ticker symbol, if known.
+    pub ticker: Option<String>,
+    /// The ISIN, if known.
+    pub isin: Option<String>,
+    /// A description of the security, or the empty string.
+    pub description: String,
+}
+
+/// All the ways loading a cross-reference table could fail.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum XrefError {
+    /// The input could not be read.
+    Io(io::Error),
+    /// The header row does not have a column with this name.
+    MissingColumn(&'static str),
+    /// A row has a CUSIP that is not valid.
+    InvalidCusip {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// Why the CUSIP is invalid
+        error: CUSIPError,
+    },
+    /// A row has an ISIN that is not valid.
+    InvalidIsin {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// The ISIN we found
+        was: String,
+    },
+    /// A row has a CUSIP-based ISIN that does not embed the row's CUSIP.
+    IsinMismatch {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// The ISIN we found
+        isin: String,
+        /// The CUSIP of the row
+        cusip: CUSIP,
+    },
+    /// A row repeats a CUSIP, ticker or ISIN from an earlier row.
+    Duplicate {
+        /// The (one-based) line number of the row
+        line: usize,
+        /// The name of the column
+        column: &'static str,
+        /// The repeated value
+        value: String,
+    },
+}
+
+impl fmt::Display for XrefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XrefError::Io(err) => write!(f, "error reading cross-reference data: {err}"),
+            XrefError::MissingColumn(name) => write!(f, "no column named {name:?}"),
+            XrefError::InvalidCusip { line, error } => write!(f, "line {line}: {error}"),
+            XrefError::InvalidIsin { line, was } => {
+                write!(f, "line {line}: {was:?} is not a valid ISIN")
+            }
+            XrefError::IsinMismatch { line, isin, cusip } => {
+                write!(f, "line {line}: ISIN {isin:?} does not embed CUSIP {cusip}")
+            }
+            XrefError::Duplicate {
+                line,
+                column,
+                value,
+            } => write!(f, "line {line}: {column} {value:?} appears more than once"),
+        }
+    }
+}
+
+impl Error for XrefError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            XrefError::Io(err) => Some(err),
+            XrefError::InvalidCusip { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for XrefError {
+    fn from(err: io::Error) -> Self {
+        XrefError::Io(err)
+    }
+}
+
+/// A table of securities, indexed by CUSIP, ticker and ISIN.
+#[derive(Clone, Debug, Default)]
+pub struct CrossReference {
+    securities: Vec<Security>,
+    by_cusip: HashMap<CUSIP, usize>,
+    by_ticker: HashMap<String, usize>,
+    by_isin: HashMap<String, usize>,
+    by_issuer: HashMap<IssuerNum, usize>,
+}
+
+impl CrossReference {
+    /// Loads a table from comma-separated text with a header row. There must be a column named
+    /// `cusip`; columns named `ticker`, `isin` and `description` are optional, and others are
+    /// ignored. CUSIPs may be given loosely (as `CUSIP::parse_loose()` allows) and ISINs are
+    /// validated after trimming. A CUSIP-based ISIN (see `isin::ISIN::cusip()`) must embed the
+    /// CUSIP of its row. Empty tickers and ISINs count as unknown. Blank lines are
+    /// skipped. A CUSIP, ticker or ISIN appearing in more than one row is an error, so lookups in
+    /// every direction are unambiguous.
+    pub fn from_csv<R: BufRead>(reader: R) -> Result<Self, XrefError> {
+        let mut lines = reader.lines();
+
+        let header = match lines.next() {
+            Some(header) => split_record(&header?, ','),
+            None => return Ok(Self::default()),
+        };
+        let column = |name: &'static str| header.iter().position(|h| h.trim() == name);
+        let cusip_col = column("cusip").ok_or(XrefError::MissingColumn("cusip"))?;
+        let ticker_col = column("ticker");
+        let isin_col = column("isin");
+        let description_col = column("description");
+
+        let mut xref = Self::default();
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_num = i + 2;
+            let fields = split_record(&line, ',');
+            let field =
+                |col: Option<usize>| col.and_then(|col| fields.get(col)).map_or("", |f| f.trim());
+            let known = |value: &str| (!value.is_empty()).then(|| value.to_string());
+
+            let cusip = CUSIP::parse_loose(field(Some(cusip_col))).map_err(|error| {
+                XrefError::InvalidCusip {
+                    line: line_num,
+                    error,
+                }
+            })?;
+            let isin = known(field(isin_col));
+            if let Some(isin) = &isin {
+                match CUSIP::from_isin_str(isin) {
+                    Err(FromIsinError::InvalidIsin) => {
+                        return Err(XrefError::InvalidIsin {
+                            line: line_num,
+                            was: isin.clone(),
+                        });
+                    }
+                    Ok(embedded) if embedded == cusip => {}
+                    Ok(_) | Err(FromIsinError::InvalidCusip(_)) => {
+                        return Err(XrefError::IsinMismatch {
+                            line: line_num,
+                            isin: isin.clone(),
+                            cusip,
+                        });
+                    }
+                    // Not CUSIP-based, so there is nothing to compare
+                    Err(_) => {}
+                }
+            }
+
+            xref.insert(
+                Security {
+                    cusip,
+                    ticker: known(field(ticker_col)),
+                    isin,
+                    description: field(description_col).to_string(),
+                },
+                line_num,
+            )?;
+        }
+
+        Ok(xref)
+    }
+
+    fn insert(&mut self, security: Security, line: usize) -> Result<(), XrefError> {
+        let duplicate = |column, value: String| XrefError::Duplicate {
+            line,
+            column,
+            value,
+        };
+        if self.by_cusip.contains_key(&security.cusip) {
+            return Err(duplicate("cusip", security.cusip.to_string()));
+        }
+        if let Some(ticker) = security
+            .ticker
+            .as_ref()
+            .filter(|t| self.by_ticker.contains_key(*t))
+        {
+            return Err(duplicate("ticker", ticker.clone()));
+        }
+        if let Some(isin) = security
+            .isin
+            .as_ref()
+            .filter(|i| self.by_isin.contains_key(*i))
+        {
+            return Err(duplicate("isin", isin.clone()));
+        }
+
+        let index = self.securities.len();
+        self.by_cusip.insert(security.cusip, index);
+        if let Some(ticker) = &security.ticker {
+            self.by_ticker.insert(ticker.clone(), index);
+        }
+        if let Some(isin) = &security.isin {
+            self.by_isin.insert(isin.clone(), index);
+        }
+        self.by_issuer
+            .entry(security.cusip.issuer())
+            .or_insert(index);
+        self.securities.push(security);
+        Ok(())
+    }
+
+    /// Returns the number of securities in the table.
+    pub fn len(&self) -> usize {
+        self.securities.len()
+    }
+
+    /// Returns true if the table holds no securities.
+    pub fn is_empty(&self) -> bool {
+        self.securities.is_empty()
+    }
+
+    /// Returns the security with the CUSIP, if there is one.
+    pub fn by_cusip(&self, cusip: &CUSIP) -> Option<&Security> {
+        self.by_cusip.get(cusip).map(|&i| &self.securities[i])
+    }
+
+    /// Returns the security with the ticker, if there is one.
+    pub fn by_ticker(&self, ticker: &str) -> Option<&Security> {
+        self.by_ticker.get(ticker).map(|&i| &self.securities[i])
+    }
+
+    /// Returns the security with the ISIN, if there is one.
+    pub fn by_isin(&self, isin: &str) -> Option<&Security> {
+        self.by_isin.get(isin).map(|&i| &self.securities[i])
+    }
+
+    /// Returns the securities in the table, in the order loaded.
+    pub fn iter(&self) -> impl Iterator<Item = &Security> + '_ {
+        self.securities.iter()
+    }
+}
+
+/// Resolves an _Issuer_ from the first security in the table with that _Issuer Number_: its
+/// description becomes the name, and its ticker and ISIN (if known) become attributes.
+impl IssuerResolver for CrossReference {
+    fn resolve(&self, issuer_num: &IssuerNum) -> Option<IssuerInfo> {
+        let security = &self.securities[*self.by_issuer.get(issuer_num)?];
+        let attributes: BTreeMap<String, String> =
+            [("ticker", &security.ticker), ("isin", &security.isin)]
+                .into_iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.clone()?)))
+                .collect();
+        Some(IssuerInfo {
+            issuer_num: *issuer_num,
+            name: security.description.clone(),
+            attributes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::enrich;
+
+    #[test]
+    fn optional_columns_and_resolver() {
+        let csv = "ticker,cusip\nBCC, 09739d100\n,S08000AA9\n";
+        let xref = CrossReference::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(xref.len(), 2);
+        assert_eq!(xref.by_ticker("BCC").unwrap().isin, None);
+        assert_eq!(xref.by_ticker(""), None);
+
+        let enriched = enrich(&CUSIP::parse("09739D100").unwrap(), &xref);
+        let issuer = enriched.issuer.unwrap();
+        assert_eq!(issuer.attributes["ticker"], "BCC");
+        assert!(!issuer.attributes.contains_key("isin"));
+    }
+
+    #[test]
+    fn load_errors() {
+        let bad_isin = "cusip,isin\n037833100,US0378331006\n";
+        assert!(matches!(
+            CrossReference::from_csv(bad_isin.as_bytes()),
+            Err(XrefError::InvalidIsin { line: 2, .. })
+        ));
+
+        let mismatch = "cusip,isin\n037833100,US0378331005\n594918104,US0378331005\n";
+        match CrossReference::from_csv(mismatch.as_bytes()) {
+            Err(XrefError::IsinMismatch { line: 3, cusip, .. }) => {
+                assert_eq!(cusip.to_string(), "594918104")
+            }
+            other => panic!("Expected a mismatched ISIN on line 3, but got: {other:?}"),
+        }
+        // An ISIN that is not CUSIP-based has no CUSIP to compare
+        let foreign = "cusip,isin\n037833100,GB0002634946\n";
+        assert!(CrossReference::from_csv(foreign.as_bytes()).is_ok());
+
+        let duplicate = "cusip,ticker\n037833100,AAPL\n594918104,AAPL\n";
+        match CrossReference::from_csv(duplicate.as_bytes()) {
+            Err(XrefError::Duplicate {
+                line: 3,
+                column: "ticker",
+                ..
+            }) => {}
+            other => panic!("Expected a duplicate ticker on line 3, but got: {other:?}"),
+        }
+
+        assert!(matches!(
+            CrossReference::from_csv("ticker\nAAPL\n".as_bytes()),
+            Err(XrefError::MissingColumn("cusip"))
+        ));
+    }
+}
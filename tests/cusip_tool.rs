@@ -0,0 +1,1028 @@
+#![cfg(feature = "cli")]
+//! Runs the `cusip-tool` binary on small inputs, for the behavior that depends on how its options
+//! combine.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Runs `cusip-tool` with the arguments, writing the input to its standard input.
+fn run(args: &[&str], input: impl AsRef<[u8]>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cusip-tool"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_ref())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// A path in the temporary directory that no other test uses.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cusip-tool-{}-{name}", std::process::id()))
+}
+
+/// Writes the contents to a file at `temp_path(name)`, returning its path.
+fn write_temp(name: &str, contents: &str) -> PathBuf {
+    let path = temp_path(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn validates_standard_input_by_default() {
+    let output = run(&[], "037833100\n09739D100\r\n");
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Read 2 values; 2 were valid CUSIPs and 0 were not."),
+        "{stderr}"
+    );
+
+    let output = run(&[], "037833100\n037833101\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 2: \"037833101\""), "{stderr}");
+}
+
+#[test]
+fn jsonl_reports_failures_to_files() {
+    let errors = temp_path("jsonl-errors.txt");
+    let quarantine = temp_path("jsonl-quarantine.csv");
+    let output = run(
+        &[
+            "validate",
+            "--output",
+            "jsonl",
+            "--errors-to",
+            errors.to_str().unwrap(),
+            "--quarantine",
+            quarantine.to_str().unwrap(),
+        ],
+        "037833100\n037833101\n09739D100\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 3);
+
+    let errors_text = std::fs::read_to_string(&errors).unwrap();
+    let quarantine_text = std::fs::read_to_string(&quarantine).unwrap();
+    std::fs::remove_file(&errors).unwrap();
+    std::fs::remove_file(&quarantine).unwrap();
+    assert_eq!(errors_text.lines().count(), 1);
+    assert!(errors_text.contains("\"037833101\""), "{errors_text}");
+    assert_eq!(quarantine_text.lines().count(), 1);
+    assert!(
+        quarantine_text.starts_with("037833101,"),
+        "{quarantine_text}"
+    );
+}
+
+#[test]
+fn jsonl_stops_at_max_errors() {
+    let output = run(
+        &["validate", "--output", "jsonl", "--fail-fast"],
+        "037833101\n09739D100\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("\"037833101\""), "{stdout}");
+
+    let output = run(
+        &["validate", "--output", "jsonl", "--max-errors", "1"],
+        "037833101\n09739D100\n037833102\n037833100\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 3);
+}
+
+#[test]
+fn jsonl_does_not_repeat_failures_as_text() {
+    let output = run(&["validate", "--output", "jsonl"], "037833101\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("\"037833101\""), "{stderr}");
+}
+
+#[test]
+fn fix_flag_runs_fix() {
+    let output = run(&["--fix"], "037833101\n09739D100\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n"
+    );
+
+    let output = run(&["--fix", "validate"], "037833100\n");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn stops_quietly_when_output_is_closed() {
+    for args in [&["gen", "-n", "100000"][..], &["completions", "bash"]] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_cusip-tool"))
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let output = child.wait_with_output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!stderr.contains("panicked"), "{stderr}");
+        assert_ne!(output.status.code(), Some(101));
+    }
+}
+
+#[test]
+fn reporting_options_apply_only_where_values_fail() {
+    let errors = temp_path("stats-errors.txt");
+    let output = run(
+        &[
+            "stats",
+            "--max-errors",
+            "0",
+            "--errors-to",
+            errors.to_str().unwrap(),
+        ],
+        "037833101\n037833100\n",
+    );
+    let errors_text = std::fs::read_to_string(&errors).unwrap();
+    std::fs::remove_file(&errors).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        errors_text.starts_with("Line 1: \"037833101\""),
+        "{errors_text}"
+    );
+
+    for args in [&["gen", "--fail-fast"][..], &["scan", "--max-errors", "1"]] {
+        let output = run(args, "");
+        assert_eq!(output.status.code(), Some(2));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("does not apply"), "{stderr}");
+    }
+}
+
+#[test]
+fn loose_accepts_whitespace_and_lowercase() {
+    let output = run(&["validate", "--loose"], " 037833100 \n09739d100\n");
+    assert_eq!(output.status.code(), Some(0));
+
+    let output = run(&["fix", "--loose"], " 037833100 \n09739d100\n");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n"
+    );
+
+    let output = run(&["validate"], " 037833100 \n09739d100\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("0 were valid CUSIPs and 2 were not"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn jsonl_writes_a_report_per_value() {
+    let output = run(&["validate", "--output", "jsonl"], "037833100\n037833101\n");
+    assert_eq!(output.status.code(), Some(1));
+    let rows: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["valid"], true);
+    assert_eq!(rows[0]["issuer_num"], "037833");
+    assert_eq!(rows[1]["valid"], false);
+    assert_eq!(rows[1]["kind"], "IncorrectCheckDigit");
+    assert_eq!(rows[1]["field"], "check_digit");
+}
+
+#[test]
+fn csv_writes_the_parsed_fields() {
+    let output = run(&["validate", "--output", "csv"], "037833100\nS08000AA9\n");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(
+        lines[0].starts_with("cusip,issuer_num,issue_num,check_digit,is_cins,"),
+        "{stdout}"
+    );
+    assert_eq!(
+        lines[1],
+        "037833100,037833,10,0,false,,North America,false,false,false"
+    );
+    assert_eq!(
+        lines[2],
+        "S08000AA9,S08000,AA,9,true,S,South Africa,false,false,false"
+    );
+}
+
+#[test]
+fn build_computes_check_digits() {
+    for args in [&["build", "03783310"][..], &["build", "037833", "10"]] {
+        let output = run(args, "");
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "037833100\n");
+    }
+
+    let output = run(&["build"], "03783310\n59491810\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n594918104\n"
+    );
+
+    let output = run(&["build", "0378331"], "");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid Payload length 7 bytes"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn stats_summarizes_values() {
+    let output = run(&["stats"], "037833100\n037833101\nS08000AA9\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("Read 3 values: 2 valid, 1 invalid\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\n  IncorrectCheckDigit  1\n"), "{stdout}");
+    assert!(stdout.contains("\n  S South Africa "), "{stdout}");
+}
+
+#[test]
+fn sort_writes_distinct_values_in_order() {
+    // A chunk size of 2 makes the sort spill to several temporary files
+    let temp_dir = temp_path("sort");
+    std::fs::create_dir(&temp_dir).unwrap();
+    let output = run(
+        &[
+            "sort",
+            "--chunk-size",
+            "2",
+            "--temp-dir",
+            temp_dir.to_str().unwrap(),
+        ],
+        "594918104\n037833100\n594918104\nbad\nS08000AA9\n09739D100\n",
+    );
+    std::fs::remove_dir_all(&temp_dir).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n594918104\nS08000AA9\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("5 were valid CUSIPs (4 distinct)"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn convert_between_cusips_and_isins() {
+    let output = run(&["convert", "--to-isin", "US"], "037833100\n09739D100\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "US0378331005\nUS09739D1000\n"
+    );
+
+    let output = run(
+        &["convert", "--from-isin"],
+        "US0378331005\nUS0378331006\nGB0002634946\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "037833100\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 2: \"US0378331006\""), "{stderr}");
+    assert!(stderr.contains("Line 3: \"GB0002634946\""), "{stderr}");
+
+    let output = run(&["convert", "--to-isin", "us"], "037833100\n");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn filter_keeps_matching_values() {
+    let input = "037833100\nS08000AA9\n594918104\n912828U24\n";
+    let output = run(&["filter", "--cins-only"], input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "S08000AA9\n");
+
+    let output = run(
+        &["filter", "--issue-kind", "equity", "--issuer-prefix", "5"],
+        input,
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "594918104\n");
+
+    let output = run(&["filter", "--issue-kind", "fixed-income"], input);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "S08000AA9\n912828U24\n"
+    );
+
+    let output = run(&["filter", "--cins-only"], "S08000AA9\nbad\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "S08000AA9\n");
+}
+
+#[test]
+fn jobs_keep_input_order_and_line_numbers() {
+    let mut input = "header\n".to_string();
+    for n in 0..100 {
+        let cusip = cusip::build_from_parts(&format!("{n:06}"), "10").unwrap();
+        let value = cusip.to_string();
+        if n == 39 {
+            // Line 41 gets a Check Digit that fix cannot fix
+            input.push_str(&value[..8]);
+            input.push_str("X\n");
+        } else {
+            input.push_str(&value);
+            input.push('\n');
+        }
+    }
+
+    let one = run(&["fix", "--skip", "1", "--limit", "60"], &input);
+    for jobs in ["2", "4", "7"] {
+        let many = run(
+            &["fix", "--jobs", jobs, "--skip", "1", "--limit", "60"],
+            &input,
+        );
+        assert_eq!(many.status.code(), one.status.code());
+        assert_eq!(many.stdout, one.stdout);
+        assert_eq!(many.stderr, one.stderr);
+    }
+    assert_eq!(String::from_utf8(one.stdout).unwrap().lines().count(), 59);
+    let stderr = String::from_utf8(one.stderr).unwrap();
+    assert!(stderr.contains("Line 41: "), "{stderr}");
+    assert!(stderr.contains("Read 60 values"), "{stderr}");
+}
+
+#[test]
+fn reads_files_in_order() {
+    let first = write_temp("files-1.txt", "037833100\n");
+    let second = write_temp("files-2.txt", "037833101\n");
+    let output = run(
+        &[
+            "fix",
+            first.to_str().unwrap(),
+            "-",
+            second.to_str().unwrap(),
+        ],
+        "594918104\n",
+    );
+    std::fs::remove_file(&first).unwrap();
+    std::fs::remove_file(&second).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n594918104\n037833100\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("{}: Read 1 values", second.display())),
+        "{stderr}"
+    );
+
+    let missing = temp_path("files-missing.txt");
+    let output = run(&["validate", missing.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Error opening "), "{stderr}");
+}
+
+#[test]
+fn annotate_adds_columns() {
+    let output = run(
+        &["annotate", "--column", "cusip"],
+        "name,cusip\nApple,037833100\nBad,037833101\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "name,cusip,cusip_valid,cusip_normalized,cusip_format,cusip_country,cusip_private_use,\
+         cusip_error\n\
+         Apple,037833100,true,037833100,domestic,North America,false,\n\
+         Bad,037833101,false,,,,,incorrect Check Digit '1' when expecting '0'\n"
+    );
+
+    let output = run(&["annotate", "--column", "id"], "name,cusip\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No column named \"id\""), "{stderr}");
+}
+
+#[test]
+fn quiet_and_verbose_levels() {
+    let input = "037833100\n037833101\n";
+    let output = run(&["validate", "-q"], input);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("Line 2"), "{stderr}");
+    assert!(stderr.contains("Read 2 values"), "{stderr}");
+
+    let output = run(&["validate", "-qq"], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+
+    let output = run(&["fix", "-v"], input);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Line 2: fixed \"037833101\" as 037833100"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn errors_to_splits_good_and_bad_values() {
+    let errors = temp_path("errors-to.txt");
+    let output = run(
+        &["validate", "--errors-to", errors.to_str().unwrap()],
+        "037833100\n037833101\n09739D100\n",
+    );
+    let errors_text = std::fs::read_to_string(&errors).unwrap();
+    std::fs::remove_file(&errors).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n"
+    );
+    assert_eq!(
+        errors_text,
+        "Line 2: \"037833101\": incorrect Check Digit '1' when expecting '0'\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("Line 2"), "{stderr}");
+}
+
+#[test]
+fn scan_finds_cusips_in_text() {
+    let output = run(
+        &["scan"],
+        "Buy 037833100 and G0450A105, not 037833101.\nnothing here\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1:5:037833100\n1:19:G0450A105\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Scanned 2 lines; found 2 CUSIPs."),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn follow_reports_lines_as_they_are_appended() {
+    let path = write_temp("follow.txt", "037833101\n");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cusip-tool"))
+        .args(["validate", "--follow", path.to_str().unwrap()])
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    let (send, lines) = mpsc::channel();
+    std::thread::spawn(move || {
+        stderr
+            .lines()
+            .map_while(Result::ok)
+            .try_for_each(|line| send.send(line))
+    });
+    let next_line = || lines.recv_timeout(Duration::from_secs(10));
+
+    let first = next_line();
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"594918104\n09739D1").unwrap();
+    file.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    // The last line is only checked once it is complete
+    file.write_all(b"01\n").unwrap();
+    let second = next_line();
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let first = first.unwrap();
+    assert!(
+        first.ends_with("Line 1: \"037833101\": incorrect Check Digit '1' when expecting '0'"),
+        "{first}"
+    );
+    let second = second.unwrap();
+    assert!(
+        second.ends_with("Line 3: \"09739D101\": incorrect Check Digit '1' when expecting '0'"),
+        "{second}"
+    );
+}
+
+#[test]
+fn explain_describes_each_value() {
+    let output = run(&["explain", "037833100", "037833101", "0378331"], "");
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let sections: Vec<&str> = stdout.split("\n\n").collect();
+    assert_eq!(sections.len(), 3, "{stdout}");
+    assert!(
+        sections[0].starts_with("\"037833100\" is a valid CUSIP\n"),
+        "{stdout}"
+    );
+    assert!(
+        sections[0].contains("\n  Issuer Number      037833\n"),
+        "{stdout}"
+    );
+    assert!(
+        sections[1].contains("\n  Check Digit        1 (incorrect: expecting 0)\n"),
+        "{stdout}"
+    );
+    assert_eq!(
+        sections[2],
+        "\"0378331\" is not a valid CUSIP: invalid CUSIP length 7 bytes when expecting 9\n"
+    );
+}
+
+#[test]
+fn build_from_csv_columns() {
+    let output = run(
+        &["build", "--column", "payload"],
+        "name,payload\nApple,03783310\nBad,0378331\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "037833100\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 3: \"0378331\""), "{stderr}");
+
+    let output = run(
+        &[
+            "build",
+            "--issuer-column",
+            "issuer",
+            "--issue-column",
+            "issue",
+        ],
+        "issue,issuer\n10,037833\n10,594918\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n594918104\n"
+    );
+}
+
+#[test]
+fn grep_matches_wildcard_patterns() {
+    let input = "037833100\n037833209\n594918104\nG0450A105\n";
+    let output = run(&["grep", "0378*"], input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n037833209\n"
+    );
+
+    let output = run(&["grep", "?####A###"], input);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "G0450A105\n");
+
+    let output = run(&["grep", "0378*"], "037833100\nbad\n");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn completions_for_each_shell() {
+    for shell in ["bash", "elvish", "fish", "powershell", "zsh"] {
+        let output = run(&["completions", shell], "");
+        assert_eq!(output.status.code(), Some(0), "{shell}");
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("check-isins"), "{shell}: {stdout}");
+    }
+
+    let output = run(&["completions", "tcsh"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Sends a raw HTTP request to the server and returns the whole response.
+fn http(addr: &str, request: &str) -> String {
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+    response
+}
+
+#[test]
+fn serve_answers_validation_requests() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cusip-tool"))
+        .args(["serve", "--bind", "127.0.0.1:0", "--loose"])
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let addr = line.trim_end().trim_start_matches("Listening on http://");
+
+    let get = http(addr, "GET /validate/%2009739d100 HTTP/1.1\r\n\r\n");
+    let malformed = http(addr, "GET /validate/%2 HTTP/1.1\r\n\r\n");
+    let body = r#"["037833100","037833101"]"#;
+    let post = http(
+        addr,
+        &format!(
+            "POST /validate HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ),
+    );
+    let missing = http(addr, "GET /other HTTP/1.1\r\n\r\n");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(get.starts_with("HTTP/1.1 200 OK\r\n"), "{get}");
+    assert!(get.contains(r#""normalized":"09739D100""#), "{get}");
+    assert!(malformed.starts_with("HTTP/1.1 400 "), "{malformed}");
+    assert!(post.starts_with("HTTP/1.1 200 OK\r\n"), "{post}");
+    let (_, body) = post.split_once("\r\n\r\n").unwrap();
+    let reports: Vec<serde_json::Value> = serde_json::from_str(body).unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0]["valid"], true);
+    assert_eq!(reports[1]["valid"], false);
+    assert!(missing.starts_with("HTTP/1.1 404 "), "{missing}");
+}
+
+#[test]
+fn fixed_width_fields_in_ebcdic() {
+    // Code page 037 has the digits at 0xF0 to 0xF9 and the capital letters A to I at 0xC1 to 0xC9
+    let ebcdic = |text: &str| -> Vec<u8> {
+        text.bytes()
+            .map(|b| match b {
+                b'0'..=b'9' => b - b'0' + 0xF0,
+                b'A'..=b'I' => b - b'A' + 0xC1,
+                _ => b,
+            })
+            .collect()
+    };
+    let mut input = ebcdic("AB037833100");
+    input.push(b'\n');
+    input.extend(ebcdic("CD09739D100"));
+    input.push(b'\n');
+    let output = run(
+        &["fix", "--fixed-width", "--offset", "2", "--ebcdic"],
+        &input,
+    );
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n"
+    );
+
+    let output = run(
+        &["fix", "--fixed-width", "--offset", "6", "--length", "8"],
+        "AAPL  03783310 X\nMSFT  59491810 Y\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 1: \"03783310\""), "{stderr}");
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn reads_parquet_columns() {
+    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let ids: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3]));
+    let cusips: ArrayRef = Arc::new(StringArray::from(vec![
+        "037833100",
+        "037833101",
+        "09739D100",
+    ]));
+    let batch = RecordBatch::try_from_iter([("id", ids), ("cusip", cusips)]).unwrap();
+    let path = temp_path("positions.parquet");
+    let file = std::fs::File::create(&path).unwrap();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+    writer.write(&batch).unwrap();
+    writer.close().unwrap();
+
+    let output = run(
+        &[
+            "validate",
+            "--output",
+            "csv",
+            "--format",
+            "parquet",
+            "--column",
+            "cusip",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    let missing = run(
+        &[
+            "validate",
+            "--format",
+            "parquet",
+            "--column",
+            "CUSIP",
+            path.to_str().unwrap(),
+        ],
+        "",
+    );
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let cusips: Vec<&str> = stdout.lines().skip(1).map(|row| &row[..9]).collect();
+    assert_eq!(cusips, ["037833100", "09739D100"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 2: \"037833101\""), "{stderr}");
+    assert_eq!(missing.status.code(), Some(1));
+    let stderr = String::from_utf8(missing.stderr).unwrap();
+    assert!(stderr.contains("No column named \"CUSIP\""), "{stderr}");
+}
+
+#[test]
+fn top_issuers_table() {
+    let names = write_temp("top-issuers.csv", "issuer_num,name\n037833,Apple Inc\n");
+    let output = run(
+        &["top-issuers", "-n", "2", "--names", names.to_str().unwrap()],
+        "037833100\n037833209\n594918104\n09739D100\n037833101\n",
+    );
+    std::fs::remove_file(&names).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Issuer  Count   Share  Name\n\
+         037833      2  50.00%  Apple Inc\n\
+         09739D      1  25.00%\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Read 4 valid values from 3 Issuers."),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn join_writes_matching_metadata_rows() {
+    let metadata = write_temp(
+        "join.csv",
+        "name,cusip\nApple,037833100\nMicrosoft,594918104\nApple again,037833100\n",
+    );
+    let output = run(
+        &[
+            "join",
+            "--metadata",
+            metadata.to_str().unwrap(),
+            "--column",
+            "cusip",
+        ],
+        "037833100\n09739D100\n",
+    );
+    std::fs::remove_file(&metadata).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "name,cusip\nApple,037833100\nApple again,037833100\n"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Line 2: \"09739D100\": not found in "),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn merge_sorted_files() {
+    let first = write_temp("merge-1.txt", "037833100\n594918104\n");
+    let second = write_temp("merge-2.txt", "09739D100\n594918104\nS08000AA9\n");
+    let unsorted = write_temp("merge-3.txt", "594918104\n037833100\n");
+    let merged = run(
+        &["merge", first.to_str().unwrap(), second.to_str().unwrap()],
+        "",
+    );
+    let out_of_order = run(
+        &["merge", first.to_str().unwrap(), unsorted.to_str().unwrap()],
+        "",
+    );
+    for path in [&first, &second, &unsorted] {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    assert_eq!(merged.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(merged.stdout).unwrap(),
+        "037833100\n09739D100\n594918104\nS08000AA9\n"
+    );
+    assert_eq!(out_of_order.status.code(), Some(1));
+    let stderr = String::from_utf8(out_of_order.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!(
+            "{}: Line 2: \"037833100\": out of order after 594918104",
+            unsorted.display()
+        )),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn normalize_passes_invalid_values_through() {
+    let input = " 037833100\n09739d100\nbad\n";
+    let output = run(&["normalize"], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\nbad\n"
+    );
+
+    let output = run(&["normalize", "--drop-invalid"], input);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n09739D100\n"
+    );
+}
+
+#[test]
+fn split_by_partition_key() {
+    let cusips: Vec<cusip::CUSIP> = (0..100)
+        .map(|n| cusip::build_from_parts(&format!("{n:06}"), "10").unwrap())
+        .collect();
+    let input: String = cusips.iter().map(|cusip| format!("{cusip}\n")).collect();
+    let dir = temp_path("split");
+    std::fs::create_dir(&dir).unwrap();
+    let prefix = dir.join("shard-");
+    let output = run(
+        &[
+            "split",
+            "--shards",
+            "3",
+            "--prefix",
+            prefix.to_str().unwrap(),
+        ],
+        input,
+    );
+    let shards: Vec<String> = (0..3)
+        .map(|i| std::fs::read_to_string(dir.join(format!("shard-{i}.txt"))).unwrap())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Wrote 100 values to 3 shards."), "{stderr}");
+    for cusip in &cusips {
+        let shard = &shards[cusip.partition_key(3) as usize];
+        assert!(
+            shard.lines().any(|line| line == cusip.to_string()),
+            "{cusip}"
+        );
+    }
+    assert_eq!(shards.iter().map(|s| s.lines().count()).sum::<usize>(), 100);
+}
+
+#[test]
+fn limit_applies_to_each_file() {
+    let file = write_temp("limit.txt", "037833100\n594918104\n037833101\n");
+    let output = run(
+        &["fix", "--limit", "2", file.to_str().unwrap(), "-"],
+        "09739D100\nS08000AA9\nbad\n",
+    );
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "037833100\n594918104\n09739D100\nS08000AA9\n"
+    );
+}
+
+#[test]
+fn check_isins_reports_inconsistent_cusips() {
+    let output = run(
+        &["check-isins"],
+        "US0378331005\nUS0378331006\nGB0002634946\nUS0378331013\nxx\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for failure in [
+        "Line 2: \"US0378331006\": incorrect ISIN Check Digit '6' when expecting '5'",
+        "Line 4: \"US0378331013\": embedded CUSIP \"037833101\" is invalid: incorrect Check Digit \
+         '1' when expecting '0'",
+        "Line 5: \"xx\": not 2 uppercase ASCII letters",
+    ] {
+        assert!(stderr.contains(failure), "{stderr}");
+    }
+    assert!(!stderr.contains("Line 3"), "{stderr}");
+    assert!(
+        stderr.contains(
+            "1 were CUSIP-based and consistent, 1 were not CUSIP-based, and 3 were not valid"
+        ),
+        "{stderr}"
+    );
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn reads_xlsx_columns() {
+    // The Positions sheet holds Apple's CUSIP a second time as a number, which has lost its
+    // leading zero
+    let workbook = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/positions.xlsx");
+    let output = run(
+        &[
+            "validate",
+            "--output",
+            "csv",
+            "--format",
+            "xlsx",
+            "--sheet",
+            "Positions",
+            "--column",
+            "CUSIP",
+            workbook,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let cusips: Vec<&str> = stdout.lines().skip(1).map(|row| &row[..9]).collect();
+    assert_eq!(cusips, ["037833100", "594918104", "09739D100"]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 4: \"37833100\""), "{stderr}");
+
+    // The first sheet has no such column
+    let output = run(
+        &[
+            "validate", "--format", "xlsx", "--column", "CUSIP", workbook,
+        ],
+        "",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No column named \"CUSIP\""), "{stderr}");
+}
+
+#[test]
+fn convert_to_payloads() {
+    let output = run(
+        &["convert", "--to-payload"],
+        "037833100\n09739D100\n037833101\n",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "03783310\n09739D10\n"
+    );
+}
+
+#[test]
+fn annotate_detects_the_column() {
+    let output = run(
+        &["annotate", "--detect-column", "-d", "tab", "-v"],
+        "id\tname\tident\n1\tApple\t037833100\n2\tMicrosoft\t594918104\n",
+    );
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(
+        lines[0].starts_with("id\tname\tident\tcusip_valid\t"),
+        "{stdout}"
+    );
+    assert!(
+        lines[1].starts_with("1\tApple\t037833100\ttrue\t037833100\t"),
+        "{stdout}"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Using column 3 (\"ident\")"), "{stderr}");
+
+    let output = run(&["annotate", "--detect-column"], "id,name\n1,Apple\n");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No column holds a valid CUSIP"), "{stderr}");
+}
+
+#[test]
+fn nul_separated_records() {
+    let output = run(
+        &["fix", "--record-delimiter", "nul"],
+        "037833101\0 09739D100\n\0",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "037833100\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Line 2: \" 09739D100\\n\""), "{stderr}");
+}